@@ -1,23 +1,30 @@
+use bytes::Bytes;
 use criterion::async_executor::FuturesExecutor;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use indexmap::IndexMap;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
-use tokio::sync::RwLock;
+use zyst::client::Client;
 use zyst::process::process_command;
+use zyst::state::State;
 
 fn benchmark_process_command(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
-    let db = Arc::new(RwLock::new(IndexMap::new()));
+    let state = Arc::new(State::new(1));
 
     for &size in &[1, 10, 100, 1000, 10_000] {
         c.bench_with_input(BenchmarkId::new("set_command", size), &size, |b, &size| {
             b.to_async(FuturesExecutor).iter(|| {
-                let db = db.clone();
+                let state = state.clone();
                 rt.spawn(async move {
                     for i in 0..size {
-                        let command = vec!["SET".to_string(), i.to_string(), i.to_string()];
-                        let _ = process_command(command.clone(), &db, true).await;
+                        let command = vec![
+                            Bytes::from("SET"),
+                            Bytes::from(i.to_string()),
+                            Bytes::from(i.to_string()),
+                        ];
+                        let _ =
+                            process_command(command.clone(), &state, &mut Client::new(), true)
+                                .await;
                     }
                 })
             });
@@ -25,11 +32,13 @@ fn benchmark_process_command(c: &mut Criterion) {
 
         c.bench_with_input(BenchmarkId::new("get_command", size), &size, |b, &size| {
             b.to_async(FuturesExecutor).iter(|| {
-                let db = db.clone();
+                let state = state.clone();
                 rt.spawn(async move {
                     for i in 0..size {
-                        let command = vec!["GET".to_string(), i.to_string()];
-                        let _ = process_command(command.clone(), &db, true).await;
+                        let command = vec![Bytes::from("GET"), Bytes::from(i.to_string())];
+                        let _ =
+                            process_command(command.clone(), &state, &mut Client::new(), true)
+                                .await;
                     }
                 })
             });
@@ -37,11 +46,13 @@ fn benchmark_process_command(c: &mut Criterion) {
 
         c.bench_with_input(BenchmarkId::new("del_command", size), &size, |b, &size| {
             b.to_async(FuturesExecutor).iter(|| {
-                let db = db.clone();
+                let state = state.clone();
                 rt.spawn(async move {
                     for i in 0..size {
-                        let command = vec!["DEL".to_string(), i.to_string()];
-                        let _ = process_command(command.clone(), &db, true).await;
+                        let command = vec![Bytes::from("DEL"), Bytes::from(i.to_string())];
+                        let _ =
+                            process_command(command.clone(), &state, &mut Client::new(), true)
+                                .await;
                     }
                 })
             });
@@ -52,10 +63,12 @@ fn benchmark_process_command(c: &mut Criterion) {
             &size,
             |b, &_size| {
                 b.to_async(FuturesExecutor).iter(|| {
-                    let db = db.clone();
+                    let state = state.clone();
                     rt.spawn(async move {
-                        let command = vec!["KEYS *".to_string()];
-                        let _ = process_command(command.clone(), &db, true).await;
+                        let command = vec![Bytes::from("KEYS *")];
+                        let _ =
+                            process_command(command.clone(), &state, &mut Client::new(), true)
+                                .await;
                     })
                 });
             },
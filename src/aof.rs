@@ -1,10 +1,18 @@
-use crate::types::{Command, CommandArgs, CommandType, Db, DbValue};
+use crate::commands::hashsets::hpexpireat;
+use crate::keyspace::Keyspace;
+use crate::resp::encode_command;
+use crate::state::State;
+use crate::types::DbValue;
+use bytes::{Bytes, BytesMut};
 use dirs::home_dir;
+use indexmap::IndexMap;
 use std::io::Error;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::fs;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
 use tokio::time::{self, Duration};
 use tracing::info;
 
@@ -26,19 +34,37 @@ pub fn get_aof_file() -> PathBuf {
     log_path.join("appendonly.aof")
 }
 
-pub async fn write_aof(command: &Command) -> std::io::Result<()> {
-    if is_read_command(command.command_type.clone()) {
-        return Ok(());
-    }
+/// Database the commands appended to the AOF apply to, None when the next
+/// command needs a SELECT. Holding the lock also keeps appends and
+/// rewrites from interleaving.
+static AOF_SELECTED_DB: Mutex<Option<usize>> = Mutex::const_new(None);
+
+fn encode_select(output: &mut BytesMut, db_index: usize) {
+    let index = db_index.to_string();
+    encode_command(output, &[&b"SELECT"[..], index.as_bytes()]);
+}
 
+/// Appends commands, with the index of the database each applies to, to the
+/// AOF file in RESP format. A SELECT precedes a command applying to another
+/// database than the previous one. The commands are written at once, so a
+/// transaction can't be cut in the middle by another client's commands.
+/// Only commands flagged as `write` in the command table are logged.
+pub async fn write_aof(commands: &[(usize, Vec<Bytes>)]) -> std::io::Result<()> {
+    let mut selected_db = AOF_SELECTED_DB.lock().await;
     let log_path = get_aof_log_dir();
 
     if !log_path.exists() {
         fs::create_dir_all(&log_path).await?;
     }
 
-    let keys_value = format_command_args(&command.args, command.command_type.clone());
-    let formatted = format!("{:?} {}\n", command.command_type, keys_value);
+    let mut formatted = BytesMut::new();
+    for (db_index, argv) in commands {
+        if *selected_db != Some(*db_index) {
+            encode_select(&mut formatted, *db_index);
+            *selected_db = Some(*db_index);
+        }
+        encode_command(&mut formatted, argv);
+    }
 
     let file_path = log_path.join("appendonly.aof");
 
@@ -48,44 +74,80 @@ pub async fn write_aof(command: &Command) -> std::io::Result<()> {
         .open(&file_path)
         .await?;
 
-    file.write_all(formatted.as_bytes()).await?;
+    if let Err(e) = file.write_all(&formatted).await {
+        // Nothing is known of the database the file ends with
+        *selected_db = None;
+        return Err(e);
+    }
     Ok(())
 }
 
-fn is_read_command(cmd_type: CommandType) -> bool {
-    matches!(
-        cmd_type,
-        CommandType::GET
-            | CommandType::KEYS
-            | CommandType::EXISTS
-            | CommandType::TTL
-            | CommandType::HGET
-            | CommandType::HGETALL
-            | CommandType::LRANGE
-    )
-}
+fn dump_keyspace(output: &mut BytesMut, keyspace: &Keyspace) {
+    for (key, value) in keyspace.iter() {
+        match value {
+            DbValue::StringKey(k) => {
+                if let Some(val) = &k.data {
+                    let mut args = vec![&b"SET"[..], &key[..], &val[..]];
+                    let expires_at = k.expires_at.map(|ms| ms.to_string());
+                    if let Some(expires_at) = &expires_at {
+                        args.extend([&b"PXAT"[..], expires_at.as_bytes()]);
+                    }
+                    encode_command(output, &args);
+                }
+            }
+            DbValue::ListKey(l) => {
+                // RPUSH keeps the elements in their current order
+                let mut args = vec![&b"RPUSH"[..], &key[..]];
+                args.extend(l.data.iter().map(|value| &value[..]));
+                encode_command(output, &args);
+            }
+            DbValue::SetKey(s) => {
+                let mut args = vec![&b"SADD"[..], &key[..]];
+                args.extend(s.data.iter().map(|value| &value[..]));
+                encode_command(output, &args);
+            }
+            DbValue::HashKey(hash_key) => {
+                let mut args = vec![&b"HSET"[..], &key[..]];
+                for (field, value) in hash_key.data.iter() {
+                    args.push(&field[..]);
+                    args.push(&value[..]);
+                }
+                encode_command(output, &args);
+
+                // Fields expiring at the same time share an HPEXPIREAT
+                let mut expiring: IndexMap<i64, Vec<Bytes>> = IndexMap::new();
+                for field in hash_key.data.keys() {
+                    if let Some(expires_at) = hash_key.data.expires_at(field) {
+                        expiring.entry(expires_at).or_default().push(field.clone());
+                    }
+                }
+                for (expires_at, fields) in expiring {
+                    let command = hpexpireat(key.clone(), expires_at, fields);
+                    let args: Vec<&[u8]> = command.iter().map(|arg| &arg[..]).collect();
+                    encode_command(output, &args);
+                }
+            }
+        }
 
-fn format_command_args(args: &CommandArgs, cmd_type: CommandType) -> String {
-    match args {
-        CommandArgs::NoArgs => format!("{cmd_type:?}"),
-        CommandArgs::SingleKey(key) => key.clone(),
-        CommandArgs::MultipleKeys(keys) => keys.join(" "),
-        CommandArgs::KeyWithValue { key, value } => format!("{key} {value}"),
-        CommandArgs::KeyWithValues { key, values } => format!("{} {}", key, values.join(" ")),
-        CommandArgs::HashFields { key, fields } => format!(
-            "{} {}",
-            key,
-            fields
-                .iter()
-                .map(|(field, value)| format!("{field} {value}"))
-                .collect::<Vec<_>>()
-                .join(" ")
-        ),
+        // Strings carry their expiry in the SET above
+        if let Some(expires_at) = value
+            .expires_at()
+            .filter(|_| !matches!(value, DbValue::StringKey(_)))
+        {
+            let expires_at = expires_at.to_string();
+            encode_command(
+                output,
+                &[&b"PEXPIREAT"[..], &key[..], expires_at.as_bytes()],
+            );
+        }
     }
 }
 
-async fn dump_db_to_aof(db: &Db) -> Result<(), Error> {
-    let db_write = db.write().await;
+async fn dump_db_to_aof(state: &State) -> Result<(), Error> {
+    // Commands in flight are already applied but not logged yet, the
+    // rewrite waits for them so they aren't replayed twice
+    let _gate = state.command_gate().write().await;
+    let mut selected_db = AOF_SELECTED_DB.lock().await;
     let db_dump_aof = get_aof_log_dir().join("db-dump.aof");
     let aof_file = get_aof_file();
 
@@ -96,41 +158,23 @@ async fn dump_db_to_aof(db: &Db) -> Result<(), Error> {
         .open(&db_dump_aof)
         .await?;
 
-    let mut output = String::new();
+    let mut output = BytesMut::new();
 
-    for (key, value) in db_write.iter() {
-        match value {
-            DbValue::StringKey(k) => {
-                if let Some(val) = &k.data {
-                    output.push_str(&format!("SET {key} {val}\n"));
-                }
-            }
-            DbValue::ListKey(l) => {
-                let values = l.data.iter().cloned().collect::<Vec<String>>().join(" ");
-                output.push_str(&format!("LPUSH {key} {values}\n"));
-            }
-            DbValue::SetKey(s) => {
-                let values = s
-                    .data
-                    .iter()
-                    .map(|value| value.to_string())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                output.push_str(&format!("SADD {} {}\n", s.name, values));
-            }
-            DbValue::HashKey(hash_key) => {
-                let fields = hash_key
-                    .data
-                    .iter()
-                    .map(|(field, value)| format!("{field} {value}"))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                output.push_str(&format!("HSET {} {}\n", hash_key.name, fields));
-            }
+    // Every database is locked at once, so keys moved between databases
+    // meanwhile are dumped exactly once
+    let mut keyspaces = Vec::new();
+    for db in state.databases() {
+        keyspaces.push(db.read().await);
+    }
+
+    for (index, keyspace) in keyspaces.iter().enumerate() {
+        if !keyspace.is_empty() {
+            encode_select(&mut output, index);
+            dump_keyspace(&mut output, keyspace);
         }
     }
 
-    file.write_all(output.as_bytes()).await?;
+    file.write_all(&output).await?;
 
     // Ensure all data is written
     file.flush().await?;
@@ -146,15 +190,16 @@ async fn dump_db_to_aof(db: &Db) -> Result<(), Error> {
         let _ = fs::remove_file(&db_dump_aof).await;
     }
 
+    *selected_db = None;
     Ok(())
 }
 
-pub async fn clean_up_db(db: Db) {
+pub async fn clean_up_db(state: Arc<State>) {
     let mut interval = time::interval(Duration::from_secs(60));
 
     loop {
         interval.tick().await;
         info!("Cleaning up Database");
-        let _ = dump_db_to_aof(&db).await;
+        let _ = dump_db_to_aof(&state).await;
     }
 }
@@ -0,0 +1,199 @@
+use crate::client::{lock, Client};
+use crate::errors::ZystError;
+use crate::response::ZystResponse;
+use crate::state::State;
+use crate::types::Command;
+use bytes::Bytes;
+use indexmap::{IndexMap, IndexSet};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+use tokio::time::{self, Instant};
+
+pub type BlockedReply = Result<ZystResponse, ZystError>;
+
+/// Reply a client blocked by a command waits for, sent by the command that
+/// served it or by CLIENT UNBLOCK
+#[derive(Debug)]
+pub struct Blocked {
+    pub reply: oneshot::Receiver<BlockedReply>,
+    /// None to wait forever
+    pub deadline: Option<Instant>,
+}
+
+/// Command a blocked client runs again once one of its keys is ready
+#[derive(Debug)]
+pub struct BlockedCommand {
+    pub db: usize,
+    pub keys: Vec<Bytes>,
+    pub command: Command,
+    /// Reply when the timeout expires first
+    pub timeout_reply: ZystResponse,
+    reply: oneshot::Sender<BlockedReply>,
+}
+
+impl BlockedCommand {
+    /// Unblocks the client with the reply of its command
+    pub fn reply(self, reply: BlockedReply) {
+        // The client may have disconnected in the meantime
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// How CLIENT UNBLOCK interrupts a blocked client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnblockReason {
+    /// As if its timeout expired
+    Timeout,
+    /// With an UNBLOCKED error
+    Error,
+}
+
+#[derive(Debug, Default)]
+struct BlockedClients {
+    /// Blocked clients by id
+    clients: IndexMap<u64, BlockedCommand>,
+    /// Ids of the clients blocked on each key of each database, in the
+    /// order they blocked
+    keys: IndexMap<(usize, Bytes), VecDeque<u64>>,
+    /// Keys that got elements while clients were blocked on them
+    ready: IndexSet<(usize, Bytes)>,
+}
+
+impl BlockedClients {
+    fn remove(&mut self, id: u64) -> Option<BlockedCommand> {
+        let blocked = self.clients.shift_remove(&id)?;
+
+        for key in &blocked.keys {
+            let entry = (blocked.db, key.clone());
+            if let Some(waiting) = self.keys.get_mut(&entry) {
+                waiting.retain(|waiting_id| *waiting_id != id);
+                if waiting.is_empty() {
+                    self.keys.shift_remove(&entry);
+                }
+            }
+        }
+
+        Some(blocked)
+    }
+}
+
+/// Clients blocked on list keys. Commands adding elements to a key mark it
+/// ready, the clients blocked on it are then served oldest first once the
+/// command is done.
+#[derive(Debug, Default)]
+pub struct Blocking {
+    blocked: Mutex<BlockedClients>,
+}
+
+impl Blocking {
+    /// Blocks a client until one of the keys is ready. Must be called while
+    /// holding the write lock of the database, so that no element can be
+    /// added in between the check that the keys are empty and now.
+    pub fn block(
+        &self,
+        id: u64,
+        db: usize,
+        keys: Vec<Bytes>,
+        command: Command,
+        timeout_reply: ZystResponse,
+    ) -> oneshot::Receiver<BlockedReply> {
+        let (sender, receiver) = oneshot::channel();
+        let mut blocked = lock(&self.blocked);
+
+        for key in keys.iter().collect::<IndexSet<_>>() {
+            blocked
+                .keys
+                .entry((db, key.clone()))
+                .or_default()
+                .push_back(id);
+        }
+        blocked.clients.insert(
+            id,
+            BlockedCommand {
+                db,
+                keys,
+                command,
+                timeout_reply,
+                reply: sender,
+            },
+        );
+
+        receiver
+    }
+
+    /// Unblocks a client as CLIENT UNBLOCK does, false when it isn't blocked
+    pub fn unblock(&self, id: u64, reason: UnblockReason) -> bool {
+        let Some(blocked) = lock(&self.blocked).remove(id) else {
+            return false;
+        };
+
+        let reply = match reason {
+            UnblockReason::Timeout => Ok(blocked.timeout_reply.clone()),
+            UnblockReason::Error => Err(ZystError::Custom(
+                "UNBLOCKED client unblocked via CLIENT UNBLOCK".to_string(),
+            )),
+        };
+        blocked.reply(reply);
+        true
+    }
+
+    /// Marks a key that got elements, if a client is blocked on it
+    pub fn signal(&self, db: usize, key: &Bytes) {
+        let mut blocked = lock(&self.blocked);
+        let entry = (db, key.clone());
+        if blocked.keys.contains_key(&entry) {
+            blocked.ready.insert(entry);
+        }
+    }
+
+    /// Takes the next key marked ready out
+    pub fn next_ready(&self) -> Option<(usize, Bytes)> {
+        lock(&self.blocked).ready.shift_remove_index(0)
+    }
+
+    /// Takes the client blocked the longest on a key out, it is no longer
+    /// blocked on its other keys either
+    pub fn next_client(&self, db: usize, key: &Bytes) -> Option<BlockedCommand> {
+        let mut blocked = lock(&self.blocked);
+        let id = *blocked.keys.get(&(db, key.clone()))?.front()?;
+        blocked.remove(id)
+    }
+}
+
+/// Waits for the reply of the command that blocked the client, until its
+/// timeout expires or the client is killed or disconnects
+pub async fn wait_unblocked(state: &State, client: &mut Client) -> BlockedReply {
+    let Some(blocked) = client.blocked.as_mut() else {
+        return Err(ZystError::DatabaseError);
+    };
+
+    let deadline = blocked.deadline;
+    let interrupted = async {
+        let expired = async {
+            match deadline {
+                Some(deadline) => time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = expired => {}
+            _ = client.handle.killed() => {}
+            _ = client.handle.closed() => {}
+        }
+    };
+
+    let reply = tokio::select! {
+        biased;
+        reply = &mut blocked.reply => reply,
+        _ = interrupted => {
+            // Served in the meantime, or the timeout reply is sent now
+            state.blocking().unblock(client.id, UnblockReason::Timeout);
+            (&mut blocked.reply).await
+        }
+    };
+
+    client.blocked = None;
+    client.publish();
+    reply.unwrap_or(Err(ZystError::DatabaseError))
+}
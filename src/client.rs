@@ -0,0 +1,355 @@
+use crate::blocking::Blocked;
+use crate::response::{Protocol, ZystResponse};
+use bytes::Bytes;
+use indexmap::{IndexMap, IndexSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Notify};
+use tokio::time;
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Pub/sub messages a connection can have waiting to be sent. A subscriber
+/// that falls further behind is disconnected, so it can't slow publishers
+/// down or grow the server memory.
+pub const PUBSUB_BUFFER: usize = 1024;
+
+// Registry locks are only held to copy data, a panic can't leave it half
+// updated
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Client names and library attributes are shown space separated by
+/// CLIENT LIST, so only printable characters without spaces are allowed
+pub fn is_valid_attribute(value: &[u8]) -> bool {
+    value.iter().all(|b| (b'!'..=b'~').contains(b))
+}
+
+/// Which replies are sent back, set by CLIENT REPLY
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplyMode {
+    #[default]
+    On,
+    Off,
+    /// The reply to the next command is dropped
+    Skip,
+}
+
+/// Commands queued after MULTI, run by EXEC
+#[derive(Debug, Default)]
+pub struct Transaction {
+    pub commands: Vec<Vec<Bytes>>,
+    /// A command couldn't be queued, EXEC discards the transaction
+    pub aborted: bool,
+}
+
+/// Key watched by WATCH, with its version at the time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedKey {
+    pub db: usize,
+    pub key: Bytes,
+    pub version: u64,
+}
+
+/// State attached to a single connection
+#[derive(Debug)]
+pub struct Client {
+    pub id: u64,
+    pub protocol: Protocol,
+    pub name: Option<String>,
+    pub lib_name: Option<String>,
+    pub lib_ver: Option<String>,
+    /// Index of the selected database
+    pub db: usize,
+    pub reply: ReplyMode,
+    /// Set between MULTI and EXEC or DISCARD
+    pub transaction: Option<Transaction>,
+    /// Keys EXEC checks for changes, until EXEC, DISCARD or UNWATCH
+    pub watched: Vec<WatchedKey>,
+    /// Channels subscribed with SUBSCRIBE
+    pub channels: IndexSet<Bytes>,
+    /// Patterns subscribed with PSUBSCRIBE
+    pub patterns: IndexSet<Bytes>,
+    /// Pub/sub messages published to the connection, sent as they arrive
+    pub messages: mpsc::Receiver<ZystResponse>,
+    /// Set by a blocking command that found nothing, the dispatcher waits
+    /// for the reply once the command is done
+    pub blocked: Option<Blocked>,
+    /// Blocking commands reply right away instead, set while EXEC runs and
+    /// while the AOF is replayed
+    pub deny_blocking: bool,
+    /// Full name of the last command, `client|list` for a subcommand
+    pub last_command: &'static str,
+    pub last_interaction: Instant,
+    /// What other connections can see of this one and act on
+    pub handle: Arc<ClientHandle>,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self::connected(None, None)
+    }
+
+    /// A client for the connection between `addr` and the local `laddr`
+    pub fn connected(addr: Option<SocketAddr>, laddr: Option<SocketAddr>) -> Self {
+        let id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        let (sender, messages) = mpsc::channel(PUBSUB_BUFFER);
+
+        let client = Client {
+            id,
+            protocol: Protocol::default(),
+            name: None,
+            lib_name: None,
+            lib_ver: None,
+            db: 0,
+            reply: ReplyMode::default(),
+            transaction: None,
+            watched: Vec::new(),
+            channels: IndexSet::new(),
+            patterns: IndexSet::new(),
+            messages,
+            blocked: None,
+            deny_blocking: false,
+            last_command: "NULL",
+            last_interaction: now,
+            handle: Arc::new(ClientHandle {
+                id,
+                addr,
+                laddr,
+                created: now,
+                info: Mutex::new(ClientInfo::default()),
+                killed: AtomicBool::new(false),
+                kill: Notify::new(),
+                closed: AtomicBool::new(false),
+                close: Notify::new(),
+                messages: sender,
+            }),
+        };
+        client.publish();
+        client
+    }
+
+    pub fn info(&self) -> ClientInfo {
+        ClientInfo {
+            name: self.name.clone(),
+            lib_name: self.lib_name.clone(),
+            lib_ver: self.lib_ver.clone(),
+            db: self.db,
+            protocol: self.protocol,
+            multi: self.transaction.as_ref().map(|t| t.commands.len()),
+            watch: self.watched.len(),
+            sub: self.channels.len(),
+            psub: self.patterns.len(),
+            blocked: self.blocked.is_some(),
+            last_command: self.last_command,
+            last_interaction: self.last_interaction,
+        }
+    }
+
+    /// Number of channels and patterns subscribed. While it isn't 0, a
+    /// RESP2 connection only accepts the pub/sub commands.
+    pub fn subscriptions(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    /// Shares the current state of the connection with the registry
+    pub fn publish(&self) {
+        *lock(&self.handle.info) = self.info();
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Snapshot of a connection, as listed by CLIENT LIST
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub name: Option<String>,
+    pub lib_name: Option<String>,
+    pub lib_ver: Option<String>,
+    pub db: usize,
+    pub protocol: Protocol,
+    /// Number of commands queued since MULTI
+    pub multi: Option<usize>,
+    /// Number of watched keys
+    pub watch: usize,
+    /// Number of channels subscribed
+    pub sub: usize,
+    /// Number of patterns subscribed
+    pub psub: usize,
+    /// Waiting for a blocking command to be served
+    pub blocked: bool,
+    pub last_command: &'static str,
+    pub last_interaction: Instant,
+}
+
+impl Default for ClientInfo {
+    fn default() -> Self {
+        ClientInfo {
+            name: None,
+            lib_name: None,
+            lib_ver: None,
+            db: 0,
+            protocol: Protocol::default(),
+            multi: None,
+            watch: 0,
+            sub: 0,
+            psub: 0,
+            blocked: false,
+            last_command: "NULL",
+            last_interaction: Instant::now(),
+        }
+    }
+}
+
+/// Part of a connection shared through the client registry
+#[derive(Debug)]
+pub struct ClientHandle {
+    pub id: u64,
+    pub addr: Option<SocketAddr>,
+    pub laddr: Option<SocketAddr>,
+    pub created: Instant,
+    info: Mutex<ClientInfo>,
+    killed: AtomicBool,
+    kill: Notify,
+    closed: AtomicBool,
+    close: Notify,
+    messages: mpsc::Sender<ZystResponse>,
+}
+
+impl ClientHandle {
+    /// State of the connection after its last command
+    pub fn info(&self) -> ClientInfo {
+        lock(&self.info).clone()
+    }
+
+    /// Asks the connection to close once the current command replied
+    pub fn kill(&self) {
+        self.killed.store(true, Ordering::Relaxed);
+        self.kill.notify_one();
+    }
+
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::Relaxed)
+    }
+
+    /// Resolves once the connection is killed
+    pub async fn killed(&self) {
+        if !self.is_killed() {
+            self.kill.notified().await;
+        }
+    }
+
+    /// Records that the peer closed the connection. The commands already
+    /// received still run, but a blocked one stops waiting.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.close.notify_one();
+    }
+
+    /// Resolves once the peer closed the connection
+    pub async fn closed(&self) {
+        if !self.closed.load(Ordering::Relaxed) {
+            self.close.notified().await;
+        }
+    }
+
+    /// Queues a pub/sub message for the connection, killing it when
+    /// `PUBSUB_BUFFER` messages are already waiting
+    pub fn push(&self, message: ZystResponse) {
+        if let Err(TrySendError::Full(_)) = self.messages.try_send(message) {
+            self.kill();
+        }
+    }
+}
+
+/// Connected clients, by id
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    clients: Mutex<IndexMap<u64, Arc<ClientHandle>>>,
+}
+
+impl ClientRegistry {
+    pub fn register(&self, handle: Arc<ClientHandle>) {
+        lock(&self.clients).insert(handle.id, handle);
+    }
+
+    pub fn unregister(&self, id: u64) {
+        lock(&self.clients).shift_remove(&id);
+    }
+
+    /// Every connected client, oldest first
+    pub fn list(&self) -> Vec<Arc<ClientHandle>> {
+        lock(&self.clients).values().cloned().collect()
+    }
+}
+
+/// Commands held back by CLIENT PAUSE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PauseMode {
+    Write,
+    All,
+}
+
+/// Pause set by CLIENT PAUSE, until its deadline or CLIENT UNPAUSE
+#[derive(Debug, Default)]
+pub struct ClientPause {
+    pause: Mutex<Option<(PauseMode, time::Instant)>>,
+    unpaused: Notify,
+}
+
+impl ClientPause {
+    /// Overlapping pauses keep the latest deadline and the strictest mode
+    pub fn pause(&self, mode: PauseMode, until: time::Instant) {
+        let mut pause = lock(&self.pause);
+        *pause = match *pause {
+            Some((current_mode, current_until)) if current_until > time::Instant::now() => {
+                Some((mode.max(current_mode), until.max(current_until)))
+            }
+            _ => Some((mode, until)),
+        };
+    }
+
+    pub fn unpause(&self) {
+        *lock(&self.pause) = None;
+        self.unpaused.notify_waiters();
+    }
+
+    // End of the pause a command has to wait for, if any
+    fn deadline(&self, write: bool) -> Option<time::Instant> {
+        match *lock(&self.pause) {
+            Some((mode, until))
+                if until > time::Instant::now() && (mode == PauseMode::All || write) =>
+            {
+                Some(until)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a command, a write one or not, would be held back
+    pub fn is_paused(&self, write: bool) -> bool {
+        self.deadline(write).is_some()
+    }
+
+    /// Waits for the end of the pause affecting a command
+    pub async fn wait(&self, write: bool) {
+        loop {
+            // Registered before the check, so an UNPAUSE in between isn't
+            // missed
+            let unpaused = self.unpaused.notified();
+            let Some(until) = self.deadline(write) else {
+                return;
+            };
+            let _ = time::timeout_at(until, unpaused).await;
+        }
+    }
+}
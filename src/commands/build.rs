@@ -1,311 +1,887 @@
 use crate::errors::ZystError;
-use crate::types::{Command, CommandArgs, CommandType};
+use crate::keys::current_timestamp_ms;
+use crate::parser::parse_number;
+use crate::types::{
+    CommandArgs, ExpireCondition, FieldExpiry, ListPushType, LposOptions, PopType, ScanOptions,
+    SetCondition, SetOptions,
+};
+use bytes::Bytes;
 use indexmap::IndexMap;
+use std::time::Duration;
 
-pub fn build_docs_command() -> Result<Command, ZystError> {
-    Ok(Command {
-        command_type: CommandType::DOCS,
-        args: CommandArgs::NoArgs,
-    })
+// Builders turn the arguments following the command name into `CommandArgs`.
+// The arity declared in the command table has already been checked, so only
+// command specific rules are validated here.
+
+pub fn build_no_args_command(_args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::NoArgs)
+}
+
+pub fn build_single_key_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::SingleKey(args[0].clone()))
+}
+
+pub fn build_multiple_keys_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::MultipleKeys(args.to_vec()))
 }
 
-pub fn build_pong_command() -> Result<Command, ZystError> {
-    Ok(Command {
-        command_type: CommandType::PONG,
-        args: CommandArgs::NoArgs,
+pub fn build_key_with_value_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::KeyWithValue {
+        key: args[0].clone(),
+        value: args[1].clone(),
     })
 }
 
-pub fn build_flush_db_command() -> Result<Command, ZystError> {
-    Ok(Command {
-        command_type: CommandType::FLUSHDB,
-        args: CommandArgs::NoArgs,
+pub fn build_key_with_values_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::KeyWithValues {
+        key: args[0].clone(),
+        values: args.iter().skip(1).cloned().collect::<Vec<Bytes>>(),
     })
 }
 
-pub fn build_get_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+/// Converts an `EX`, `PX`, `EXAT` or `PXAT` argument into a Unix time in
+/// milliseconds
+pub fn parse_expire_time(unit: &[u8], value: &[u8], command: &str) -> Result<i64, ZystError> {
+    let value = parse_number::<i64>(value).ok_or(ZystError::NotIntOrOutOfRange)?;
+    let invalid = || ZystError::InvalidExpireTime(command.to_string());
+
+    if value <= 0 {
+        return Err(invalid());
     }
-    Ok(Command {
-        command_type: CommandType::GET,
-        args: CommandArgs::SingleKey(args[0].to_string()),
-    })
+
+    match unit.to_ascii_uppercase().as_slice() {
+        b"EX" => value
+            .checked_mul(1000)
+            .and_then(|ms| ms.checked_add(current_timestamp_ms())),
+        b"PX" => value.checked_add(current_timestamp_ms()),
+        b"EXAT" => value.checked_mul(1000),
+        b"PXAT" => Some(value),
+        _ => return Err(ZystError::Syntax),
+    }
+    .ok_or_else(invalid)
 }
 
-pub fn build_keys_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+// SET key value [NX | XX | IFEQ comparison-value] [GET]
+//     [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+//      PXAT unix-time-milliseconds | KEEPTTL]
+fn parse_set_options(args: &[Bytes]) -> Result<SetOptions, ZystError> {
+    let mut options = SetOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let option = arg.to_ascii_uppercase();
+        let has_expiry = options.expires_at.is_some() || options.keep_ttl;
+
+        match (option.as_slice(), &options.condition) {
+            (b"NX", None | Some(SetCondition::NX)) => {
+                options.condition = Some(SetCondition::NX)
+            }
+            (b"XX", None | Some(SetCondition::XX)) => {
+                options.condition = Some(SetCondition::XX)
+            }
+            (b"IFEQ", None) => {
+                let value = args.next().ok_or(ZystError::Syntax)?;
+                options.condition = Some(SetCondition::IFEQ(value.clone()));
+            }
+            (b"GET", _) => options.get = true,
+            (b"KEEPTTL", _) if options.expires_at.is_none() => options.keep_ttl = true,
+            (b"EX" | b"PX" | b"EXAT" | b"PXAT", _) if !has_expiry => {
+                let value = args.next().ok_or(ZystError::Syntax)?;
+                options.expires_at = Some(parse_expire_time(&option, value, "set")?);
+            }
+            _ => return Err(ZystError::Syntax),
+        }
     }
-    Ok(Command {
-        command_type: CommandType::KEYS,
-        args: CommandArgs::SingleKey(args[0].to_string()),
+
+    Ok(options)
+}
+
+pub fn build_set_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::Set {
+        key: args[0].clone(),
+        value: args[1].clone(),
+        options: parse_set_options(&args[2..])?,
     })
 }
 
-pub fn build_set_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() != 2 {
-        return Err(ZystError::WrongNumberArgs);
-    }
-    Ok(Command {
-        command_type: CommandType::SET,
-        args: CommandArgs::KeyWithValue {
-            key: args[0].to_string(),
-            value: args[1].to_string(),
+pub fn build_setnx_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::Set {
+        key: args[0].clone(),
+        value: args[1].clone(),
+        options: SetOptions {
+            condition: Some(SetCondition::NX),
+            ..Default::default()
         },
     })
 }
 
-pub fn build_delete_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
-    }
-    Ok(Command {
-        command_type: CommandType::DEL,
-        args: CommandArgs::MultipleKeys(args.to_vec()),
+fn build_setex_psetex_command(
+    args: &[Bytes],
+    unit: &[u8],
+    command: &str,
+) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::Set {
+        key: args[0].clone(),
+        value: args[2].clone(),
+        options: SetOptions {
+            expires_at: Some(parse_expire_time(unit, &args[1], command)?),
+            ..Default::default()
+        },
     })
 }
 
-pub fn build_exists_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
-    }
-    Ok(Command {
-        command_type: CommandType::EXISTS,
-        args: CommandArgs::MultipleKeys(args.to_vec()),
-    })
+// SETEX key seconds value
+pub fn build_setex_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_setex_psetex_command(args, b"EX", "setex")
 }
 
-pub fn build_expire_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 2 {
-        return Err(ZystError::WrongNumberArgs);
-    }
+// PSETEX key milliseconds value
+pub fn build_psetex_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_setex_psetex_command(args, b"PX", "psetex")
+}
 
-    Ok(Command {
-        command_type: CommandType::EXPIRE,
-        args: CommandArgs::KeyWithValue {
-            key: args[0].to_string(),
-            value: args[1].to_string(),
+pub fn build_getset_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::Set {
+        key: args[0].clone(),
+        value: args[1].clone(),
+        options: SetOptions {
+            get: true,
+            ..Default::default()
         },
     })
 }
 
-pub fn build_ttl_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+// EXPIRE key seconds [NX | XX | GT | LT], and its PEXPIRE, EXPIREAT and
+// PEXPIREAT variants. Times in the past are accepted and delete the key.
+fn build_expire_variant_command(
+    args: &[Bytes],
+    unit: &[u8],
+    command: &str,
+) -> Result<CommandArgs, ZystError> {
+    let value = parse_number::<i64>(&args[1]).ok_or(ZystError::NotIntOrOutOfRange)?;
+    let invalid = || ZystError::InvalidExpireTime(command.to_string());
+
+    let expires_at = match unit {
+        b"EX" => value
+            .checked_mul(1000)
+            .and_then(|ms| ms.checked_add(current_timestamp_ms())),
+        b"PX" => value.checked_add(current_timestamp_ms()),
+        b"EXAT" => value.checked_mul(1000),
+        _ => Some(value),
     }
-    Ok(Command {
-        command_type: CommandType::TTL,
-        args: CommandArgs::SingleKey(args[0].to_string()),
+    .ok_or_else(invalid)?;
+
+    let mut conditions = Vec::new();
+    for arg in &args[2..] {
+        let condition = match arg.to_ascii_uppercase().as_slice() {
+            b"NX" => ExpireCondition::NX,
+            b"XX" => ExpireCondition::XX,
+            b"GT" => ExpireCondition::GT,
+            b"LT" => ExpireCondition::LT,
+            _ => {
+                return Err(ZystError::Custom(format!(
+                    "ERR Unsupported option {}",
+                    String::from_utf8_lossy(arg)
+                )))
+            }
+        };
+
+        if !conditions.contains(&condition) {
+            conditions.push(condition);
+        }
+    }
+
+    let has = |condition| conditions.contains(&condition);
+    if has(ExpireCondition::NX) && conditions.len() > 1 {
+        return Err(ZystError::Custom(
+            "ERR NX and XX, GT or LT options at the same time are not compatible".to_string(),
+        ));
+    }
+    if has(ExpireCondition::GT) && has(ExpireCondition::LT) {
+        return Err(ZystError::Custom(
+            "ERR GT and LT options at the same time are not compatible".to_string(),
+        ));
+    }
+
+    Ok(CommandArgs::Expire {
+        key: args[0].clone(),
+        expires_at,
+        conditions,
     })
 }
 
-pub fn build_incr_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+pub fn build_expire_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_expire_variant_command(args, b"EX", "expire")
+}
+
+pub fn build_pexpire_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_expire_variant_command(args, b"PX", "pexpire")
+}
+
+pub fn build_expireat_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_expire_variant_command(args, b"EXAT", "expireat")
+}
+
+pub fn build_pexpireat_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_expire_variant_command(args, b"PXAT", "pexpireat")
+}
+
+/// Types SCAN accepts, including the Redis ones zyst doesn't store
+const SCAN_TYPES: &[&str] = &["string", "list", "set", "hash", "zset", "stream"];
+
+// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+// SSCAN key cursor [MATCH pattern] [COUNT count]
+// HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+fn build_scan_variant_command(
+    key: Option<Bytes>,
+    args: &[Bytes],
+    command: &str,
+) -> Result<CommandArgs, ZystError> {
+    let cursor = parse_number::<u64>(&args[0])
+        .ok_or_else(|| ZystError::Custom("ERR invalid cursor".to_string()))?;
+
+    let mut options = ScanOptions {
+        pattern: None,
+        count: 10,
+        kind: None,
+        no_values: false,
+    };
+
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.to_ascii_uppercase().as_slice() {
+            b"MATCH" => options.pattern = Some(args.next().ok_or(ZystError::Syntax)?.clone()),
+            b"COUNT" => {
+                let count = args.next().ok_or(ZystError::Syntax)?;
+                options.count = match parse_number::<i64>(count) {
+                    Some(count) if count < 1 => return Err(ZystError::Syntax),
+                    Some(count) => count as usize,
+                    None => return Err(ZystError::NotIntOrOutOfRange),
+                };
+            }
+            b"TYPE" if command == "scan" => {
+                let kind = args.next().ok_or(ZystError::Syntax)?;
+                let name = SCAN_TYPES
+                    .iter()
+                    .find(|name| name.as_bytes().eq_ignore_ascii_case(kind))
+                    .ok_or_else(|| {
+                        ZystError::Custom(format!(
+                            "ERR unknown type name '{}'",
+                            String::from_utf8_lossy(kind)
+                        ))
+                    })?;
+                options.kind = Some(name);
+            }
+            b"NOVALUES" if command == "hscan" => options.no_values = true,
+            _ => return Err(ZystError::Syntax),
+        }
     }
-    Ok(Command {
-        command_type: CommandType::INCR,
-        args: CommandArgs::SingleKey(args[0].to_string()),
+
+    Ok(CommandArgs::Scan {
+        key,
+        cursor,
+        options,
     })
 }
 
-pub fn build_decr_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+pub fn build_scan_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_scan_variant_command(None, args, "scan")
+}
+
+pub fn build_sscan_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_scan_variant_command(Some(args[0].clone()), &args[1..], "sscan")
+}
+
+pub fn build_hscan_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_scan_variant_command(Some(args[0].clone()), &args[1..], "hscan")
+}
+
+// COPY source destination [DB destination-db] [REPLACE]
+pub fn build_copy_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let mut db = None;
+    let mut replace = false;
+
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.to_ascii_uppercase().as_slice() {
+            b"DB" => {
+                let index = options.next().ok_or(ZystError::Syntax)?;
+                db = Some(parse_db_index(index)?);
+            }
+            b"REPLACE" => replace = true,
+            _ => return Err(ZystError::Syntax),
+        }
     }
-    Ok(Command {
-        command_type: CommandType::DECR,
-        args: CommandArgs::SingleKey(args[0].to_string()),
+
+    Ok(CommandArgs::Copy {
+        source: args[0].clone(),
+        destination: args[1].clone(),
+        db,
+        replace,
+    })
+}
+
+// Negative indexes are out of range rather than malformed, like in Redis
+fn parse_db_index(arg: &Bytes) -> Result<usize, ZystError> {
+    let index = parse_number::<i64>(arg).ok_or(ZystError::NotIntOrOutOfRange)?;
+    usize::try_from(index)
+        .map_err(|_| ZystError::Custom("ERR DB index is out of range".to_string()))
+}
+
+// SELECT index
+pub fn build_select_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::Select(parse_db_index(&args[0])?))
+}
+
+// MOVE key db
+pub fn build_move_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::Move {
+        key: args[0].clone(),
+        db: parse_db_index(&args[1])?,
     })
 }
 
-pub fn build_incrby_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 2 {
-        return Err(ZystError::WrongNumberArgs);
+// SWAPDB index1 index2
+pub fn build_swapdb_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let first = parse_number::<i64>(&args[0])
+        .ok_or_else(|| ZystError::Custom("ERR invalid first DB index".to_string()))?;
+    let second = parse_number::<i64>(&args[1])
+        .ok_or_else(|| ZystError::Custom("ERR invalid second DB index".to_string()))?;
+
+    match (usize::try_from(first), usize::try_from(second)) {
+        (Ok(first), Ok(second)) => Ok(CommandArgs::SwapDb(first, second)),
+        _ => Err(ZystError::Custom(
+            "ERR DB index is out of range".to_string(),
+        )),
     }
+}
 
-    Ok(Command {
-        command_type: CommandType::INCRBY,
-        args: CommandArgs::KeyWithValue {
-            key: args[0].to_string(),
-            value: args[1].to_string(),
+// FLUSHDB [ASYNC | SYNC], FLUSHALL [ASYNC | SYNC]
+pub fn build_flush_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let lazy = match args {
+        [] => false,
+        [mode] => match mode.to_ascii_uppercase().as_slice() {
+            b"ASYNC" => true,
+            b"SYNC" => false,
+            _ => return Err(ZystError::Syntax),
         },
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(CommandArgs::Flush { lazy })
+}
+
+pub fn build_lrange_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::KeyWithValues {
+        key: args[0].clone(),
+        values: vec![args[1].clone(), args[2].clone()],
     })
 }
 
-fn build_push_command(args: &[String], cmd_type: CommandType) -> Result<Command, ZystError> {
-    Ok(Command {
-        command_type: cmd_type,
-        args: CommandArgs::KeyWithValues {
-            key: args[0].to_string(),
-            values: args.iter().skip(1).cloned().collect::<Vec<String>>(),
-        },
+pub fn build_pop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    match args.len() {
+        1 => build_single_key_command(args),
+        2 => build_key_with_value_command(args),
+        _ => Err(ZystError::Syntax),
+    }
+}
+
+// LPOP key [count], RPOP key [count]
+fn build_list_pop_command(args: &[Bytes], pop_type: PopType) -> Result<CommandArgs, ZystError> {
+    let count = match args {
+        [_] => None,
+        [_, count] => Some(parse_number::<usize>(count).ok_or_else(|| {
+            ZystError::Custom("ERR value is out of range, must be positive".to_string())
+        })?),
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(CommandArgs::ListPop {
+        keys: vec![args[0].clone()],
+        pop_type,
+        count,
+        timeout: None,
     })
 }
 
-pub fn build_lpush_command(args: &[String]) -> Result<Command, ZystError> {
-    build_push_command(args, CommandType::LPUSH)
+pub fn build_lpop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_list_pop_command(args, PopType::LPOP)
+}
+
+pub fn build_rpop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_list_pop_command(args, PopType::RPOP)
 }
 
-pub fn build_rpush_command(args: &[String]) -> Result<Command, ZystError> {
-    build_push_command(args, CommandType::RPUSH)
+// LINSERT key <BEFORE | AFTER> pivot element
+pub fn build_linsert_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let before = match args[1].to_ascii_uppercase().as_slice() {
+        b"BEFORE" => true,
+        b"AFTER" => false,
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(CommandArgs::ListInsert {
+        key: args[0].clone(),
+        before,
+        pivot: args[2].clone(),
+        element: args[3].clone(),
+    })
 }
 
-pub fn build_lrange_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 3 {
-        return Err(ZystError::WrongNumberArgs);
+// LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]
+pub fn build_lpos_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let mut options = LposOptions {
+        rank: 1,
+        count: None,
+        maxlen: 0,
+    };
+    let mut option_args = args[2..].iter();
+
+    while let Some(option) = option_args.next() {
+        let value = option_args.next().ok_or(ZystError::Syntax)?;
+        let value = parse_number::<i64>(value).ok_or(ZystError::NotIntOrOutOfRange)?;
+        let not_negative = |name: &str| {
+            usize::try_from(value)
+                .map_err(|_| ZystError::Custom(format!("ERR {name} can't be negative")))
+        };
+
+        match option.to_ascii_uppercase().as_slice() {
+            b"RANK" if value == 0 => {
+                return Err(ZystError::Custom(
+                    "ERR RANK can't be zero: use 1 to start from the first match, 2 from the \
+                     second ... or use negative to start from the end of the list"
+                        .to_string(),
+                ))
+            }
+            b"RANK" => options.rank = value,
+            b"COUNT" => options.count = Some(not_negative("COUNT")?),
+            b"MAXLEN" => options.maxlen = not_negative("MAXLEN")?,
+            _ => return Err(ZystError::Syntax),
+        }
     }
 
-    Ok(Command {
-        command_type: CommandType::LRANGE,
-        args: CommandArgs::KeyWithValues {
-            key: args[0].to_string(),
-            values: vec![args[1].to_string(), args[2].to_string()],
-        },
+    Ok(CommandArgs::Lpos {
+        key: args[0].clone(),
+        element: args[1].clone(),
+        options,
     })
 }
 
-pub fn build_lpop_command(args: &[String]) -> Result<Command, ZystError> {
-    build_lpop_rpop_command(args, CommandType::LPOP)
+// Timeout of a blocking command, in seconds with an optional fraction
+fn parse_timeout(arg: &[u8]) -> Result<Duration, ZystError> {
+    let seconds = parse_number::<f64>(arg)
+        .filter(|seconds| seconds.is_finite())
+        .ok_or_else(|| {
+            ZystError::Custom("ERR timeout is not a float or out of range".to_string())
+        })?;
+
+    if seconds < 0.0 {
+        return Err(ZystError::Custom("ERR timeout is negative".to_string()));
+    }
+
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| ZystError::Custom("ERR timeout is out of range".to_string()))
 }
-pub fn build_rpop_command(args: &[String]) -> Result<Command, ZystError> {
-    build_lpop_rpop_command(args, CommandType::RPOP)
+
+// LEFT or RIGHT, the end of a list elements are popped from
+fn parse_pop_type(arg: &[u8]) -> Result<PopType, ZystError> {
+    match arg.to_ascii_uppercase().as_slice() {
+        b"LEFT" => Ok(PopType::LPOP),
+        b"RIGHT" => Ok(PopType::RPOP),
+        _ => Err(ZystError::Syntax),
+    }
 }
 
-fn build_lpop_rpop_command(
-    args: &[String],
-    cmd_type: CommandType,
-) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+// LEFT or RIGHT, the end of a list elements are pushed to
+fn parse_push_type(arg: &[u8]) -> Result<ListPushType, ZystError> {
+    match arg.to_ascii_uppercase().as_slice() {
+        b"LEFT" => Ok(ListPushType::LPUSH),
+        b"RIGHT" => Ok(ListPushType::RPUSH),
+        _ => Err(ZystError::Syntax),
     }
+}
 
-    let command = if args.len() == 1 {
-        Command {
-            command_type: cmd_type,
-            args: CommandArgs::SingleKey(args[0].clone()),
-        }
-    } else {
-        Command {
-            command_type: cmd_type,
-            args: CommandArgs::KeyWithValue {
-                key: args[0].clone(),
-                value: args[1].clone(),
-            },
+// A positive integer, `error` is the reply otherwise
+fn parse_positive(arg: &[u8], error: &str) -> Result<usize, ZystError> {
+    parse_number::<usize>(arg)
+        .filter(|value| *value > 0)
+        .ok_or_else(|| ZystError::Custom(error.to_string()))
+}
+
+// BLPOP key [key ...] timeout, BRPOP key [key ...] timeout
+fn build_blocking_pop_command(
+    args: &[Bytes],
+    pop_type: PopType,
+) -> Result<CommandArgs, ZystError> {
+    let (timeout, keys) = args.split_last().ok_or(ZystError::Syntax)?;
+
+    Ok(CommandArgs::ListPop {
+        keys: keys.to_vec(),
+        pop_type,
+        count: None,
+        timeout: Some(parse_timeout(timeout)?),
+    })
+}
+
+pub fn build_blpop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_blocking_pop_command(args, PopType::LPOP)
+}
+
+pub fn build_brpop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_blocking_pop_command(args, PopType::RPOP)
+}
+
+// numkeys key [key ...] <LEFT | RIGHT> [COUNT count], the arguments of
+// LMPOP following the timeout of BLMPOP
+fn build_mpop_command(
+    args: &[Bytes],
+    timeout: Option<Duration>,
+) -> Result<CommandArgs, ZystError> {
+    let numkeys = parse_positive(&args[0], "ERR numkeys should be greater than 0")?;
+
+    let keys_end = numkeys
+        .checked_add(1)
+        .filter(|end| *end < args.len())
+        .ok_or(ZystError::Syntax)?;
+    let keys = &args[1..keys_end];
+    let (direction, options) = args[keys_end..].split_first().ok_or(ZystError::Syntax)?;
+
+    let count = match options {
+        [] => 1,
+        [option, count] if option.eq_ignore_ascii_case(b"COUNT") => {
+            parse_positive(count, "ERR count should be greater than 0")?
         }
+        _ => return Err(ZystError::Syntax),
     };
 
-    Ok(command)
+    Ok(CommandArgs::ListPop {
+        keys: keys.to_vec(),
+        pop_type: parse_pop_type(direction)?,
+        count: Some(count),
+        timeout,
+    })
 }
 
-pub fn build_hset_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 3 || args.len() % 2 == 0 {
-        return Err(ZystError::WrongNumberArgs);
-    }
+// LMPOP numkeys key [key ...] <LEFT | RIGHT> [COUNT count]
+pub fn build_lmpop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_mpop_command(args, None)
+}
 
-    let key = args[0].clone();
-    let fields = args[1..]
-        .iter()
-        .step_by(2) // Selects every other element starting from the first (field)
-        .zip(args[2..].iter().step_by(2)) // Pairs each field with the next value. zip() is great!
-        .map(|(field, value)| (field.clone(), value.clone())) // Convert to owned Strings
-        .collect::<IndexMap<String, String>>();
+// BLMPOP timeout numkeys key [key ...] <LEFT | RIGHT> [COUNT count]
+pub fn build_blmpop_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let timeout = parse_timeout(&args[0])?;
+    build_mpop_command(&args[1..], Some(timeout))
+}
 
-    Ok(Command {
-        command_type: CommandType::HSET,
-        args: CommandArgs::HashFields { key, fields },
+// LMOVE source destination <LEFT | RIGHT> <LEFT | RIGHT>
+pub fn build_lmove_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::ListMove {
+        source: args[0].clone(),
+        destination: args[1].clone(),
+        pop_type: parse_pop_type(&args[2])?,
+        push_type: parse_push_type(&args[3])?,
+        timeout: None,
     })
 }
 
-pub fn build_hget_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() != 2 {
-        return Err(ZystError::WrongNumberArgs);
+// BLMOVE source destination <LEFT | RIGHT> <LEFT | RIGHT> timeout
+pub fn build_blmove_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let mut command = build_lmove_command(args)?;
+    if let CommandArgs::ListMove { timeout, .. } = &mut command {
+        *timeout = Some(parse_timeout(&args[4])?);
     }
+    Ok(command)
+}
 
-    Ok(Command {
-        command_type: CommandType::HGET,
-        args: CommandArgs::KeyWithValue {
-            key: args[0].to_string(),
-            value: args[1].to_string(),
-        },
+// RPOPLPUSH source destination, LMOVE source destination RIGHT LEFT
+pub fn build_rpoplpush_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::ListMove {
+        source: args[0].clone(),
+        destination: args[1].clone(),
+        pop_type: PopType::RPOP,
+        push_type: ListPushType::LPUSH,
+        timeout: None,
+    })
+}
+
+// SMOVE source destination member
+pub fn build_smove_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::SetMove {
+        source: args[0].clone(),
+        destination: args[1].clone(),
+        member: args[2].clone(),
     })
 }
 
-pub fn build_hgetall_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+// Largest number of elements a negative count can ask SRANDMEMBER or
+// HRANDFIELD for. The elements may repeat, so unlike a positive count the
+// reply isn't bounded by the size of the collection.
+const MAX_RANDOM_COUNT: i64 = 1024 * 1024;
+
+// Count of SRANDMEMBER and HRANDFIELD, negative to allow repetitions
+fn parse_random_count(count: &[u8]) -> Result<i64, ZystError> {
+    let count = parse_number::<i64>(count).ok_or(ZystError::NotIntOrOutOfRange)?;
+    if count < -MAX_RANDOM_COUNT {
+        return Err(ZystError::Custom("ERR value is out of range".to_string()));
     }
+    Ok(count)
+}
 
-    Ok(Command {
-        command_type: CommandType::HGETALL,
-        args: CommandArgs::SingleKey(args[0].to_string()),
+// SRANDMEMBER key [count]
+pub fn build_srandmember_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let count = match args {
+        [_] => None,
+        [_, count] => Some(parse_random_count(count)?),
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(CommandArgs::Srandmember {
+        key: args[0].clone(),
+        count,
     })
 }
 
-pub fn build_hdel_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 2 {
-        return Err(ZystError::WrongNumberArgs);
-    }
+// SINTERCARD numkeys key [key ...] [LIMIT limit]
+pub fn build_sintercard_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let numkeys = parse_positive(&args[0], "ERR numkeys should be greater than 0")?;
+
+    let keys_end = numkeys
+        .checked_add(1)
+        .filter(|end| *end <= args.len())
+        .ok_or_else(|| {
+            ZystError::Custom(
+                "ERR Number of keys can't be greater than number of args".to_string(),
+            )
+        })?;
+
+    let limit = match &args[keys_end..] {
+        [] => 0,
+        [option, limit] if option.eq_ignore_ascii_case(b"LIMIT") => parse_number::<i64>(limit)
+            .ok_or(ZystError::NotIntOrOutOfRange)
+            .and_then(|limit| {
+                usize::try_from(limit)
+                    .map_err(|_| ZystError::Custom("ERR LIMIT can't be negative".to_string()))
+            })?,
+        _ => return Err(ZystError::Syntax),
+    };
 
-    Ok(Command {
-        command_type: CommandType::HDEL,
-        args: CommandArgs::KeyWithValues {
-            key: args[0].clone(),
-            values: args.iter().skip(1).cloned().collect::<Vec<String>>(),
-        },
+    Ok(CommandArgs::Sintercard {
+        keys: args[1..keys_end].to_vec(),
+        limit,
     })
 }
 
-pub fn build_client_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 2 {
-        return Err(ZystError::WrongNumberArgs);
-    }
+// HRANDFIELD key [count [WITHVALUES]]
+pub fn build_hrandfield_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let count = match args.get(1) {
+        Some(count) => parse_random_count(count)?,
+        None => {
+            return Ok(CommandArgs::Hrandfield {
+                key: args[0].clone(),
+                count: None,
+                with_values: false,
+            })
+        }
+    };
 
-    Ok(Command {
-        command_type: CommandType::CLIENT,
-        args: CommandArgs::KeyWithValues {
-            key: args[0].clone(),
-            values: args.iter().skip(1).cloned().collect::<Vec<String>>(),
-        },
+    let with_values = match &args[2..] {
+        [] => false,
+        [option] if option.eq_ignore_ascii_case(b"WITHVALUES") => true,
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(CommandArgs::Hrandfield {
+        key: args[0].clone(),
+        count: Some(count),
+        with_values,
     })
 }
 
-pub fn build_sadd_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.len() < 2 {
-        return Err(ZystError::WrongNumberArgs);
+// FIELDS numfields field [field ...], ending the hash field expiration
+// commands. Each field is followed by `values` values, returns them all.
+fn parse_fields(args: &[Bytes], values: usize) -> Result<&[Bytes], ZystError> {
+    match args.first() {
+        Some(arg) if arg.eq_ignore_ascii_case(b"FIELDS") => {}
+        _ => {
+            return Err(ZystError::Custom(
+                "ERR Mandatory argument FIELDS is missing or not at the right position"
+                    .to_string(),
+            ))
+        }
+    }
+
+    let numfields = args.get(1).ok_or(ZystError::Syntax)?;
+    let numfields = parse_number::<i64>(numfields).ok_or(ZystError::NotIntOrOutOfRange)?;
+    if numfields <= 0 {
+        return Err(ZystError::Custom(
+            "ERR Parameter `numFields` should be greater than 0".to_string(),
+        ));
+    }
+
+    let fields = &args[2..];
+    let expected = usize::try_from(numfields)
+        .ok()
+        .and_then(|numfields| numfields.checked_mul(values + 1));
+    if expected != Some(fields.len()) {
+        return Err(ZystError::Custom(
+            "ERR The `numfields` parameter must match the number of arguments".to_string(),
+        ));
+    }
+
+    Ok(fields)
+}
+
+// HEXPIRE key seconds [NX | XX | GT | LT] FIELDS numfields field [field ...]
+// and its PEXPIRE, EXPIREAT and PEXPIREAT variants
+fn build_hexpire_variant_command(
+    args: &[Bytes],
+    unit: &[u8],
+    command: &str,
+) -> Result<CommandArgs, ZystError> {
+    let value = parse_number::<i64>(&args[1]).ok_or(ZystError::NotIntOrOutOfRange)?;
+    if value < 0 {
+        return Err(ZystError::Custom(
+            "ERR invalid expire time, must be >= 0".to_string(),
+        ));
     }
 
-    Ok(Command {
-        command_type: CommandType::SADD,
-        args: CommandArgs::KeyWithValues {
-            key: args[0].to_string(),
-            values: args.iter().skip(1).cloned().collect::<Vec<String>>(),
-        },
+    let expires_at = match unit {
+        b"EX" => value
+            .checked_mul(1000)
+            .and_then(|ms| ms.checked_add(current_timestamp_ms())),
+        b"PX" => value.checked_add(current_timestamp_ms()),
+        b"EXAT" => value.checked_mul(1000),
+        _ => Some(value),
+    }
+    .ok_or_else(|| ZystError::InvalidExpireTime(command.to_string()))?;
+
+    let (condition, fields) = match args[2].to_ascii_uppercase().as_slice() {
+        b"NX" => (Some(ExpireCondition::NX), &args[3..]),
+        b"XX" => (Some(ExpireCondition::XX), &args[3..]),
+        b"GT" => (Some(ExpireCondition::GT), &args[3..]),
+        b"LT" => (Some(ExpireCondition::LT), &args[3..]),
+        _ => (None, &args[2..]),
+    };
+
+    Ok(CommandArgs::HashExpire {
+        key: args[0].clone(),
+        expires_at,
+        condition,
+        fields: parse_fields(fields, 0)?.to_vec(),
+    })
+}
+
+pub fn build_hexpire_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_hexpire_variant_command(args, b"EX", "hexpire")
+}
+
+pub fn build_hpexpire_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_hexpire_variant_command(args, b"PX", "hpexpire")
+}
+
+pub fn build_hexpireat_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_hexpire_variant_command(args, b"EXAT", "hexpireat")
+}
+
+pub fn build_hpexpireat_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    build_hexpire_variant_command(args, b"PXAT", "hpexpireat")
+}
+
+// HTTL key FIELDS numfields field [field ...], and the other commands
+// reading or removing the expiry of fields
+pub fn build_hash_fields_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    Ok(CommandArgs::KeyWithValues {
+        key: args[0].clone(),
+        values: parse_fields(&args[1..], 0)?.to_vec(),
     })
 }
 
-pub fn build_smembers_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+// [FNX | FXX] [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+//  PXAT unix-time-milliseconds | PERSIST | KEEPTTL], up to FIELDS whose
+// position is returned with them. HGETEX and HSETEX each reject the options
+// meant for the other.
+fn parse_field_options(
+    args: &[Bytes],
+    command: &str,
+) -> Result<(Option<bool>, Option<FieldExpiry>, usize), ZystError> {
+    let mut fields_exist = None;
+    let mut expiry = None;
+    let mut i = 0;
+
+    while let Some(arg) = args.get(i) {
+        let option = arg.to_ascii_uppercase();
+
+        match option.as_slice() {
+            b"FIELDS" => break,
+            b"FNX" | b"FXX" if fields_exist.is_none() => {
+                fields_exist = Some(option == b"FXX");
+            }
+            b"EX" | b"PX" | b"EXAT" | b"PXAT" if expiry.is_none() => {
+                let value = args.get(i + 1).ok_or(ZystError::Syntax)?;
+                expiry = Some(FieldExpiry::At(parse_expire_time(&option, value, command)?));
+                i += 1;
+            }
+            b"PERSIST" if expiry.is_none() => expiry = Some(FieldExpiry::Persist),
+            b"KEEPTTL" if expiry.is_none() => expiry = Some(FieldExpiry::KeepTtl),
+            _ => return Err(ZystError::Syntax),
+        }
+        i += 1;
+    }
+
+    Ok((fields_exist, expiry, i))
+}
+
+// HGETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+//     PXAT unix-time-milliseconds | PERSIST] FIELDS numfields field [field ...]
+pub fn build_hgetex_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let (fields_exist, expiry, fields) = parse_field_options(&args[1..], "hgetex")?;
+    let fields = &args[1 + fields..];
+    if fields_exist.is_some() || expiry == Some(FieldExpiry::KeepTtl) {
+        return Err(ZystError::Syntax);
     }
 
-    Ok(Command {
-        command_type: CommandType::SMEMBERS,
-        args: CommandArgs::SingleKey(args[0].to_string()),
+    Ok(CommandArgs::HashGetEx {
+        key: args[0].clone(),
+        expiry,
+        fields: parse_fields(fields, 0)?.to_vec(),
     })
 }
 
-pub fn build_srem_command(args: &[String]) -> Result<Command, ZystError> {
-    if args.is_empty() {
-        return Err(ZystError::WrongNumberArgs);
+// HSETEX key [FNX | FXX] [EX seconds | PX milliseconds |
+//     EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+//     FIELDS numfields field value [field value ...]
+pub fn build_hsetex_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    let (fields_exist, expiry, fields) = parse_field_options(&args[1..], "hsetex")?;
+    let fields = &args[1 + fields..];
+    if expiry == Some(FieldExpiry::Persist) {
+        return Err(ZystError::Syntax);
     }
 
-    Ok(Command {
-        command_type: CommandType::SREM,
-        args: CommandArgs::KeyWithValues {
-            key: args[0].to_string(),
-            values: args.iter().skip(1).cloned().collect::<Vec<String>>(),
-        },
+    let fields = parse_fields(fields, 1)?
+        .chunks_exact(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+
+    Ok(CommandArgs::HashSetEx {
+        key: args[0].clone(),
+        fields_exist,
+        expiry,
+        fields,
     })
 }
+
+pub fn build_hset_command(args: &[Bytes]) -> Result<CommandArgs, ZystError> {
+    if args.len().is_multiple_of(2) {
+        return Err(ZystError::WrongArity("hset".to_string()));
+    }
+
+    let key = args[0].clone();
+    let fields = args[1..]
+        .iter()
+        .step_by(2) // Selects every other element starting from the first (field)
+        .zip(args[2..].iter().step_by(2)) // Pairs each field with the next value. zip() is great!
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect::<IndexMap<Bytes, Bytes>>();
+
+    Ok(CommandArgs::HashFields { key, fields })
+}
@@ -0,0 +1,474 @@
+use crate::blocking::UnblockReason;
+use crate::client::{
+    is_valid_attribute, Client, ClientHandle, ClientInfo, PauseMode, ReplyMode,
+};
+use crate::errors::ZystError;
+use crate::parser::parse_number;
+use crate::response::{Protocol, ZystResponse};
+use crate::state::State;
+use crate::types::{Command, CommandArgs};
+use bytes::Bytes;
+use std::time::{Duration, Instant};
+use tokio::time;
+
+const CLIENT_HELP: &[&str] = &[
+    "CLIENT <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "GETNAME",
+    "    Return the name of the current connection.",
+    "ID",
+    "    Return the ID of the current connection.",
+    "INFO",
+    "    Return information about the current client connection.",
+    "KILL <ip:port>",
+    "    Kill connection made from <ip:port>.",
+    "KILL <option> <value> [<option> <value> [...]]",
+    "    Kill connections. Options are:",
+    "    * ADDR (<ip:port>|<unixsocket>:0)",
+    "      Kill connections made from the specified address",
+    "    * LADDR (<ip:port>|<unixsocket>:0)",
+    "      Kill connections made to specified local address",
+    "    * TYPE (NORMAL|MASTER|REPLICA|PUBSUB)",
+    "      Kill connections by type.",
+    "    * USER <username>",
+    "      Kill connections authenticated by <username>.",
+    "    * SKIPME (YES|NO)",
+    "      Skip killing current connection (default: yes).",
+    "    * ID <client-id>",
+    "      Kill connections by client id.",
+    "LIST [options ...]",
+    "    Return information about client connections. Options:",
+    "    * TYPE (NORMAL|MASTER|REPLICA|PUBSUB)",
+    "      Return clients of specified type.",
+    "UNPAUSE",
+    "    Stop the current client pause, resuming traffic.",
+    "UNBLOCK <clientid> [TIMEOUT|ERROR]",
+    "    Unblock the specified blocked client.",
+    "PAUSE <timeout> [WRITE|ALL]",
+    "    Suspend all, or just write, clients for <timeout> milliseconds.",
+    "REPLY (ON|OFF|SKIP)",
+    "    Control the replies sent to the current connection.",
+    "SETNAME <name>",
+    "    Assign the name <name> to the current connection.",
+    "SETINFO <option> <value>",
+    "    Set client meta attr. Options are:",
+    "    * LIB-NAME: the client lib name.",
+    "    * LIB-VER: the client lib version.",
+    "HELP",
+    "    Print this help.",
+];
+
+fn client_args(command: Command) -> Result<Vec<Bytes>, ZystError> {
+    match command.args {
+        CommandArgs::NoArgs => Ok(Vec::new()),
+        CommandArgs::MultipleKeys(args) => Ok(args),
+        _ => Err(ZystError::InvalidCommand),
+    }
+}
+
+fn lossy(value: &[u8]) -> String {
+    String::from_utf8_lossy(value).into_owned()
+}
+
+// Type filter of CLIENT LIST and CLIENT KILL. Zyst has no replication, so
+// a client is a pubsub one while subscribed and a normal one otherwise.
+#[derive(Clone, Copy)]
+enum ClientType {
+    Normal,
+    PubSub,
+    Replication,
+}
+
+impl ClientType {
+    fn parse(kind: &[u8]) -> Result<Self, ZystError> {
+        match kind.to_ascii_lowercase().as_slice() {
+            b"normal" => Ok(ClientType::Normal),
+            b"pubsub" => Ok(ClientType::PubSub),
+            b"master" | b"replica" | b"slave" => Ok(ClientType::Replication),
+            _ => Err(ZystError::Custom(format!(
+                "ERR Unknown client type '{}'",
+                lossy(kind)
+            ))),
+        }
+    }
+
+    fn matches(self, info: &ClientInfo) -> bool {
+        let subscribed = info.sub + info.psub > 0;
+        match self {
+            ClientType::Normal => !subscribed,
+            ClientType::PubSub => subscribed,
+            ClientType::Replication => false,
+        }
+    }
+}
+
+// Flags of CLIENT LIST: x in MULTI, P subscribed, N for none
+fn flags(info: &ClientInfo) -> String {
+    let mut flags = String::new();
+    if info.multi.is_some() {
+        flags.push('x');
+    }
+    if info.sub + info.psub > 0 {
+        flags.push('P');
+    }
+    if info.blocked {
+        flags.push('b');
+    }
+    if flags.is_empty() {
+        flags.push('N');
+    }
+    flags
+}
+
+/// One line of CLIENT LIST, the fields Zyst tracks in the Redis format
+fn client_line(handle: &ClientHandle, info: &ClientInfo) -> String {
+    let address = |addr: Option<std::net::SocketAddr>| addr.map(|a| a.to_string());
+    let now = Instant::now();
+    let resp = match info.protocol {
+        Protocol::Resp2 => 2,
+        Protocol::Resp3 => 3,
+    };
+
+    format!(
+        "id={} addr={} laddr={} name={} age={} idle={} flags={} db={} sub={} psub={} \
+         multi={} watch={} cmd={} user=default resp={} lib-name={} lib-ver={}\n",
+        handle.id,
+        address(handle.addr).unwrap_or_default(),
+        address(handle.laddr).unwrap_or_default(),
+        info.name.as_deref().unwrap_or_default(),
+        now.duration_since(handle.created).as_secs(),
+        now.duration_since(info.last_interaction).as_secs(),
+        flags(info),
+        info.db,
+        info.sub,
+        info.psub,
+        info.multi.map_or(-1, |queued| queued as i64),
+        info.watch,
+        info.last_command,
+        resp,
+        info.lib_name.as_deref().unwrap_or_default(),
+        info.lib_ver.as_deref().unwrap_or_default(),
+    )
+}
+
+pub async fn client_help() -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Array(
+        CLIENT_HELP
+            .iter()
+            .map(|line| ZystResponse::SimpleString(line.to_string()))
+            .collect(),
+    ))
+}
+
+pub async fn client_id(client: &Client) -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Int(client.id as i64))
+}
+
+/// Names the connection, an empty name removes it
+pub async fn client_setname(
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+    let name = &args[0];
+
+    if !is_valid_attribute(name) {
+        return Err(ZystError::Custom(
+            "ERR Client names cannot contain spaces, newlines or special characters."
+                .to_string(),
+        ));
+    }
+
+    client.name = (!name.is_empty()).then(|| lossy(name));
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn client_getname(client: &Client) -> Result<ZystResponse, ZystError> {
+    Ok(client.name.as_ref().map_or(ZystResponse::Nil, |name| {
+        ZystResponse::BulkString(Bytes::from(name.clone()))
+    }))
+}
+
+/// Records the library the client connects with, shown by CLIENT LIST
+pub async fn client_setinfo(
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+    let (attribute, value) = (&args[0], &args[1]);
+
+    let field = match attribute.to_ascii_lowercase().as_slice() {
+        b"lib-name" => &mut client.lib_name,
+        b"lib-ver" => &mut client.lib_ver,
+        _ => {
+            return Err(ZystError::Custom(format!(
+                "ERR Unrecognized option '{}'",
+                lossy(attribute)
+            )))
+        }
+    };
+
+    if !is_valid_attribute(value) {
+        return Err(ZystError::Custom(format!(
+            "ERR {} cannot contain spaces, newlines or special characters.",
+            lossy(attribute).to_ascii_lowercase()
+        )));
+    }
+
+    *field = (!value.is_empty()).then(|| lossy(value));
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn client_info(client: &Client) -> Result<ZystResponse, ZystError> {
+    let line = client_line(&client.handle, &client.info());
+    Ok(ZystResponse::BulkString(Bytes::from(line)))
+}
+
+/// Lists the connected clients, optionally only those of a type or with
+/// one of the given ids
+pub async fn client_list(
+    state: &State,
+    client: &Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+    let mut ids = None;
+    let mut kind = None;
+
+    let mut options = args.iter();
+    while let Some(option) = options.next() {
+        match option.to_ascii_uppercase().as_slice() {
+            b"TYPE" => {
+                let value = options.next().ok_or(ZystError::Syntax)?;
+                kind = Some(ClientType::parse(value)?);
+            }
+            b"ID" => {
+                let mut wanted = Vec::new();
+                for id in options.by_ref() {
+                    match parse_number::<u64>(id) {
+                        Some(id) if id > 0 => wanted.push(id),
+                        _ => {
+                            return Err(ZystError::Custom("ERR Invalid client ID".to_string()))
+                        }
+                    }
+                }
+                if wanted.is_empty() {
+                    return Err(ZystError::Syntax);
+                }
+                ids = Some(wanted);
+            }
+            _ => return Err(ZystError::Syntax),
+        }
+    }
+
+    let mut lines = String::new();
+    for handle in state.clients().list() {
+        if ids.as_ref().is_some_and(|ids| !ids.contains(&handle.id)) {
+            continue;
+        }
+
+        // The registry only has the state of the caller before this command
+        let info = if handle.id == client.id {
+            client.info()
+        } else {
+            handle.info()
+        };
+        if kind.is_some_and(|kind| !kind.matches(&info)) {
+            continue;
+        }
+        lines.push_str(&client_line(&handle, &info));
+    }
+
+    Ok(ZystResponse::BulkString(Bytes::from(lines)))
+}
+
+#[derive(Default)]
+struct KillFilter {
+    id: Option<u64>,
+    addr: Option<String>,
+    laddr: Option<String>,
+    kind: Option<ClientType>,
+    keep_caller: bool,
+}
+
+impl KillFilter {
+    fn matches(&self, handle: &ClientHandle, caller: u64) -> bool {
+        let address = |addr: Option<std::net::SocketAddr>| addr.map(|a| a.to_string());
+
+        !(self.keep_caller && handle.id == caller)
+            && self.id.is_none_or(|id| id == handle.id)
+            && self
+                .addr
+                .as_ref()
+                .is_none_or(|addr| Some(addr) == address(handle.addr).as_ref())
+            && self
+                .laddr
+                .as_ref()
+                .is_none_or(|addr| Some(addr) == address(handle.laddr).as_ref())
+            && self.kind.is_none_or(|kind| kind.matches(&handle.info()))
+    }
+}
+
+/// Closes connections once their current command replied. The old form
+/// takes a single address and replies OK, the filter form replies with the
+/// number of clients killed and spares the caller unless `SKIPME no`.
+pub async fn client_kill(
+    state: &State,
+    client: &Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+
+    if let [addr] = &args[..] {
+        let filter = KillFilter {
+            addr: Some(lossy(addr)),
+            ..KillFilter::default()
+        };
+        let target = state
+            .clients()
+            .list()
+            .into_iter()
+            .find(|handle| filter.matches(handle, client.id));
+
+        return match target {
+            Some(handle) => {
+                handle.kill();
+                Ok(ZystResponse::Ok)
+            }
+            None => Err(ZystError::Custom("ERR No such client".to_string())),
+        };
+    }
+
+    if !args.len().is_multiple_of(2) {
+        return Err(ZystError::Syntax);
+    }
+
+    let mut filter = KillFilter {
+        keep_caller: true,
+        ..KillFilter::default()
+    };
+
+    for pair in args.chunks(2) {
+        let (option, value) = (&pair[0], &pair[1]);
+        match option.to_ascii_uppercase().as_slice() {
+            b"ID" => match parse_number::<u64>(value) {
+                Some(id) if id > 0 => filter.id = Some(id),
+                _ => {
+                    return Err(ZystError::Custom(
+                        "ERR client-id should be greater than 0".to_string(),
+                    ))
+                }
+            },
+            b"ADDR" => filter.addr = Some(lossy(value)),
+            b"LADDR" => filter.laddr = Some(lossy(value)),
+            b"TYPE" => filter.kind = Some(ClientType::parse(value)?),
+            // Without ACL every connection is authenticated as `default`
+            b"USER" => {
+                if &value[..] != b"default" {
+                    return Err(ZystError::Custom(format!(
+                        "ERR No such user '{}'",
+                        lossy(value)
+                    )));
+                }
+            }
+            b"SKIPME" => match value.to_ascii_lowercase().as_slice() {
+                b"yes" => filter.keep_caller = true,
+                b"no" => filter.keep_caller = false,
+                _ => return Err(ZystError::Syntax),
+            },
+            _ => return Err(ZystError::Syntax),
+        }
+    }
+
+    let mut killed = 0;
+    for handle in state.clients().list() {
+        if filter.matches(&handle, client.id) {
+            handle.kill();
+            killed += 1;
+        }
+    }
+
+    Ok(ZystResponse::Int(killed))
+}
+
+/// Switches replies on or off, or drops the reply to the next command.
+/// The connection loop applies the mode, OFF and SKIP aren't replied to.
+pub async fn client_reply(
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+
+    client.reply = match args[0].to_ascii_uppercase().as_slice() {
+        b"ON" => ReplyMode::On,
+        b"OFF" => ReplyMode::Off,
+        b"SKIP" => ReplyMode::Skip,
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(ZystResponse::Ok)
+}
+
+/// Holds back the commands of every client, or only writes, for `timeout`
+/// milliseconds. Used to stop writes while a replica takes over.
+pub async fn client_pause(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+
+    let timeout = parse_number::<i64>(&args[0]).ok_or_else(|| {
+        ZystError::Custom("ERR timeout is not an integer or out of range".to_string())
+    })?;
+    if timeout < 0 {
+        return Err(ZystError::Custom("ERR timeout is negative".to_string()));
+    }
+
+    let mode = match &args[1..] {
+        [] => PauseMode::All,
+        [mode] => match mode.to_ascii_uppercase().as_slice() {
+            b"WRITE" => PauseMode::Write,
+            b"ALL" => PauseMode::All,
+            _ => return Err(ZystError::Syntax),
+        },
+        _ => return Err(ZystError::Syntax),
+    };
+
+    // A timeout past what an Instant holds pauses for 30 years instead
+    let now = time::Instant::now();
+    let until = now
+        .checked_add(Duration::from_millis(timeout as u64))
+        .unwrap_or(now + Duration::from_secs(30 * 365 * 86_400));
+    state.client_pause().pause(mode, until);
+
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn client_unpause(state: &State) -> Result<ZystResponse, ZystError> {
+    state.client_pause().unpause();
+    Ok(ZystResponse::Ok)
+}
+
+/// Interrupts a client blocked by a blocking command, which replies as if
+/// its timeout expired or with an error. Replies 1 when the client was
+/// blocked.
+pub async fn client_unblock(
+    state: &State,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = client_args(command)?;
+
+    let id = parse_number::<u64>(&args[0]).ok_or(ZystError::NotIntOrOutOfRange)?;
+    let reason = match &args[1..] {
+        [] => UnblockReason::Timeout,
+        [reason] => match reason.to_ascii_uppercase().as_slice() {
+            b"TIMEOUT" => UnblockReason::Timeout,
+            b"ERROR" => UnblockReason::Error,
+            _ => {
+                return Err(ZystError::Custom(
+                    "ERR CLIENT UNBLOCK reason should be TIMEOUT or ERROR".to_string(),
+                ))
+            }
+        },
+        _ => return Err(ZystError::Syntax),
+    };
+
+    let unblocked = state.blocking().unblock(id, reason);
+    Ok(ZystResponse::Int(i64::from(unblocked)))
+}
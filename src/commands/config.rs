@@ -0,0 +1,112 @@
+use crate::errors::ZystError;
+use crate::glob::string_match;
+use crate::notify::NotifyFlags;
+use crate::response::ZystResponse;
+use crate::state::State;
+use crate::types::{Command, CommandArgs};
+use bytes::Bytes;
+
+const CONFIG_HELP: &[&str] = &[
+    "CONFIG <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "GET <pattern>",
+    "    Return parameters matching the glob-like <pattern> and their values.",
+    "SET <directive> <value>",
+    "    Set the configuration <directive> to <value>.",
+    "HELP",
+    "    Print this help.",
+];
+
+/// Parameters CONFIG knows about. Only those Zyst can change at runtime
+/// are settable, the others are read from the command line and the
+/// config file at startup.
+const PARAMETERS: &[&str] = &["databases", "notify-keyspace-events"];
+
+fn config_args(command: Command) -> Result<Vec<Bytes>, ZystError> {
+    match command.args {
+        CommandArgs::NoArgs => Ok(Vec::new()),
+        CommandArgs::MultipleKeys(args) => Ok(args),
+        _ => Err(ZystError::InvalidCommand),
+    }
+}
+
+fn value(state: &State, parameter: &str) -> String {
+    match parameter {
+        "databases" => state.databases().len().to_string(),
+        _ => state.keyspace_events().to_string(),
+    }
+}
+
+pub async fn config_help() -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Array(
+        CONFIG_HELP
+            .iter()
+            .map(|line| ZystResponse::SimpleString(line.to_string()))
+            .collect(),
+    ))
+}
+
+/// Replies with the parameters matching any of the glob patterns and their
+/// values
+pub async fn config_get(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let patterns = config_args(command)?;
+
+    let entries = PARAMETERS
+        .iter()
+        .filter(|parameter| {
+            patterns
+                .iter()
+                .any(|pattern| string_match(pattern, parameter.as_bytes(), true))
+        })
+        .map(|parameter| {
+            (
+                ZystResponse::BulkString(Bytes::from_static(parameter.as_bytes())),
+                ZystResponse::BulkString(Bytes::from(value(state, parameter))),
+            )
+        })
+        .collect();
+
+    Ok(ZystResponse::Map(entries))
+}
+
+/// Sets parameters, all of them or none when one is invalid
+pub async fn config_set(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let args = config_args(command)?;
+    if !args.len().is_multiple_of(2) {
+        return Err(ZystError::Custom(
+            "ERR wrong number of arguments for 'config|set' command".to_string(),
+        ));
+    }
+
+    let mut keyspace_events = None;
+    for pair in args.chunks(2) {
+        let (parameter, value) = (&pair[0], &pair[1]);
+        let failed = |reason: &str| {
+            ZystError::Custom(format!(
+                "ERR CONFIG SET failed (possibly related to argument '{}') - {reason}",
+                String::from_utf8_lossy(parameter)
+            ))
+        };
+
+        match parameter.to_ascii_lowercase().as_slice() {
+            b"notify-keyspace-events" => {
+                let flags = NotifyFlags::parse(value).ok_or_else(|| {
+                    failed("Invalid event class character. Use 'Ag$lshzxeKEtmn'.")
+                })?;
+                keyspace_events = Some(flags);
+            }
+            b"databases" => return Err(failed("can't set immutable config")),
+            _ => {
+                return Err(ZystError::Custom(format!(
+                    "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+                    String::from_utf8_lossy(parameter)
+                )))
+            }
+        }
+    }
+
+    if let Some(flags) = keyspace_events {
+        state.set_keyspace_events(flags);
+    }
+
+    Ok(ZystResponse::Ok)
+}
@@ -1,10 +1,134 @@
-use crate::aof::delete_aof_file;
+use crate::client::Client;
 use crate::errors::ZystError;
+use crate::keyspace::Keyspace;
+use crate::notify::{publish_events, NotifyFlags};
 use crate::response::ZystResponse;
-use crate::types::Db;
+use crate::state::State;
+use crate::types::{Command, CommandArgs, Db};
 
-pub async fn flush_db(db: &Db) -> Result<ZystResponse, ZystError> {
-    db.write().await.clear();
-    delete_aof_file().await;
+fn out_of_range() -> ZystError {
+    ZystError::Custom("ERR DB index is out of range".to_string())
+}
+
+// Frees the keyspaces off the request path when the flush is ASYNC
+fn free(keyspaces: Vec<Keyspace>, lazy: bool) {
+    if lazy {
+        tokio::task::spawn_blocking(move || drop(keyspaces));
+    }
+}
+
+/// Switches the database the connection's commands apply to
+pub async fn select(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let index = match command.args {
+        CommandArgs::Select(index) => index,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    if state.db(index).is_none() {
+        return Err(out_of_range());
+    }
+
+    client.db = index;
     Ok(ZystResponse::Ok)
 }
+
+/// Moves a key, with its TTL, from the selected database to another one.
+/// Nothing moves when the key is missing or already in the target database.
+pub async fn move_key(
+    state: &State,
+    current: usize,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let (key, index) = match command.args {
+        CommandArgs::Move { key, db } => (key, db),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    if state.db(index).is_none() {
+        return Err(out_of_range());
+    }
+
+    if index == current {
+        return Err(ZystError::Custom(
+            "ERR source and destination objects are the same".to_string(),
+        ));
+    }
+
+    let (mut source, mut destination) = state
+        .write_pair(current, index)
+        .await
+        .ok_or(ZystError::DatabaseError)?;
+
+    if destination.contains_key(&key) {
+        return Ok(ZystResponse::Int(0));
+    }
+
+    let Some(value) = source.swap_remove(&key) else {
+        return Ok(ZystResponse::Int(0));
+    };
+    destination.insert(key.clone(), value);
+    source.notify(NotifyFlags::GENERIC, "move_from", key.clone());
+    destination.notify(NotifyFlags::GENERIC, "move_to", key);
+
+    // The dispatcher only publishes the events of the selected database,
+    // the source ones go first so that move_from precedes move_to
+    publish_events(state, current, &source);
+    publish_events(state, index, &destination);
+
+    Ok(ZystResponse::Int(1))
+}
+
+/// Exchanges the contents of two databases, connections keep their
+/// selected index and so see the other dataset right away
+pub async fn swap_db(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let (first, second) = match command.args {
+        CommandArgs::SwapDb(first, second) => (first, second),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    if state.db(first).is_none() || state.db(second).is_none() {
+        return Err(out_of_range());
+    }
+
+    if let Some((mut first, mut second)) = state.write_pair(first, second).await {
+        first.swap(&mut second);
+    }
+
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn flush_db(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let lazy = match command.args {
+        CommandArgs::Flush { lazy } => lazy,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let keyspace = db.write().await.take();
+    free(vec![keyspace], lazy);
+
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn flush_all(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let lazy = match command.args {
+        CommandArgs::Flush { lazy } => lazy,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut keyspaces = Vec::new();
+    for db in state.databases() {
+        keyspaces.push(db.write().await.take());
+    }
+    free(keyspaces, lazy);
+
+    Ok(ZystResponse::Ok)
+}
+
+/// Number of keys, including expired keys that weren't deleted yet
+pub async fn db_size(db: &Db) -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Int(db.read().await.len() as i64))
+}
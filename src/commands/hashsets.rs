@@ -1,38 +1,46 @@
+use crate::commands::build::{build_hgetex_command, build_hsetex_command};
+use crate::commands::registry::Context;
 use crate::errors::ZystError;
-use crate::response::ZystResponse;
+use crate::keys::current_timestamp_ms;
+use crate::keyspace::Keyspace;
+use crate::notify::NotifyFlags;
+use crate::parser::parse_number;
+use crate::response::{Protocol, ZystResponse};
 use crate::types::Command;
 use crate::types::CommandArgs;
 use crate::types::Db;
 use crate::types::DbValue;
+use crate::types::FieldExpiry;
+use crate::types::HashData;
 use crate::types::KeyHash;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use std::fmt;
 
 pub async fn hset(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
     let (key_name, key_values) = match &command.args {
-        CommandArgs::HashFields { key, fields } => (key.to_string(), fields),
+        CommandArgs::HashFields { key, fields } => (key.clone(), fields),
         _ => return Err(ZystError::InvalidCommand),
     };
 
-    let key = {
-        let db_read = db.read().await;
-        db_read.get(&key_name).cloned()
-    };
-
     let mut db_write = db.write().await;
 
-    let nb = match key {
-        Some(DbValue::HashKey(mut k)) => {
+    let nb = match db_write.get_mut(&key_name) {
+        Some(DbValue::HashKey(k)) => {
             let before_len = k.data.len();
-            k.data.extend(key_values.clone());
-            let after_len = k.data.len();
-            db_write.insert(key_name, DbValue::HashKey(k));
-            after_len - before_len
+            for (field, value) in key_values {
+                k.data.insert(field.clone(), value.clone());
+            }
+            let nb = k.data.len() - before_len;
+            db_write.signal_modified(&key_name);
+            nb
         }
         None => {
             db_write.insert(
                 key_name.clone(),
                 DbValue::HashKey(KeyHash {
-                    name: key_name,
-                    data: key_values.clone(),
+                    name: key_name.clone(),
+                    data: key_values.clone().into(),
                     ..Default::default()
                 }),
             );
@@ -41,6 +49,7 @@ pub async fn hset(db: &Db, command: Command) -> Result<ZystResponse, ZystError>
         Some(_) => return Err(ZystError::WrongType),
     };
 
+    db_write.notify(NotifyFlags::HASH, "hset", key_name);
     Ok(ZystResponse::Int(nb as i64))
 }
 
@@ -54,10 +63,13 @@ pub async fn hget(db: &Db, command: Command) -> Result<ZystResponse, ZystError>
 
     match db_read.get(hash_name) {
         Some(DbValue::HashKey(hash)) => match hash.data.get(field_name) {
-            Some(value) => Ok(ZystResponse::SimpleString(value.to_string())),
+            Some(value) => Ok(ZystResponse::BulkString(value.clone())),
             None => Ok(ZystResponse::Nil),
         },
-        None => Ok(ZystResponse::Nil),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", hash_name.clone());
+            Ok(ZystResponse::Nil)
+        }
         Some(_) => Err(ZystError::WrongType),
     }
 }
@@ -74,13 +86,21 @@ pub async fn hgetall(db: &Db, command: Command) -> Result<ZystResponse, ZystErro
         Some(DbValue::HashKey(key)) => key
             .data
             .iter()
-            .flat_map(|(k, v)| vec![k.clone(), v.clone()])
-            .collect::<Vec<String>>(),
+            .map(|(k, v)| {
+                (
+                    ZystResponse::BulkString(k.clone()),
+                    ZystResponse::BulkString(v.clone()),
+                )
+            })
+            .collect::<Vec<_>>(),
         Some(_) => return Err(ZystError::WrongType),
-        None => return Ok(ZystResponse::EmptyArray),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name.clone());
+            Vec::new()
+        }
     };
 
-    Ok(ZystResponse::List(results))
+    Ok(ZystResponse::Map(results))
 }
 
 pub async fn hdel(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
@@ -90,22 +110,861 @@ pub async fn hdel(db: &Db, command: Command) -> Result<ZystResponse, ZystError>
     };
 
     let mut db_write = db.write().await;
+    let deleted_count = delete_fields(&mut db_write, &key_name, &fields)?;
+
+    Ok(ZystResponse::Int(deleted_count as i64))
+}
 
-    match db_write.get_mut(&key_name) {
+// Deletes fields of a hash, and the hash once none is left. Returns how
+// many fields existed.
+fn delete_fields(
+    keyspace: &mut Keyspace,
+    key: &Bytes,
+    fields: &[Bytes],
+) -> Result<usize, ZystError> {
+    let (deleted_count, emptied) = match keyspace.get_mut(key) {
         Some(DbValue::HashKey(hash)) => {
-            let mut deleted_count = 0;
-            for field in fields {
-                if hash.data.swap_remove(&field).is_some() {
-                    deleted_count += 1;
+            let deleted_count = fields
+                .iter()
+                .filter(|field| hash.data.swap_remove(field).is_some())
+                .count();
+            (deleted_count, hash.data.is_empty())
+        }
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(0),
+    };
+
+    if deleted_count > 0 {
+        keyspace.signal_modified(key);
+        keyspace.notify(NotifyFlags::HASH, "hdel", key.clone());
+    }
+    if emptied {
+        keyspace.swap_remove(key);
+        keyspace.notify(NotifyFlags::GENERIC, "del", key.clone());
+    }
+    Ok(deleted_count)
+}
+
+// Fields of the hash at a key, None when the key doesn't exist
+fn lookup_hash<'a>(
+    keyspace: &'a Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a HashData>, ZystError> {
+    match keyspace.get(key) {
+        Some(DbValue::HashKey(hash)) => Ok(Some(&hash.data)),
+        Some(_) => Err(ZystError::WrongType),
+        None => {
+            keyspace.notify(NotifyFlags::KEY_MISS, "keymiss", key.clone());
+            Ok(None)
+        }
+    }
+}
+
+// Sets a field, keeping its TTL, the hash is created when the key doesn't
+// exist
+fn set_field(
+    keyspace: &mut Keyspace,
+    key: &Bytes,
+    field: Bytes,
+    value: Bytes,
+) -> Result<(), ZystError> {
+    match keyspace.get_mut(key) {
+        Some(DbValue::HashKey(hash)) => {
+            hash.data.insert_keep_ttl(field, value);
+            keyspace.signal_modified(key);
+        }
+        Some(_) => return Err(ZystError::WrongType),
+        None => {
+            let hash = KeyHash {
+                name: key.clone(),
+                data: IndexMap::from([(field, value)]).into(),
+                ..Default::default()
+            };
+            keyspace.insert(key.clone(), DbValue::HashKey(hash));
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn hexists(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, field) = match command.args {
+        CommandArgs::KeyWithValue { key, value } => (key, value),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let found = lookup_hash(&db_read, &key_name)?.is_some_and(|hash| hash.contains_key(&field));
+
+    Ok(ZystResponse::Int(found as i64))
+}
+
+pub async fn hlen(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let len = lookup_hash(&db_read, &key_name)?.map_or(0, HashData::len);
+
+    Ok(ZystResponse::Int(len as i64))
+}
+
+pub async fn hkeys(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+
+    match lookup_hash(&db_read, &key_name)? {
+        Some(hash) => Ok(ZystResponse::List(hash.keys().cloned().collect())),
+        None => Ok(ZystResponse::EmptyArray),
+    }
+}
+
+pub async fn hvals(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+
+    match lookup_hash(&db_read, &key_name)? {
+        Some(hash) => Ok(ZystResponse::List(hash.values().cloned().collect())),
+        None => Ok(ZystResponse::EmptyArray),
+    }
+}
+
+/// HMGET replies with a null for each field that doesn't exist
+pub async fn hmget(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, fields) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let hash = lookup_hash(&db_read, &key_name)?;
+
+    Ok(ZystResponse::Array(
+        fields
+            .iter()
+            .map(|field| {
+                hash.and_then(|hash| hash.get(field))
+                    .map_or(ZystResponse::Nil, |value| {
+                        ZystResponse::BulkString(value.clone())
+                    })
+            })
+            .collect(),
+    ))
+}
+
+pub async fn hstrlen(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, field) = match command.args {
+        CommandArgs::KeyWithValue { key, value } => (key, value),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let len = lookup_hash(&db_read, &key_name)?
+        .and_then(|hash| hash.get(&field))
+        .map_or(0, Bytes::len);
+
+    Ok(ZystResponse::Int(len as i64))
+}
+
+pub async fn hsetnx(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+    let (field, value) = (values[0].clone(), values[1].clone());
+
+    let mut db_write = db.write().await;
+
+    match db_write.get(&key_name) {
+        Some(DbValue::HashKey(hash)) if hash.data.contains_key(&field) => {
+            return Ok(ZystResponse::Int(0))
+        }
+        Some(DbValue::HashKey(_)) | None => {}
+        Some(_) => return Err(ZystError::WrongType),
+    }
+
+    set_field(&mut db_write, &key_name, field, value)?;
+    db_write.notify(NotifyFlags::HASH, "hset", key_name);
+
+    Ok(ZystResponse::Int(1))
+}
+
+// Current value of a field to increment, None when the field doesn't exist
+fn field_value(
+    keyspace: &Keyspace,
+    key: &Bytes,
+    field: &Bytes,
+) -> Result<Option<Bytes>, ZystError> {
+    match keyspace.get(key) {
+        Some(DbValue::HashKey(hash)) => Ok(hash.data.get(field).cloned()),
+        Some(_) => Err(ZystError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// HINCRBY counts from 0 when the field doesn't exist
+pub async fn hincrby(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let field = &values[0];
+    let increment = parse_number::<i64>(&values[1]).ok_or(ZystError::NotIntOrOutOfRange)?;
+
+    let mut db_write = db.write().await;
+
+    let current = match field_value(&db_write, &key_name, field)? {
+        Some(value) => parse_number::<i64>(&value)
+            .ok_or_else(|| ZystError::Custom("ERR hash value is not an integer".to_string()))?,
+        None => 0,
+    };
+    let value = current.checked_add(increment).ok_or_else(|| {
+        ZystError::Custom("ERR increment or decrement would overflow".to_string())
+    })?;
+
+    set_field(
+        &mut db_write,
+        &key_name,
+        field.clone(),
+        Bytes::from(value.to_string()),
+    )?;
+    db_write.notify(NotifyFlags::HASH, "hincrby", key_name);
+
+    Ok(ZystResponse::Int(value))
+}
+
+// A float as Redis reads one, NaN isn't a number it accepts
+fn parse_float(value: &[u8]) -> Option<f64> {
+    parse_number::<f64>(value).filter(|value| !value.is_nan())
+}
+
+// Redis writes floats in fixed notation with 17 decimals, then trims the
+// trailing zeros
+const FLOAT_DECIMALS: u32 = 17;
+
+// A number as it is written, `mantissa / 10^scale`. Redis adds floats as
+// long doubles, which are precise enough that 0.1 plus 0.2 writes as 0.3,
+// an exact decimal sum gives the same result where a f64 one wouldn't.
+#[derive(Debug, Clone, Copy)]
+struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    // Reads "12", "-1.5", ".5" or "2.5e-3", None when the digits don't fit
+    fn parse(value: &[u8]) -> Option<Decimal> {
+        let value = std::str::from_utf8(value).ok()?;
+        let (number, exponent) = match value.find(['e', 'E']) {
+            Some(at) => (&value[..at], value[at + 1..].parse::<i64>().ok()?),
+            None => (value, 0),
+        };
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return None;
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in integer.bytes().chain(fraction.bytes()) {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            mantissa = mantissa
+                .checked_mul(10)?
+                .checked_add(i128::from(digit - b'0'))?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        // A positive exponent larger than the decimals moves to the mantissa
+        let scale = (fraction.len() as i64).checked_sub(exponent)?;
+        match u32::try_from(scale) {
+            Ok(scale) => Some(Decimal { mantissa, scale }),
+            Err(_) => {
+                let shift = 10i128.checked_pow(u32::try_from(scale.unsigned_abs()).ok()?)?;
+                Some(Decimal {
+                    mantissa: mantissa.checked_mul(shift)?,
+                    scale: 0,
+                })
+            }
+        }
+    }
+
+    fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        let scale = self.scale.max(other.scale);
+        let align = |decimal: Decimal| {
+            decimal
+                .mantissa
+                .checked_mul(10i128.checked_pow(scale - decimal.scale)?)
+        };
+
+        Some(Decimal {
+            mantissa: align(self)?.checked_add(align(other)?)?,
+            scale,
+        })
+    }
+
+    // Rounds half away from zero to `FLOAT_DECIMALS` decimals at most
+    fn round(self) -> Decimal {
+        if self.scale <= FLOAT_DECIMALS {
+            return self;
+        }
+
+        // Past the 38 digits of an i128 the value rounds to 0
+        let mantissa = match 10i128.checked_pow(self.scale - FLOAT_DECIMALS) {
+            Some(divisor) => {
+                let rounded = self.mantissa / divisor;
+                if (self.mantissa % divisor).unsigned_abs() * 2 >= divisor.unsigned_abs() {
+                    rounded + self.mantissa.signum()
+                } else {
+                    rounded
                 }
             }
+            None => 0,
+        };
+
+        Decimal {
+            mantissa,
+            scale: FLOAT_DECIMALS,
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Decimal { mantissa, scale } = self.round();
+        let digits = mantissa.unsigned_abs().to_string();
+        let sign = if mantissa < 0 { "-" } else { "" };
+
+        // Pads with leading zeros so that there is an integer part
+        let digits = format!("{digits:0>width$}", width = scale as usize + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+        let fraction = fraction.trim_end_matches('0');
 
-            if hash.data.is_empty() {
-                db_write.swap_remove(&key_name);
+        if fraction.is_empty() {
+            write!(f, "{sign}{integer}")
+        } else {
+            write!(f, "{sign}{integer}.{fraction}")
+        }
+    }
+}
+
+// A f64 as Redis writes a float, for sums whose digits don't fit a Decimal
+fn format_float(value: f64) -> String {
+    let value = format!("{value:.precision$}", precision = FLOAT_DECIMALS as usize);
+    value
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// HINCRBYFLOAT adds an increment to a float field, counting from 0 when it
+/// doesn't exist, and replies with the new value as Redis writes it: in
+/// fixed notation, with at most 17 decimals.
+pub async fn hincrbyfloat(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let (field, increment) = (&values[0], &values[1]);
+    let increment_value = parse_float(increment)
+        .ok_or_else(|| ZystError::Custom("ERR value is not a valid float".to_string()))?;
+
+    let mut db_write = db.write().await;
+
+    let current = field_value(&db_write, &key_name, field)?;
+    let current_value = match &current {
+        Some(value) => parse_float(value)
+            .ok_or_else(|| ZystError::Custom("ERR hash value is not a float".to_string()))?,
+        None => 0.0,
+    };
+
+    let sum = Decimal::parse(current.as_deref().unwrap_or(b"0"))
+        .zip(Decimal::parse(increment))
+        .and_then(|(current, increment)| current.checked_add(increment));
+    let value = match sum {
+        Some(sum) => sum.to_string(),
+        None => {
+            let value = current_value + increment_value;
+            if !value.is_finite() {
+                return Err(ZystError::Custom(
+                    "ERR increment would produce NaN or Infinity".to_string(),
+                ));
             }
-            Ok(ZystResponse::Int(deleted_count))
+            format_float(value)
         }
-        Some(_) => Err(ZystError::WrongType),
-        None => Ok(ZystResponse::Int(0)),
+    };
+
+    let value = Bytes::from(value);
+    set_field(&mut db_write, &key_name, field.clone(), value.clone())?;
+    db_write.notify(NotifyFlags::HASH, "hincrbyfloat", key_name);
+
+    Ok(ZystResponse::BulkString(value))
+}
+
+/// Logs HINCRBYFLOAT as an HSETEX of the value it replied with, KEEPTTL
+/// so that replaying it doesn't persist the field
+pub fn propagate_hincrbyfloat(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    match response {
+        ZystResponse::BulkString(value) => vec![
+            Bytes::from_static(b"HSETEX"),
+            argv[1].clone(),
+            Bytes::from_static(b"KEEPTTL"),
+            Bytes::from_static(b"FIELDS"),
+            Bytes::from_static(b"1"),
+            argv[2].clone(),
+            value.clone(),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// HRANDFIELD returns distinct fields with a positive count, and may return
+/// the same field several times with a negative one. With WITHVALUES, RESP3
+/// clients get a pair per field.
+pub async fn hrandfield(
+    db: &Db,
+    command: Command,
+    protocol: Protocol,
+) -> Result<ZystResponse, ZystError> {
+    let (key_name, count, with_values) = match command.args {
+        CommandArgs::Hrandfield {
+            key,
+            count,
+            with_values,
+        } => (key, count, with_values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+
+    let hash = match lookup_hash(&db_read, &key_name)? {
+        Some(hash) => hash,
+        None if count.is_some() => return Ok(ZystResponse::EmptyArray),
+        None => return Ok(ZystResponse::Nil),
+    };
+
+    let Some(count) = count else {
+        return Ok(hash.random_field().map_or(ZystResponse::Nil, |(field, _)| {
+            ZystResponse::BulkString(field.clone())
+        }));
+    };
+
+    let picked = hash.random_fields(count);
+    if picked.is_empty() {
+        return Ok(ZystResponse::EmptyArray);
+    }
+
+    let reply = match (with_values, protocol) {
+        (false, _) => {
+            ZystResponse::List(picked.into_iter().map(|(field, _)| field.clone()).collect())
+        }
+        (true, Protocol::Resp3) => ZystResponse::Array(
+            picked
+                .into_iter()
+                .map(|(field, value)| ZystResponse::List(vec![field.clone(), value.clone()]))
+                .collect(),
+        ),
+        (true, Protocol::Resp2) => ZystResponse::List(
+            picked
+                .into_iter()
+                .flat_map(|(field, value)| [field.clone(), value.clone()])
+                .collect(),
+        ),
+    };
+
+    Ok(reply)
+}
+
+// Replies with the same integer for each field, when the key doesn't exist
+fn each_field(fields: &[Bytes], reply: i64) -> ZystResponse {
+    ZystResponse::Array(vec![ZystResponse::Int(reply); fields.len()])
+}
+
+/// Shared by HEXPIRE, HPEXPIRE, HEXPIREAT and HPEXPIREAT, whose builders all
+/// resolve the new expiry to a Unix time in milliseconds. Replies for each
+/// field with -2 when it doesn't exist, 0 when the condition doesn't hold,
+/// 1 when the expiry is set, and 2 when the expiry is in the past, which
+/// deletes the field right away. The expiry is logged as that absolute
+/// HPEXPIREAT of the fields it applied to, so replaying the AOF later
+/// doesn't push it back.
+pub async fn hexpire(
+    ctx: &mut Context<'_>,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let (key_name, expires_at, condition, fields) = match command.args {
+        CommandArgs::HashExpire {
+            key,
+            expires_at,
+            condition,
+            fields,
+        } => (key, expires_at, condition, fields),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = ctx.db.write().await;
+    let expired = expires_at <= current_timestamp_ms();
+
+    let replies: Vec<i64> = match db_write.get_mut(&key_name) {
+        Some(DbValue::HashKey(hash)) => fields
+            .iter()
+            .map(|field| {
+                let current = hash.data.expires_at(field);
+                match hash.data.get(field) {
+                    None => -2,
+                    Some(_) if !condition.is_none_or(|c| c.holds(current, expires_at)) => 0,
+                    Some(_) if expired => 2,
+                    Some(_) => 1,
+                }
+            })
+            .collect(),
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(each_field(&fields, -2)),
+    };
+
+    let applied: Vec<Bytes> = fields
+        .into_iter()
+        .zip(&replies)
+        .filter(|(_, reply)| **reply > 0)
+        .map(|(field, _)| field)
+        .collect();
+
+    if expired {
+        delete_fields(&mut db_write, &key_name, &applied)?;
+    } else if !applied.is_empty() {
+        for field in &applied {
+            db_write.set_field_expires_at(&key_name, field, Some(expires_at));
+        }
+        db_write.notify(NotifyFlags::HASH, "hexpire", key_name.clone());
+    }
+
+    let hpexpireat = hpexpireat(key_name, expires_at, applied);
+    if !hpexpireat.is_empty() {
+        ctx.propagated.push((ctx.client.db, hpexpireat));
+    }
+
+    Ok(ZystResponse::Array(
+        replies.into_iter().map(ZystResponse::Int).collect(),
+    ))
+}
+
+// HPEXPIREAT key unix-time-milliseconds FIELDS numfields field [field ...],
+// nothing when there are no fields
+pub(crate) fn hpexpireat(key: Bytes, expires_at: i64, fields: Vec<Bytes>) -> Vec<Bytes> {
+    with_fields(
+        vec![
+            Bytes::from_static(b"HPEXPIREAT"),
+            key,
+            Bytes::from(expires_at.to_string()),
+        ],
+        fields,
+    )
+}
+
+// Appends FIELDS numfields and the fields to a command, nothing is left
+// to log when there are no fields
+fn with_fields(mut command: Vec<Bytes>, fields: Vec<Bytes>) -> Vec<Bytes> {
+    if fields.is_empty() {
+        return Vec::new();
     }
+
+    command.push(Bytes::from_static(b"FIELDS"));
+    command.push(Bytes::from(fields.len().to_string()));
+    command.extend(fields);
+    command
+}
+
+// HTTL, HPTTL, HEXPIRETIME and HPEXPIRETIME reply for each field with -2
+// when it doesn't exist and -1 when it has no expiry, and only differ in
+// how they format the expiry time
+async fn field_expiry(
+    db: &Db,
+    command: Command,
+    format: fn(i64) -> i64,
+) -> Result<ZystResponse, ZystError> {
+    let (key_name, fields) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+
+    let Some(hash) = lookup_hash(&db_read, &key_name)? else {
+        return Ok(each_field(&fields, -2));
+    };
+
+    Ok(ZystResponse::Array(
+        fields
+            .iter()
+            .map(|field| match hash.get(field) {
+                Some(_) => ZystResponse::Int(hash.expires_at(field).map_or(-1, format)),
+                None => ZystResponse::Int(-2),
+            })
+            .collect(),
+    ))
+}
+
+pub async fn httl(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    field_expiry(db, command, |expires_at| {
+        (expires_at - current_timestamp_ms() + 500).div_euclid(1000)
+    })
+    .await
+}
+
+pub async fn hpttl(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    field_expiry(db, command, |expires_at| {
+        expires_at - current_timestamp_ms()
+    })
+    .await
+}
+
+pub async fn hexpiretime(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    field_expiry(db, command, |expires_at| expires_at.div_euclid(1000)).await
+}
+
+pub async fn hpexpiretime(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    field_expiry(db, command, |expires_at| expires_at).await
+}
+
+/// HPERSIST replies for each field with -2 when it doesn't exist, -1 when
+/// it has no expiry and 1 when its expiry is removed
+pub async fn hpersist(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, fields) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+
+    let replies: Vec<i64> = match db_write.get(&key_name) {
+        Some(DbValue::HashKey(hash)) => fields
+            .iter()
+            .map(
+                |field| match (hash.data.get(field), hash.data.expires_at(field)) {
+                    (None, _) => -2,
+                    (Some(_), None) => -1,
+                    (Some(_), Some(_)) => 1,
+                },
+            )
+            .collect(),
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(each_field(&fields, -2)),
+    };
+
+    let persisted: Vec<&Bytes> = fields
+        .iter()
+        .zip(&replies)
+        .filter(|(_, reply)| **reply == 1)
+        .map(|(field, _)| field)
+        .collect();
+
+    if !persisted.is_empty() {
+        for field in persisted {
+            db_write.set_field_expires_at(&key_name, field, None);
+        }
+        db_write.notify(NotifyFlags::HASH, "hpersist", key_name);
+    }
+
+    Ok(ZystResponse::Array(
+        replies.into_iter().map(ZystResponse::Int).collect(),
+    ))
+}
+
+/// HGETEX replies with the values of the fields, a null for each field that
+/// doesn't exist, and sets or removes the expiry of those that do. An
+/// expiry in the past deletes them.
+pub async fn hgetex(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, expiry, fields) = match command.args {
+        CommandArgs::HashGetEx {
+            key,
+            expiry,
+            fields,
+        } => (key, expiry, fields),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+
+    let Some(hash) = lookup_hash(&db_write, &key_name)? else {
+        return Ok(ZystResponse::Array(vec![ZystResponse::Nil; fields.len()]));
+    };
+
+    let values: Vec<Option<Bytes>> = fields
+        .iter()
+        .map(|field| hash.get(field).cloned())
+        .collect();
+    let found: Vec<Bytes> = fields
+        .iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_some())
+        .map(|(field, _)| field.clone())
+        .collect();
+    let expiring: Vec<&Bytes> = found
+        .iter()
+        .filter(|field| hash.expires_at(*field).is_some())
+        .collect();
+
+    match expiry {
+        Some(FieldExpiry::At(expires_at)) if expires_at <= current_timestamp_ms() => {
+            delete_fields(&mut db_write, &key_name, &found)?;
+        }
+        Some(FieldExpiry::At(expires_at)) if !found.is_empty() => {
+            for field in &found {
+                db_write.set_field_expires_at(&key_name, field, Some(expires_at));
+            }
+            db_write.notify(NotifyFlags::HASH, "hexpire", key_name);
+        }
+        Some(FieldExpiry::Persist) if !expiring.is_empty() => {
+            for field in expiring {
+                db_write.set_field_expires_at(&key_name, field, None);
+            }
+            db_write.notify(NotifyFlags::HASH, "hpersist", key_name);
+        }
+        _ => {}
+    }
+
+    Ok(ZystResponse::Array(
+        values
+            .into_iter()
+            .map(|value| value.map_or(ZystResponse::Nil, ZystResponse::BulkString))
+            .collect(),
+    ))
+}
+
+/// Logs the expiry HGETEX gave the fields it found as an HPEXPIREAT or an
+/// HPERSIST, nothing is logged when it only read them
+pub fn propagate_hgetex(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    let (
+        Ok(CommandArgs::HashGetEx {
+            key,
+            expiry,
+            fields,
+        }),
+        ZystResponse::Array(values),
+    ) = (build_hgetex_command(&argv[1..]), response)
+    else {
+        return Vec::new();
+    };
+
+    let found: Vec<Bytes> = fields
+        .into_iter()
+        .zip(values)
+        .filter(|(_, value)| !matches!(value, ZystResponse::Nil))
+        .map(|(field, _)| field)
+        .collect();
+
+    match expiry {
+        Some(FieldExpiry::At(expires_at)) => hpexpireat(key, expires_at, found),
+        Some(FieldExpiry::Persist) => {
+            with_fields(vec![Bytes::from_static(b"HPERSIST"), key], found)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// HSETEX sets all the fields or none of them: with FNX only when none of
+/// them exists, with FXX only when all of them do. The fields lose their
+/// expiry unless it's given or KEEPTTL keeps it.
+pub async fn hsetex(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, fields_exist, expiry, fields) = match command.args {
+        CommandArgs::HashSetEx {
+            key,
+            fields_exist,
+            expiry,
+            fields,
+        } => (key, fields_exist, expiry, fields),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+
+    let existing = match db_write.get(&key_name) {
+        Some(DbValue::HashKey(hash)) => fields
+            .keys()
+            .filter(|field| hash.data.contains_key(*field))
+            .count(),
+        Some(_) => return Err(ZystError::WrongType),
+        None => 0,
+    };
+    match fields_exist {
+        Some(true) if existing < fields.len() => return Ok(ZystResponse::Int(0)),
+        Some(false) if existing > 0 => return Ok(ZystResponse::Int(0)),
+        _ => {}
+    }
+
+    for (field, value) in &fields {
+        set_field(&mut db_write, &key_name, field.clone(), value.clone())?;
+    }
+    db_write.notify(NotifyFlags::HASH, "hset", key_name.clone());
+
+    let fields: Vec<Bytes> = fields.into_keys().collect();
+    match expiry {
+        Some(FieldExpiry::At(expires_at)) if expires_at <= current_timestamp_ms() => {
+            delete_fields(&mut db_write, &key_name, &fields)?;
+        }
+        Some(FieldExpiry::At(expires_at)) => {
+            for field in &fields {
+                db_write.set_field_expires_at(&key_name, field, Some(expires_at));
+            }
+            db_write.notify(NotifyFlags::HASH, "hexpire", key_name);
+        }
+        Some(FieldExpiry::KeepTtl) => {}
+        Some(FieldExpiry::Persist) | None => {
+            for field in &fields {
+                db_write.set_field_expires_at(&key_name, field, None);
+            }
+        }
+    }
+
+    Ok(ZystResponse::Int(1))
+}
+
+/// Logs HSETEX with an absolute PXAT, so replaying the AOF later doesn't
+/// push the expiry back. Nothing is logged when no field was set.
+pub fn propagate_hsetex(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    if !matches!(response, ZystResponse::Int(1)) {
+        return Vec::new();
+    }
+
+    let Ok(CommandArgs::HashSetEx {
+        key,
+        expiry,
+        fields,
+        ..
+    }) = build_hsetex_command(&argv[1..])
+    else {
+        return argv.to_vec();
+    };
+
+    let mut propagated = vec![Bytes::from_static(b"HSETEX"), key];
+    match expiry {
+        Some(FieldExpiry::At(expires_at)) => propagated.extend([
+            Bytes::from_static(b"PXAT"),
+            Bytes::from(expires_at.to_string()),
+        ]),
+        Some(FieldExpiry::KeepTtl) => propagated.push(Bytes::from_static(b"KEEPTTL")),
+        Some(FieldExpiry::Persist) | None => {}
+    }
+
+    propagated.push(Bytes::from_static(b"FIELDS"));
+    propagated.push(Bytes::from(fields.len().to_string()));
+    propagated.extend(fields.into_iter().flat_map(|(field, value)| [field, value]));
+    propagated
 }
@@ -0,0 +1,332 @@
+use crate::commands::registry::{resolve_command, Arg, ArgType, CommandSpec, COMMANDS};
+use crate::errors::ZystError;
+use crate::glob::string_match;
+use crate::response::ZystResponse;
+use crate::types::{Command, CommandArgs};
+use bytes::Bytes;
+
+const COMMAND_HELP: &[&str] = &[
+    "COMMAND <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "(no subcommand)",
+    "    Return details about all commands.",
+    "COUNT",
+    "    Return the total number of commands in this server.",
+    "LIST [FILTERBY (MODULE <module-name>|ACLCAT <category>|PATTERN <pattern>)]",
+    "    Return a list of all commands in this server.",
+    "INFO [<command-name> ...]",
+    "    Return details about multiple commands.",
+    "    If no command names are given, details for all commands are returned.",
+    "DOCS [<command-name> ...]",
+    "    Return documentation details about multiple commands.",
+    "    If no command names are given, documentation details for all",
+    "    commands are returned.",
+    "GETKEYS <full-command>",
+    "    Return the keys from a full command.",
+    "HELP",
+    "    Print this help.",
+];
+
+fn command_args(command: Command) -> Result<Vec<Bytes>, ZystError> {
+    match command.args {
+        CommandArgs::NoArgs => Ok(Vec::new()),
+        CommandArgs::MultipleKeys(args) => Ok(args),
+        _ => Err(ZystError::InvalidCommand),
+    }
+}
+
+fn bulk(value: &'static str) -> ZystResponse {
+    ZystResponse::BulkString(Bytes::from_static(value.as_bytes()))
+}
+
+fn status_set<I: IntoIterator<Item = String>>(values: I) -> ZystResponse {
+    ZystResponse::Set(values.into_iter().map(ZystResponse::SimpleString).collect())
+}
+
+// Accepts full names of subcommands, such as `command|info`
+fn find_command(name: &[u8]) -> Option<&'static CommandSpec> {
+    let argv = name
+        .splitn(2, |b| *b == b'|')
+        .map(Bytes::copy_from_slice)
+        .collect::<Vec<Bytes>>();
+
+    let spec = resolve_command(&argv).ok()?;
+    (spec.name_len() == argv.len()).then_some(spec)
+}
+
+fn all_commands() -> impl Iterator<Item = &'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .flat_map(|spec| std::iter::once(spec).chain(spec.subcommands.iter()))
+}
+
+fn key_specs(spec: &CommandSpec) -> ZystResponse {
+    let (begin_index, find_type, find_spec) = if spec.has_movable_keys() {
+        // The number of keys is the first argument the search finds, and
+        // the keys come right after it
+        let find_spec = ZystResponse::Map(vec![
+            (bulk("keynumidx"), ZystResponse::Int(0)),
+            (bulk("firstkey"), ZystResponse::Int(1)),
+            (bulk("keystep"), ZystResponse::Int(1)),
+        ]);
+        (spec.numkeys as i64, "keynum", find_spec)
+    } else if spec.first_key > 0 {
+        // `lastkey` is relative to the first key, or to the end when negative
+        let last_key = if spec.last_key < 0 {
+            spec.last_key
+        } else {
+            spec.last_key - spec.first_key
+        };
+        let find_spec = ZystResponse::Map(vec![
+            (bulk("lastkey"), ZystResponse::Int(last_key)),
+            (bulk("keystep"), ZystResponse::Int(spec.step)),
+            (bulk("limit"), ZystResponse::Int(0)),
+        ]);
+        (spec.first_key, "range", find_spec)
+    } else {
+        return ZystResponse::EmptyArray;
+    };
+
+    let flags = if spec.is_write() {
+        vec!["RW".to_string()]
+    } else {
+        vec!["RO".to_string(), "ACCESS".to_string()]
+    };
+
+    ZystResponse::Array(vec![ZystResponse::Map(vec![
+        (bulk("flags"), status_set(flags)),
+        (
+            bulk("begin_search"),
+            ZystResponse::Map(vec![
+                (bulk("type"), bulk("index")),
+                (
+                    bulk("spec"),
+                    ZystResponse::Map(vec![(bulk("index"), ZystResponse::Int(begin_index))]),
+                ),
+            ]),
+        ),
+        (
+            bulk("find_keys"),
+            ZystResponse::Map(vec![
+                (bulk("type"), bulk(find_type)),
+                (bulk("spec"), find_spec),
+            ]),
+        ),
+    ])])
+}
+
+fn command_info_reply(spec: &CommandSpec) -> ZystResponse {
+    let mut flags: Vec<String> = spec
+        .flags
+        .iter()
+        .map(|flag| flag.as_str().to_string())
+        .collect();
+    if spec.has_movable_keys() {
+        flags.push("movablekeys".to_string());
+    }
+
+    ZystResponse::Array(vec![
+        bulk(spec.name),
+        ZystResponse::Int(spec.arity),
+        status_set(flags),
+        ZystResponse::Int(spec.first_key),
+        ZystResponse::Int(spec.last_key),
+        ZystResponse::Int(spec.step),
+        status_set(
+            spec.acl_categories()
+                .into_iter()
+                .map(|category| format!("@{category}")),
+        ),
+        ZystResponse::Set(Vec::new()),
+        key_specs(spec),
+        ZystResponse::Array(spec.subcommands.iter().map(command_info_reply).collect()),
+    ])
+}
+
+fn arg_docs_reply(arg: &Arg) -> ZystResponse {
+    let mut entries = vec![
+        (
+            bulk("name"),
+            ZystResponse::BulkString(Bytes::from(arg.name.to_ascii_lowercase())),
+        ),
+        (bulk("type"), bulk(arg.kind.as_str())),
+    ];
+
+    match arg.kind {
+        ArgType::Key => {
+            entries.push((bulk("display_text"), bulk(arg.name)));
+            entries.push((bulk("key_spec_index"), ZystResponse::Int(0)));
+        }
+        ArgType::String
+        | ArgType::Integer
+        | ArgType::Double
+        | ArgType::Pattern
+        | ArgType::UnixTime => {
+            entries.push((bulk("display_text"), bulk(arg.name)));
+        }
+        ArgType::PureToken | ArgType::OneOf | ArgType::Block => {}
+    }
+
+    if let Some(token) = arg.token {
+        entries.push((bulk("token"), bulk(token)));
+    }
+
+    let mut flags = Vec::new();
+    if arg.optional {
+        flags.push("optional".to_string());
+    }
+    if arg.multiple {
+        flags.push("multiple".to_string());
+    }
+    if !flags.is_empty() {
+        entries.push((bulk("flags"), status_set(flags)));
+    }
+
+    if !arg.arguments.is_empty() {
+        entries.push((
+            bulk("arguments"),
+            ZystResponse::Array(arg.arguments.iter().map(arg_docs_reply).collect()),
+        ));
+    }
+
+    ZystResponse::Map(entries)
+}
+
+fn command_docs_reply(spec: &CommandSpec) -> ZystResponse {
+    let mut entries = vec![
+        (bulk("summary"), bulk(spec.summary)),
+        (bulk("since"), bulk(spec.since)),
+        (bulk("group"), bulk(spec.group.as_str())),
+        (bulk("complexity"), bulk(spec.complexity)),
+    ];
+
+    if !spec.arguments.is_empty() {
+        entries.push((
+            bulk("arguments"),
+            ZystResponse::Array(spec.arguments.iter().map(arg_docs_reply).collect()),
+        ));
+    }
+
+    if !spec.subcommands.is_empty() {
+        entries.push((
+            bulk("subcommands"),
+            ZystResponse::Map(
+                spec.subcommands
+                    .iter()
+                    .map(|sub| (bulk(sub.name), command_docs_reply(sub)))
+                    .collect(),
+            ),
+        ));
+    }
+
+    ZystResponse::Map(entries)
+}
+
+pub async fn command_count() -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Int(COMMANDS.len() as i64))
+}
+
+/// Replies with the details of the requested commands, or of all of them.
+/// Unknown commands get a nil entry.
+pub async fn command_info(command: Command) -> Result<ZystResponse, ZystError> {
+    let names = command_args(command)?;
+
+    if names.is_empty() {
+        return Ok(ZystResponse::Array(
+            COMMANDS.iter().map(command_info_reply).collect(),
+        ));
+    }
+
+    Ok(ZystResponse::Array(
+        names
+            .iter()
+            .map(|name| find_command(name).map_or(ZystResponse::Nil, command_info_reply))
+            .collect(),
+    ))
+}
+
+/// Replies with a map of command names to their documentation, unknown
+/// commands are left out
+pub async fn command_docs(command: Command) -> Result<ZystResponse, ZystError> {
+    let names = command_args(command)?;
+
+    let specs: Vec<&CommandSpec> = if names.is_empty() {
+        COMMANDS.iter().collect()
+    } else {
+        names.iter().filter_map(|name| find_command(name)).collect()
+    };
+
+    Ok(ZystResponse::Map(
+        specs
+            .into_iter()
+            .map(|spec| (bulk(spec.name), command_docs_reply(spec)))
+            .collect(),
+    ))
+}
+
+/// COMMAND LIST [FILTERBY <MODULE module-name | ACLCAT category | PATTERN pattern>]
+pub async fn command_list(command: Command) -> Result<ZystResponse, ZystError> {
+    let args = command_args(command)?;
+
+    let names = match args.as_slice() {
+        [] => all_commands().map(|spec| spec.name).collect(),
+        [filterby, kind, value] if filterby.eq_ignore_ascii_case(b"FILTERBY") => {
+            match kind.to_ascii_uppercase().as_slice() {
+                // Zyst has no modules
+                b"MODULE" => Vec::new(),
+                b"ACLCAT" => all_commands()
+                    .filter(|spec| {
+                        spec.acl_categories()
+                            .iter()
+                            .any(|category| category.as_bytes().eq_ignore_ascii_case(value))
+                    })
+                    .map(|spec| spec.name)
+                    .collect(),
+                b"PATTERN" => all_commands()
+                    .filter(|spec| string_match(value, spec.name.as_bytes(), true))
+                    .map(|spec| spec.name)
+                    .collect(),
+                _ => return Err(ZystError::Syntax),
+            }
+        }
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(ZystResponse::List(
+        names
+            .into_iter()
+            .map(|name: &'static str| Bytes::from_static(name.as_bytes()))
+            .collect(),
+    ))
+}
+
+/// Returns the keys of a full command, as the command table declares them
+pub async fn command_getkeys(command: Command) -> Result<ZystResponse, ZystError> {
+    let argv = command_args(command)?;
+
+    let spec = resolve_command(&argv)
+        .map_err(|_| ZystError::Custom("ERR Invalid command specified".to_string()))?;
+
+    if spec.check_arity(argv.len()).is_err() {
+        return Err(ZystError::Custom(
+            "ERR Invalid number of arguments specified for command".to_string(),
+        ));
+    }
+
+    let keys = spec.keys_of(&argv);
+    if keys.is_empty() {
+        return Err(ZystError::Custom(
+            "ERR The command has no key arguments".to_string(),
+        ));
+    }
+
+    Ok(ZystResponse::List(keys.into_iter().cloned().collect()))
+}
+
+pub async fn command_help() -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Array(
+        COMMAND_HELP
+            .iter()
+            .map(|line| ZystResponse::SimpleString(line.to_string()))
+            .collect(),
+    ))
+}
@@ -1,49 +1,180 @@
+use crate::commands::build::parse_expire_time;
+use crate::commands::registry::Context;
 use crate::errors::ZystError;
+use crate::glob::string_match;
+use crate::keys::current_timestamp_ms;
+use crate::keyspace::Keyspace;
+use crate::notify::{publish_events, NotifyFlags};
+use crate::parser::parse_number;
 use crate::response::ZystResponse;
-use crate::types::{Command, CommandArgs, Db, DbValue, Key};
-use regex::Regex;
+use crate::state::State;
+use crate::types::{Command, CommandArgs, Db, DbValue, Key, SetCondition, SetOptions};
+use bytes::Bytes;
 
 pub async fn get_key(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    let key_name = match &command.args {
-        CommandArgs::SingleKey(key) => &key.clone(),
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
         _ => return Err(ZystError::InvalidCommand),
     };
 
-    let key = {
-        let db_read = db.read().await;
-        db_read.get(key_name).cloned() // Clone key to release lock
+    let db_read = db.read().await;
+
+    match db_read.get(&key_name) {
+        Some(DbValue::StringKey(key)) => Ok(key
+            .data
+            .clone()
+            .map_or(ZystResponse::Nil, ZystResponse::BulkString)),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name);
+            Ok(ZystResponse::Nil)
+        }
+        Some(_) => Err(ZystError::WrongType),
+    }
+}
+
+/// SET with its NX/XX/IFEQ conditions, GET and expiration options. Replies
+/// with the previous value when GET is given, nil when a condition is not
+/// met, OK otherwise.
+pub async fn set_key(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (set, previous) = set_with_options(db, command).await?;
+
+    match previous {
+        Some(previous) => Ok(previous.map_or(ZystResponse::Nil, ZystResponse::BulkString)),
+        None if set => Ok(ZystResponse::Ok),
+        None => Ok(ZystResponse::Nil),
+    }
+}
+
+/// Sets the key only if it does not exist, replies with 1 if it was set
+pub async fn setnx(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (set, _) = set_with_options(db, command).await?;
+    Ok(ZystResponse::Int(set as i64))
+}
+
+// Returns whether the value was set and, when GET was given, the previous
+// value of the key
+async fn set_with_options(
+    db: &Db,
+    command: Command,
+) -> Result<(bool, Option<Option<Bytes>>), ZystError> {
+    let (key_name, value, options) = match command.args {
+        CommandArgs::Set {
+            key,
+            value,
+            options,
+        } => (key, value, options),
+        CommandArgs::KeyWithValue { key, value } => (key, value, SetOptions::default()),
+        _ => return Err(ZystError::InvalidCommand),
     };
 
-    let key = match key {
-        Some(DbValue::StringKey(k)) => k,
-        None => return Ok(ZystResponse::Nil),
-        Some(_) => return Err(ZystError::WrongType),
+    let mut db_write = db.write().await;
+
+    let existing = db_write.get(&key_name);
+    let current = match existing {
+        Some(DbValue::StringKey(key)) => Some(key.data.clone()),
+        Some(_) if options.get || matches!(options.condition, Some(SetCondition::IFEQ(_))) => {
+            return Err(ZystError::WrongType)
+        }
+        _ => None,
     };
 
-    if let Some(value) = &key.data {
-        let deleted = delete_expired_key(db, key.clone()).await; // No read lock at this point
+    let set = match &options.condition {
+        None => true,
+        Some(SetCondition::NX) => existing.is_none(),
+        Some(SetCondition::XX) => existing.is_some(),
+        Some(SetCondition::IFEQ(expected)) => {
+            matches!(&current, Some(Some(current)) if current == expected)
+        }
+    };
 
-        if !deleted {
-            return Ok(ZystResponse::SimpleString(value.to_string()));
+    let previous = options.get.then(|| current.flatten());
+
+    if set {
+        let expires_at = if options.keep_ttl {
+            existing.and_then(DbValue::expires_at)
+        } else {
+            options.expires_at
+        };
+
+        let key = Key::new(key_name.clone(), Some(value), expires_at);
+        db_write.insert(key_name.clone(), DbValue::StringKey(key));
+        db_write.notify(NotifyFlags::STRING, "set", key_name.clone());
+        if options.expires_at.is_some() {
+            db_write.notify(NotifyFlags::GENERIC, "expire", key_name);
         }
     }
 
-    Ok(ZystResponse::Nil)
+    Ok((set, previous))
 }
 
-pub async fn set_key(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    let (key_name, value) = match command.args {
-        CommandArgs::KeyWithValue { key, value } => (key, value),
-        _ => return Err(ZystError::InvalidCommand),
-    };
+/// Logs relative expirations as an absolute `PXAT`, so that replaying the
+/// AOF does not extend them. A SET whose condition didn't hold is not logged.
+pub fn propagate_set(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    let with_get = argv[3..]
+        .iter()
+        .any(|option| option.eq_ignore_ascii_case(b"GET"));
+    if matches!(response, ZystResponse::Nil) && !with_get {
+        return Vec::new();
+    }
 
-    let key = Key::new(key_name.clone(), Some(value.clone()), None);
+    let mut propagated = argv[..3].to_vec();
+    let mut i = 3;
 
-    db.write()
-        .await
-        .insert(key.name.clone(), DbValue::StringKey(key));
+    while i < argv.len() {
+        let option = argv[i].to_ascii_uppercase();
+
+        match (option.as_slice(), argv.get(i + 1)) {
+            (b"EX" | b"PX", Some(value)) => {
+                propagated.push(Bytes::from_static(b"PXAT"));
+                propagated.push(absolute_expire_time(&option, value));
+                i += 2;
+            }
+            // Options taking a value are copied with it
+            (b"IFEQ" | b"EXAT" | b"PXAT", Some(value)) => {
+                propagated.extend([argv[i].clone(), value.clone()]);
+                i += 2;
+            }
+            _ => {
+                propagated.push(argv[i].clone());
+                i += 1;
+            }
+        }
+    }
 
-    Ok(ZystResponse::Ok)
+    propagated
+}
+
+/// Logs a command as is, unless it replied 0 because it changed nothing,
+/// as SETNX does when the key already exists
+pub fn propagate_if_applied(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    if matches!(response, ZystResponse::Int(0)) {
+        return Vec::new();
+    }
+
+    argv.to_vec()
+}
+
+/// SETEX and PSETEX are logged as a SET with an absolute `PXAT`
+pub fn propagate_setex(argv: &[Bytes], _response: &ZystResponse) -> Vec<Bytes> {
+    let unit: &[u8] = if argv[0].eq_ignore_ascii_case(b"PSETEX") {
+        b"PX"
+    } else {
+        b"EX"
+    };
+
+    vec![
+        Bytes::from_static(b"SET"),
+        argv[1].clone(),
+        argv[3].clone(),
+        Bytes::from_static(b"PXAT"),
+        absolute_expire_time(unit, &argv[2]),
+    ]
+}
+
+// The command already ran successfully, so the value is known to be valid
+fn absolute_expire_time(unit: &[u8], value: &[u8]) -> Bytes {
+    let expires_at = parse_expire_time(unit, value, "set").unwrap_or_default();
+    Bytes::from(expires_at.to_string())
 }
 
 pub async fn delete_key(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
@@ -58,6 +189,7 @@ pub async fn delete_key(db: &Db, command: Command) -> Result<ZystResponse, ZystE
 
     for key in keys {
         if db_write.swap_remove(&key).is_some() {
+            db_write.notify(NotifyFlags::GENERIC, "del", key);
             deleted_count += 1;
         }
     }
@@ -65,6 +197,179 @@ pub async fn delete_key(db: &Db, command: Command) -> Result<ZystResponse, ZystE
     Ok(ZystResponse::Int(deleted_count))
 }
 
+/// Values costing more than this to free are dropped off the request path
+/// by UNLINK
+const LAZYFREE_THRESHOLD: usize = 64;
+
+/// Like DEL, but large values are freed in the background
+pub async fn unlink(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let keys = match command.args {
+        CommandArgs::MultipleKeys(keys) => keys,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let removed = {
+        let mut db_write = db.write().await;
+        let mut removed = Vec::new();
+        for key in keys {
+            if let Some(value) = db_write.swap_remove(&key) {
+                db_write.notify(NotifyFlags::GENERIC, "del", key);
+                removed.push(value);
+            }
+        }
+        removed
+    };
+
+    let count = removed.len() as i64;
+    if removed.iter().map(DbValue::free_effort).sum::<usize>() > LAZYFREE_THRESHOLD {
+        tokio::task::spawn_blocking(move || drop(removed));
+    }
+
+    Ok(ZystResponse::Int(count))
+}
+
+/// Replies with the type of the value stored at key, `none` when missing
+pub async fn key_type(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let name = db_read.get(&key_name).map_or("none", DbValue::type_name);
+
+    Ok(ZystResponse::SimpleString(name.to_string()))
+}
+
+pub async fn rename(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    rename_key(db, command, false).await
+}
+
+pub async fn renamenx(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    rename_key(db, command, true).await
+}
+
+// Moves the value and its TTL to the new name, overwriting the destination
+// unless `nx` is set. RENAME replies OK, RENAMENX whether it renamed.
+async fn rename_key(db: &Db, command: Command, nx: bool) -> Result<ZystResponse, ZystError> {
+    let (key_name, new_name) = match command.args {
+        CommandArgs::KeyWithValue { key, value } => (key, value),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+
+    if !db_write.contains_key(&key_name) {
+        return Err(ZystError::Custom("ERR no such key".to_string()));
+    }
+
+    let renamed = if key_name == new_name {
+        !nx
+    } else if nx && db_write.contains_key(&new_name) {
+        false
+    } else if let Some(mut value) = db_write.swap_remove(&key_name) {
+        value.set_name(new_name.clone());
+        db_write.insert(new_name.clone(), value);
+        db_write.notify(NotifyFlags::GENERIC, "rename_from", key_name);
+        db_write.notify(NotifyFlags::GENERIC, "rename_to", new_name);
+        true
+    } else {
+        false
+    };
+
+    if nx {
+        Ok(ZystResponse::Int(renamed as i64))
+    } else {
+        Ok(ZystResponse::Ok)
+    }
+}
+
+/// Copies a key and its TTL into the selected database, or into the one given with DB
+pub async fn copy(
+    state: &State,
+    current: usize,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let (source, destination, db_index, replace) = match command.args {
+        CommandArgs::Copy {
+            source,
+            destination,
+            db,
+            replace,
+        } => (source, destination, db, replace),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_index = db_index.unwrap_or(current);
+    if state.db(db_index).is_none() {
+        return Err(ZystError::Custom(
+            "ERR DB index is out of range".to_string(),
+        ));
+    }
+
+    if db_index == current && source == destination {
+        return Err(ZystError::Custom(
+            "ERR source and destination objects are the same".to_string(),
+        ));
+    }
+
+    let copied = if db_index == current {
+        let db = state.db(current).ok_or(ZystError::DatabaseError)?;
+        let mut db_write = db.write().await;
+        let value = db_write.get(&source).cloned();
+        copy_into(&mut db_write, value, destination, replace)
+    } else {
+        let (source_db, mut destination_db) = state
+            .write_pair(current, db_index)
+            .await
+            .ok_or(ZystError::DatabaseError)?;
+        let value = source_db.get(&source).cloned();
+        let copied = copy_into(&mut destination_db, value, destination, replace);
+        // The dispatcher only publishes the events of the selected database
+        publish_events(state, db_index, &destination_db);
+        copied
+    };
+
+    Ok(ZystResponse::Int(copied as i64))
+}
+
+// Stores a copy of the source value, keeping its TTL, under the
+// destination name. An existing destination is only overwritten with
+// REPLACE.
+fn copy_into(
+    keyspace: &mut Keyspace,
+    value: Option<DbValue>,
+    destination: Bytes,
+    replace: bool,
+) -> bool {
+    let Some(mut value) = value else {
+        return false;
+    };
+
+    if !replace && keyspace.contains_key(&destination) {
+        return false;
+    }
+
+    value.set_name(destination.clone());
+    keyspace.insert(destination.clone(), value);
+    keyspace.notify(NotifyFlags::GENERIC, "copy_to", destination);
+    true
+}
+
+pub async fn random_key(db: &Db) -> Result<ZystResponse, ZystError> {
+    let db_read = db.read().await;
+
+    Ok(db_read.random_key().map_or(ZystResponse::Nil, |key| {
+        ZystResponse::BulkString(key.clone())
+    }))
+}
+
+/// Replies with how many of the keys exist. Zyst doesn't track access
+/// times, so there is nothing else to update.
+pub async fn touch(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    exists(db, command).await
+}
+
 // Increases the numeric value stored at the key by one.
 // If the key does not exist, it is initialized to 0 before
 // applying the operation. Returns an error if the key holds
@@ -95,9 +400,9 @@ pub async fn incrby(db: &Db, command: Command) -> Result<ZystResponse, ZystError
         _ => return Err(ZystError::InvalidCommand),
     };
 
-    let by = match by_str.parse::<i64>() {
-        Ok(num) => num,
-        Err(_) => return Err(ZystError::NotInt),
+    let by = match parse_number::<i64>(&by_str) {
+        Some(num) => num,
+        None => return Err(ZystError::NotInt),
     };
 
     let mut db_write = db.write().await;
@@ -109,7 +414,7 @@ pub async fn incrby(db: &Db, command: Command) -> Result<ZystResponse, ZystError
                 key_name.clone(),
                 DbValue::StringKey(Key {
                     name: key_name.clone(),
-                    data: Some("0".to_string()),
+                    data: Some(Bytes::from_static(b"0")),
                     ..Default::default()
                 }),
             );
@@ -121,15 +426,17 @@ pub async fn incrby(db: &Db, command: Command) -> Result<ZystResponse, ZystError
         Some(_) => return Err(ZystError::WrongType),
     };
 
-    let num_str = key.data.as_deref().unwrap_or("0");
+    let num_str = key.data.as_deref().unwrap_or(b"0");
 
-    let num = match num_str.parse::<i64>() {
-        Ok(n) => n,
-        Err(_) => return Err(ZystError::NotInt),
+    let num = match parse_number::<i64>(num_str) {
+        Some(n) => n,
+        None => return Err(ZystError::NotInt),
     };
 
     let new_value = num + by;
-    key.data = Some(new_value.to_string());
+    key.data = Some(Bytes::from(new_value.to_string()));
+    db_write.signal_modified(&key_name);
+    db_write.notify(NotifyFlags::STRING, "incrby", key_name);
 
     Ok(ZystResponse::Int(new_value))
 }
@@ -145,7 +452,7 @@ async fn incr_decr(db: &Db, command: Command, inc: bool) -> Result<ZystResponse,
     let key = match db_write.get_mut(&key_name) {
         Some(DbValue::StringKey(key)) => key,
         None => {
-            let key = Key::new(key_name.clone(), Some("0".to_string()), None);
+            let key = Key::new(key_name.clone(), Some(Bytes::from_static(b"0")), None);
             db_write.insert(key_name.clone(), DbValue::StringKey(key));
             match db_write.get_mut(&key_name) {
                 Some(DbValue::StringKey(key)) => key,
@@ -156,37 +463,32 @@ async fn incr_decr(db: &Db, command: Command, inc: bool) -> Result<ZystResponse,
         Some(_) => return Err(ZystError::WrongType),
     };
 
-    let Ok(num) = key.data.as_deref().unwrap_or("0").parse::<i64>() else {
+    let Some(num) = parse_number::<i64>(key.data.as_deref().unwrap_or(b"0")) else {
         return Err(ZystError::NotInt);
     };
 
     let new_value = if inc { num + 1 } else { num - 1 };
 
-    key.data = Some(new_value.to_string());
+    key.data = Some(Bytes::from(new_value.to_string()));
+    db_write.signal_modified(&key_name);
+    db_write.notify(NotifyFlags::STRING, "incrby", key_name);
 
     Ok(ZystResponse::Int(new_value))
 }
 
-/// Returns keys matching the Redis-style pattern
+/// Returns keys matching the Redis-style glob pattern
 pub async fn get_keys(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
     let pattern = match &command.args {
         CommandArgs::SingleKey(key) => key,
         _ => return Err(ZystError::InvalidCommand),
     };
 
-    // Convert Redis glob pattern to regex
-    let regex_pattern = convert_redis_pattern_to_regex(pattern);
-    let re = match Regex::new(&regex_pattern) {
-        Ok(re) => re,
-        Err(_) => return Err(ZystError::RegexError),
-    };
-
     let mut results = vec![];
 
     let db_read = db.read().await;
 
     for key in db_read.keys() {
-        if re.is_match(key) {
+        if string_match(pattern, key, false) {
             results.push(key.clone());
         }
     }
@@ -200,7 +502,7 @@ pub async fn get_keys(db: &Db, command: Command) -> Result<ZystResponse, ZystErr
 
 pub async fn exists(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
     let keys = match &command.args {
-        CommandArgs::SingleKey(key) => vec![key.to_string()],
+        CommandArgs::SingleKey(key) => vec![key.clone()],
         CommandArgs::MultipleKeys(keys) => keys.to_vec(),
         _ => return Err(ZystError::InvalidCommand),
     };
@@ -211,38 +513,82 @@ pub async fn exists(db: &Db, command: Command) -> Result<ZystResponse, ZystError
     Ok(ZystResponse::Int(nb_keys))
 }
 
-pub async fn expire(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    let (key_name, ttl) = match command.args {
-        CommandArgs::KeyWithValue { key, value } => (key, value),
+/// Shared by EXPIRE, PEXPIRE, EXPIREAT and PEXPIREAT, whose builders all
+/// resolve the new expiry to a Unix time in milliseconds. An expiry that is
+/// applied is logged as that absolute PEXPIREAT, so replaying the AOF later
+/// doesn't push it back.
+pub async fn expire(
+    ctx: &mut Context<'_>,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let (key_name, expires_at, conditions) = match command.args {
+        CommandArgs::Expire {
+            key,
+            expires_at,
+            conditions,
+        } => (key, expires_at, conditions),
         _ => return Err(ZystError::InvalidCommand),
     };
 
-    let ttl = ttl.parse::<i64>().map_err(|_| ZystError::TTL)?;
+    let mut db_write = ctx.db.write().await;
+
+    let current = match db_write.get_mut(&key_name) {
+        Some(value) => value.expires_at(),
+        None => return Ok(ZystResponse::Int(0)),
+    };
+
+    if !conditions
+        .iter()
+        .all(|condition| condition.holds(current, expires_at))
+    {
+        return Ok(ZystResponse::Int(0));
+    }
+
+    // An expiry in the past deletes the key right away
+    if expires_at <= current_timestamp_ms() {
+        db_write.swap_remove(&key_name);
+        db_write.notify(NotifyFlags::GENERIC, "del", key_name.clone());
+    } else {
+        db_write.set_expires_at(&key_name, Some(expires_at));
+        db_write.notify(NotifyFlags::GENERIC, "expire", key_name.clone());
+    }
+
+    let pexpireat = vec![
+        Bytes::from_static(b"PEXPIREAT"),
+        key_name,
+        Bytes::from(expires_at.to_string()),
+    ];
+    ctx.propagated.push((ctx.client.db, pexpireat));
+
+    Ok(ZystResponse::Int(1))
+}
+
+pub async fn persist(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
+    };
 
     let mut db_write = db.write().await;
 
-    match db_write.get_mut(&key_name) {
-        Some(DbValue::StringKey(key)) => {
-            key.set_ttl(ttl);
+    match db_write.get(&key_name) {
+        Some(value) if value.expires_at().is_some() => {
+            db_write.set_expires_at(&key_name, None);
+            db_write.notify(NotifyFlags::GENERIC, "persist", key_name);
             Ok(ZystResponse::Int(1))
         }
-        Some(DbValue::ListKey(key)) => {
-            key.set_ttl(ttl);
-            Ok(ZystResponse::Int(1))
-        }
-        Some(DbValue::SetKey(key)) => {
-            key.set_ttl(ttl);
-            Ok(ZystResponse::Int(1))
-        }
-        Some(DbValue::HashKey(key)) => {
-            key.set_ttl(ttl);
-            Ok(ZystResponse::Int(1))
-        }
-        None => Ok(ZystResponse::Int(0)),
+        _ => Ok(ZystResponse::Int(0)),
     }
 }
 
-pub async fn ttl(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+// TTL, PTTL, EXPIRETIME and PEXPIRETIME all reply -2 for a missing key
+// and -1 for a key without expiry, and only differ in how they format
+// the expiry time
+async fn key_expiry(
+    db: &Db,
+    command: Command,
+    format: fn(i64) -> i64,
+) -> Result<ZystResponse, ZystError> {
     let key_name = match command.args {
         CommandArgs::SingleKey(key) => key,
         _ => return Err(ZystError::InvalidCommand),
@@ -250,44 +596,32 @@ pub async fn ttl(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
 
     let db_read = db.read().await;
 
-    let key = match db_read.get(&key_name) {
-        Some(DbValue::StringKey(key)) => key,
-        None => return Ok(ZystResponse::Int(-2)),
-        Some(_) => return Err(ZystError::WrongType),
+    let reply = match db_read.get(&key_name) {
+        Some(value) => value.expires_at().map_or(-1, format),
+        None => -2,
     };
 
-    Ok(ZystResponse::Int(key.get_ttl()))
+    Ok(ZystResponse::Int(reply))
 }
 
-/// Converts Redis-style glob pattern into a valid regex pattern
-// '*' becomes '.*'
-// '?' becomes '.'
-// '[' stays '[' (range starts)
-// ']' stays ']' (range ends)
-pub fn convert_redis_pattern_to_regex(pattern: &str) -> String {
-    let mut regex_pattern = String::from("^");
-
-    for c in pattern.chars() {
-        match c {
-            '*' => regex_pattern.push_str(".*"),
-            '?' => regex_pattern.push('.'),
-            '[' => regex_pattern.push('['),
-            ']' => regex_pattern.push(']'),
-            _ => regex_pattern.push_str(&regex::escape(&c.to_string())), // Escape other chars
-        }
-    }
-
-    regex_pattern.push('$');
-    regex_pattern
+pub async fn ttl(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    key_expiry(db, command, |expires_at| {
+        (expires_at - current_timestamp_ms() + 500).div_euclid(1000)
+    })
+    .await
 }
 
-pub async fn delete_expired_key(db: &Db, key: Key) -> bool {
-    let mut db_write = db.write().await;
+pub async fn pttl(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    key_expiry(db, command, |expires_at| {
+        expires_at - current_timestamp_ms()
+    })
+    .await
+}
 
-    if key.is_expired() {
-        db_write.swap_remove(&key.name);
-        return true;
-    }
+pub async fn expiretime(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    key_expiry(db, command, |expires_at| expires_at.div_euclid(1000)).await
+}
 
-    false
+pub async fn pexpiretime(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    key_expiry(db, command, |expires_at| expires_at).await
 }
@@ -1,61 +1,162 @@
+use crate::blocking::Blocked;
+use crate::client::Client;
+use crate::commands::build::{
+    build_blmove_command, build_blmpop_command, build_blpop_command, build_brpop_command,
+    build_lmove_command, build_lmpop_command, build_rpoplpush_command,
+};
 use crate::errors::ZystError;
+use crate::keyspace::Keyspace;
+use crate::notify::{publish_events, NotifyFlags};
+use crate::parser::parse_number;
 use crate::response::ZystResponse;
+use crate::state::State;
 use crate::types::{Command, CommandArgs, Db, DbValue, KeyList, ListPushType, PopType};
+use bytes::Bytes;
+use std::ops::Range;
+use tokio::time::Instant;
 
-async fn push_to_list(
-    db: &Db,
-    command: Command,
+// Pushes elements to an end of a list, created when the key doesn't exist.
+// Returns the length of the list.
+fn push_elements(
+    keyspace: &mut Keyspace,
+    key: &Bytes,
+    values: Vec<Bytes>,
     push_type: ListPushType,
-) -> Result<ZystResponse, ZystError> {
-    let (key_name, values) = match command.args {
-        CommandArgs::KeyWithValues { key, values } => (key, values),
-        _ => return Err(ZystError::InvalidCommand),
-    };
-
-    let mut new_values = values;
-
-    let mut db_write = db.write().await;
-
-    match db_write.get_mut(&key_name) {
+) -> Result<usize, ZystError> {
+    let len = match keyspace.get_mut(key) {
         Some(DbValue::ListKey(existing_list)) => {
             match push_type {
                 ListPushType::LPUSH => {
-                    for value in new_values.into_iter().rev() {
+                    for value in values {
                         existing_list.data.push_front(value);
                     }
                 }
                 ListPushType::RPUSH => {
-                    existing_list.data.extend(new_values);
+                    existing_list.data.extend(values);
                 }
             }
-            let nb = existing_list.data.len() as i64;
-            Ok(ZystResponse::Int(nb))
+            existing_list.data.len()
         }
         None => {
+            let mut new_values = values;
             if let ListPushType::LPUSH = push_type {
                 new_values.reverse();
             }
-            db_write.insert(
-                key_name.clone(),
+            let len = new_values.len();
+            keyspace.insert(
+                key.clone(),
                 DbValue::ListKey(KeyList {
-                    name: key_name,
-                    data: new_values.clone().into(),
+                    name: key.clone(),
+                    data: new_values.into(),
                     ..Default::default()
                 }),
             );
-            let nb = new_values.len() as i64;
-            Ok(ZystResponse::Int(nb))
+            len
         }
-        Some(_) => Err(ZystError::WrongType),
+        Some(_) => return Err(ZystError::WrongType),
+    };
+
+    let event = match push_type {
+        ListPushType::LPUSH => "lpush",
+        ListPushType::RPUSH => "rpush",
+    };
+    keyspace.signal_modified(key);
+    keyspace.notify(NotifyFlags::LIST, event, key.clone());
+
+    Ok(len)
+}
+
+async fn push_to_list(
+    state: &State,
+    db_index: usize,
+    command: Command,
+    push_type: ListPushType,
+    only_existing: bool,
+) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db = state.db(db_index).ok_or(ZystError::DatabaseError)?;
+    let mut db_write = db.write().await;
+
+    if only_existing && db_write.get(&key_name).is_none() {
+        return Ok(ZystResponse::Int(0));
     }
+
+    let len = push_elements(&mut db_write, &key_name, values, push_type)?;
+    state.blocking().signal(db_index, &key_name);
+
+    Ok(ZystResponse::Int(len as i64))
 }
 
-pub async fn lpush(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    push_to_list(db, command, ListPushType::LPUSH).await
+pub async fn lpush(
+    state: &State,
+    db_index: usize,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    push_to_list(state, db_index, command, ListPushType::LPUSH, false).await
 }
 
-pub async fn rpush(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    push_to_list(db, command, ListPushType::RPUSH).await
+pub async fn rpush(
+    state: &State,
+    db_index: usize,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    push_to_list(state, db_index, command, ListPushType::RPUSH, false).await
+}
+
+pub async fn lpushx(
+    state: &State,
+    db_index: usize,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    push_to_list(state, db_index, command, ListPushType::LPUSH, true).await
+}
+
+pub async fn rpushx(
+    state: &State,
+    db_index: usize,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    push_to_list(state, db_index, command, ListPushType::RPUSH, true).await
+}
+
+// Position of an index in a list of `len` elements, negative indexes count
+// from the tail. None when out of range.
+fn list_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index.checked_add(len as i64)?
+    } else {
+        index
+    };
+    usize::try_from(index).ok().filter(|index| *index < len)
+}
+
+// Positions between two inclusive indexes, clamped to a list of `len`
+// elements. None when the range is empty.
+fn list_range(start: i64, stop: i64, len: usize) -> Option<Range<usize>> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+
+    if start > stop || start >= len {
+        return None;
+    }
+    Some(start as usize..stop as usize + 1)
+}
+
+fn parse_index(value: &[u8]) -> Result<i64, ZystError> {
+    parse_number::<i64>(value).ok_or(ZystError::NotIntOrOutOfRange)
 }
 
 pub async fn lrange(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
@@ -64,106 +165,631 @@ pub async fn lrange(db: &Db, command: Command) -> Result<ZystResponse, ZystError
         _ => return Err(ZystError::InvalidCommand),
     };
 
-    let min: isize = match values[0].parse::<isize>() {
-        Ok(val) => val,
-        Err(_) => return Err(ZystError::NotIntOrOutOfRange),
+    let start = parse_index(&values[0])?;
+    let stop = parse_index(&values[1])?;
+
+    let db_read = db.read().await;
+
+    let key = match db_read.get(&key_name) {
+        Some(DbValue::ListKey(key)) => key,
+        Some(_) => return Err(ZystError::WrongType),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name);
+            return Ok(ZystResponse::EmptyArray);
+        }
     };
 
-    let max: isize = match values[1].parse::<isize>() {
-        Ok(val) => val,
-        Err(_) => return Err(ZystError::NotIntOrOutOfRange),
+    match list_range(start, stop, key.data.len()) {
+        Some(range) => Ok(ZystResponse::List(key.data.range(range).cloned().collect())),
+        None => Ok(ZystResponse::EmptyArray),
+    }
+}
+
+pub async fn llen(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
     };
 
     let db_read = db.read().await;
 
+    match db_read.get(&key_name) {
+        Some(DbValue::ListKey(key)) => Ok(ZystResponse::Int(key.data.len() as i64)),
+        Some(_) => Err(ZystError::WrongType),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name);
+            Ok(ZystResponse::Int(0))
+        }
+    }
+}
+
+pub async fn lindex(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, value) = match command.args {
+        CommandArgs::KeyWithValue { key, value } => (key, value),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let index = parse_index(&value)?;
+    let db_read = db.read().await;
+
     let key = match db_read.get(&key_name) {
         Some(DbValue::ListKey(key)) => key,
-        Some(DbValue::StringKey(_)) => return Err(ZystError::WrongType),
-        _ => return Ok(ZystResponse::EmptyArray),
+        Some(_) => return Err(ZystError::WrongType),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name);
+            return Ok(ZystResponse::Nil);
+        }
     };
 
+    Ok(list_index(index, key.data.len())
+        .and_then(|index| key.data.get(index))
+        .map_or(ZystResponse::Nil, |element| {
+            ZystResponse::BulkString(element.clone())
+        }))
+}
+
+pub async fn lset(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let index = parse_index(&values[0])?;
+    let mut db_write = db.write().await;
+
+    let key = match db_write.get_mut(&key_name) {
+        Some(DbValue::ListKey(key)) => key,
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Err(ZystError::Custom("ERR no such key".to_string())),
+    };
+
+    let Some(element) = list_index(index, key.data.len()).and_then(|i| key.data.get_mut(i))
+    else {
+        return Err(ZystError::Custom("ERR index out of range".to_string()));
+    };
+    *element = values[1].clone();
+
+    db_write.signal_modified(&key_name);
+    db_write.notify(NotifyFlags::LIST, "lset", key_name);
+    Ok(ZystResponse::Ok)
+}
+
+/// LINSERT replies with the length of the list, -1 when the pivot isn't
+/// found and 0 when the key doesn't exist
+pub async fn linsert(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, before, pivot, element) = match command.args {
+        CommandArgs::ListInsert {
+            key,
+            before,
+            pivot,
+            element,
+        } => (key, before, pivot, element),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+
+    let key = match db_write.get_mut(&key_name) {
+        Some(DbValue::ListKey(key)) => key,
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(ZystResponse::Int(0)),
+    };
+
+    let Some(position) = key.data.iter().position(|value| *value == pivot) else {
+        return Ok(ZystResponse::Int(-1));
+    };
+    let position = if before { position } else { position + 1 };
+    key.data.insert(position, element);
     let len = key.data.len();
 
-    let min = if min >= 0 {
-        min as usize
-    } else {
-        (len as isize + min).max(0) as usize
+    db_write.signal_modified(&key_name);
+    db_write.notify(NotifyFlags::LIST, "linsert", key_name);
+    Ok(ZystResponse::Int(len as i64))
+}
+
+/// LREM removes the first `count` occurrences of an element, the last ones
+/// when negative and all of them when zero
+pub async fn lrem(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
     };
 
-    let max = if max >= 0 {
-        (max + 1).min(len as isize) as usize
+    let count = parse_index(&values[0])?;
+    let element = &values[1];
+    let mut db_write = db.write().await;
+
+    let key = match db_write.get_mut(&key_name) {
+        Some(DbValue::ListKey(key)) => key,
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(ZystResponse::Int(0)),
+    };
+
+    let limit = match count {
+        0 => usize::MAX,
+        count => usize::try_from(count.unsigned_abs()).unwrap_or(usize::MAX),
+    };
+    // From the tail, the first matches are kept
+    let skipped = if count < 0 {
+        let matches = key.data.iter().filter(|value| *value == element).count();
+        matches.saturating_sub(limit)
     } else {
-        (len as isize + max + 1).max(0).min(len as isize) as usize
+        0
     };
 
-    if min >= max || min >= len {
-        return Ok(ZystResponse::EmptyArray);
+    let len = key.data.len();
+    let mut seen = 0;
+    key.data.retain(|value| {
+        if value != element {
+            return true;
+        }
+        seen += 1;
+        seen <= skipped || seen - skipped > limit
+    });
+    let removed = len - key.data.len();
+    let emptied = key.data.is_empty();
+
+    if removed > 0 {
+        db_write.signal_modified(&key_name);
+        db_write.notify(NotifyFlags::LIST, "lrem", key_name.clone());
+    }
+    if emptied {
+        db_write.swap_remove(&key_name);
+        db_write.notify(NotifyFlags::GENERIC, "del", key_name);
     }
 
-    let results: Vec<String> = key.data.range(min..max).cloned().collect();
+    Ok(ZystResponse::Int(removed as i64))
+}
+
+pub async fn ltrim(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, values) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let start = parse_index(&values[0])?;
+    let stop = parse_index(&values[1])?;
+    let mut db_write = db.write().await;
 
-    if results.is_empty() {
-        return Ok(ZystResponse::EmptyArray);
+    let key = match db_write.get_mut(&key_name) {
+        Some(DbValue::ListKey(key)) => key,
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(ZystResponse::Ok),
+    };
+
+    match list_range(start, stop, key.data.len()) {
+        Some(range) => {
+            key.data.truncate(range.end);
+            key.data.drain(..range.start);
+        }
+        None => key.data.clear(),
     }
+    let emptied = key.data.is_empty();
 
-    Ok(ZystResponse::List(results.to_vec()))
+    db_write.signal_modified(&key_name);
+    db_write.notify(NotifyFlags::LIST, "ltrim", key_name.clone());
+    if emptied {
+        db_write.swap_remove(&key_name);
+        db_write.notify(NotifyFlags::GENERIC, "del", key_name);
+    }
+
+    Ok(ZystResponse::Ok)
+}
+
+/// LPOS replies with the position of the first match from the head, or an
+/// array of positions when COUNT is given
+pub async fn lpos(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, element, options) = match command.args {
+        CommandArgs::Lpos {
+            key,
+            element,
+            options,
+        } => (key, element, options),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+
+    let key = match db_read.get(&key_name) {
+        Some(DbValue::ListKey(key)) => key,
+        Some(_) => return Err(ZystError::WrongType),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name);
+            return Ok(match options.count {
+                Some(_) => ZystResponse::EmptyArray,
+                None => ZystResponse::Nil,
+            });
+        }
+    };
+
+    let len = key.data.len();
+    let compared = match options.maxlen {
+        0 => len,
+        maxlen => maxlen.min(len),
+    };
+    let skipped = usize::try_from(options.rank.unsigned_abs() - 1).unwrap_or(usize::MAX);
+    let wanted = match options.count {
+        Some(0) => usize::MAX,
+        count => count.unwrap_or(1),
+    };
+
+    let matches = |index: &usize| key.data[*index] == element;
+    let positions: Vec<usize> = if options.rank > 0 {
+        (0..compared)
+            .filter(matches)
+            .skip(skipped)
+            .take(wanted)
+            .collect()
+    } else {
+        (len - compared..len)
+            .rev()
+            .filter(matches)
+            .skip(skipped)
+            .take(wanted)
+            .collect()
+    };
+
+    match options.count {
+        Some(_) => Ok(ZystResponse::Array(
+            positions
+                .into_iter()
+                .map(|position| ZystResponse::Int(position as i64))
+                .collect(),
+        )),
+        None => Ok(positions.first().map_or(ZystResponse::Nil, |position| {
+            ZystResponse::Int(*position as i64)
+        })),
+    }
 }
 
 pub async fn lpop(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    pop_list(db, command, PopType::LPOP).await
+    pop_list(db, command).await
 }
 
 pub async fn rpop(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
-    pop_list(db, command, PopType::RPOP).await
+    pop_list(db, command).await
 }
 
-async fn pop_list(
-    db: &Db,
-    command: Command,
+// Pops up to `count` elements from an end of a list, in the order they are
+// popped, and deletes the list once empty. None when the key doesn't exist.
+fn pop_elements(
+    keyspace: &mut Keyspace,
+    key: &Bytes,
     pop_type: PopType,
-) -> Result<ZystResponse, ZystError> {
-    let (key_name, value) = match &command.args {
-        CommandArgs::SingleKey(key) => (key.clone(), None),
-        CommandArgs::KeyWithValue { key, value } => (key.clone(), Some(value.clone())),
+    count: usize,
+) -> Result<Option<Vec<Bytes>>, ZystError> {
+    let list = match keyspace.get_mut(key) {
+        Some(DbValue::ListKey(list)) => list,
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(None),
+    };
+
+    let len = list.data.len();
+    let (start, end) = match pop_type {
+        PopType::LPOP => (0, count.min(len)),
+        PopType::RPOP => (len.saturating_sub(count), len),
+    };
+
+    let mut removed: Vec<Bytes> = list.data.drain(start..end).collect();
+    let emptied = list.data.is_empty();
+
+    if let PopType::RPOP = pop_type {
+        removed.reverse();
+    }
+
+    if !removed.is_empty() {
+        let event = match pop_type {
+            PopType::LPOP => "lpop",
+            PopType::RPOP => "rpop",
+        };
+        keyspace.signal_modified(key);
+        keyspace.notify(NotifyFlags::LIST, event, key.clone());
+    }
+
+    // An empty list doesn't exist
+    if emptied {
+        keyspace.swap_remove(key);
+        keyspace.notify(NotifyFlags::GENERIC, "del", key.clone());
+    }
+
+    Ok(Some(removed))
+}
+
+async fn pop_list(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, pop_type, count) = match command.args {
+        CommandArgs::ListPop {
+            mut keys,
+            pop_type,
+            count,
+            ..
+        } => (keys.swap_remove(0), pop_type, count),
         _ => return Err(ZystError::InvalidCommand),
     };
 
     let mut db_write = db.write().await;
 
-    let key_db = match db_write.get_mut(&key_name) {
-        Some(DbValue::ListKey(key)) => key,
-        Some(DbValue::StringKey(_)) => return Err(ZystError::WrongType),
-        _ => return Ok(ZystResponse::EmptyArray),
+    let removed = match pop_elements(&mut db_write, &key_name, pop_type, count.unwrap_or(1))? {
+        Some(removed) => removed,
+        None if count.is_some() => return Ok(ZystResponse::NilArray),
+        None => return Ok(ZystResponse::Nil),
     };
 
-    let nb = value
-        .as_deref()
-        .map_or(1, |v| v.parse::<usize>().unwrap_or(1));
+    // Given a count, the reply is an array even when it holds one element
+    match count {
+        Some(_) => Ok(ZystResponse::List(removed)),
+        None => Ok(removed
+            .into_iter()
+            .next()
+            .map_or(ZystResponse::Nil, ZystResponse::BulkString)),
+    }
+}
 
-    let len = key_db.data.len();
+// Moves an element from an end of a list to an end of another, or of the
+// same one which is then rotated. None when the source doesn't exist.
+fn move_element(
+    state: &State,
+    db_index: usize,
+    keyspace: &mut Keyspace,
+    source: &Bytes,
+    destination: &Bytes,
+    pop_type: PopType,
+    push_type: ListPushType,
+) -> Result<Option<Bytes>, ZystError> {
+    match keyspace.get(source) {
+        Some(DbValue::ListKey(_)) => {}
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(None),
+    }
+    if keyspace
+        .get(destination)
+        .is_some_and(|value| !matches!(value, DbValue::ListKey(_)))
+    {
+        return Err(ZystError::WrongType);
+    }
 
-    let (start, end) = match pop_type {
-        PopType::LPOP => (0, nb),
-        PopType::RPOP => (len.saturating_sub(nb), len),
+    // A list rotated in place isn't deleted when it holds a single element
+    if source == destination {
+        let Some(DbValue::ListKey(list)) = keyspace.get_mut(source) else {
+            return Ok(None);
+        };
+        let element = match pop_type {
+            PopType::LPOP => list.data.pop_front(),
+            PopType::RPOP => list.data.pop_back(),
+        };
+        let Some(element) = element else {
+            return Ok(None);
+        };
+        match push_type {
+            ListPushType::LPUSH => list.data.push_front(element.clone()),
+            ListPushType::RPUSH => list.data.push_back(element.clone()),
+        }
+
+        let (popped, pushed) = list_events(pop_type, push_type);
+        keyspace.signal_modified(source);
+        keyspace.notify(NotifyFlags::LIST, popped, source.clone());
+        keyspace.notify(NotifyFlags::LIST, pushed, source.clone());
+        return Ok(Some(element));
+    }
+
+    let Some(element) = pop_elements(keyspace, source, pop_type, 1)?
+        .and_then(|removed| removed.into_iter().next())
+    else {
+        return Ok(None);
     };
+    push_elements(keyspace, destination, vec![element.clone()], push_type)?;
+    state.blocking().signal(db_index, destination);
 
-    let mut removed: Vec<String> = key_db
-        .data
-        .drain(start..end.min(key_db.data.len()))
-        .collect();
+    Ok(Some(element))
+}
 
-    if removed.is_empty() {
-        return Ok(ZystResponse::Nil);
+fn list_events(pop_type: PopType, push_type: ListPushType) -> (&'static str, &'static str) {
+    let popped = match pop_type {
+        PopType::LPOP => "lpop",
+        PopType::RPOP => "rpop",
+    };
+    let pushed = match push_type {
+        ListPushType::LPUSH => "lpush",
+        ListPushType::RPUSH => "rpush",
+    };
+    (popped, pushed)
+}
+
+// Runs a blocking command as if it didn't block: pops from the first of its
+// lists holding elements. None when they are all empty.
+fn pop_ready(
+    state: &State,
+    db_index: usize,
+    keyspace: &mut Keyspace,
+    command: &Command,
+) -> Result<Option<ZystResponse>, ZystError> {
+    match &command.args {
+        CommandArgs::ListPop {
+            keys,
+            pop_type,
+            count,
+            ..
+        } => {
+            for key in keys {
+                let Some(removed) = pop_elements(keyspace, key, *pop_type, count.unwrap_or(1))?
+                else {
+                    continue;
+                };
+                let Some(first) = removed.first().cloned() else {
+                    continue;
+                };
+
+                let popped = match count {
+                    Some(_) => ZystResponse::List(removed),
+                    None => ZystResponse::BulkString(first),
+                };
+                return Ok(Some(ZystResponse::Array(vec![
+                    ZystResponse::BulkString(key.clone()),
+                    popped,
+                ])));
+            }
+            Ok(None)
+        }
+        CommandArgs::ListMove {
+            source,
+            destination,
+            pop_type,
+            push_type,
+            ..
+        } => Ok(move_element(
+            state,
+            db_index,
+            keyspace,
+            source,
+            destination,
+            *pop_type,
+            *push_type,
+        )?
+        .map(ZystResponse::BulkString)),
+        _ => Err(ZystError::InvalidCommand),
     }
+}
 
-    if let PopType::RPOP = pop_type {
-        removed.reverse();
+/// BLPOP, BRPOP, BLMPOP and BLMOVE. When all their lists are empty, the
+/// client blocks until an element is pushed to one of them or the timeout
+/// expires, the reply is then a null. They don't block inside a
+/// transaction. LMPOP, LMOVE and RPOPLPUSH, which have no timeout, reply
+/// with a null right away.
+pub async fn blocking_pop(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let (keys, timeout, timeout_reply) = match &command.args {
+        CommandArgs::ListPop { keys, timeout, .. } => {
+            (keys.clone(), *timeout, ZystResponse::NilArray)
+        }
+        CommandArgs::ListMove {
+            source, timeout, ..
+        } => (vec![source.clone()], *timeout, ZystResponse::Nil),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db = state.db(client.db).ok_or(ZystError::DatabaseError)?;
+    let mut keyspace = db.write().await;
+
+    if let Some(reply) = pop_ready(state, client.db, &mut keyspace, &command)? {
+        return Ok(reply);
+    }
+
+    let Some(timeout) = timeout.filter(|_| !client.deny_blocking) else {
+        return Ok(timeout_reply);
+    };
+
+    // A timeout past what an Instant holds waits forever, like 0
+    let deadline = if timeout.is_zero() {
+        None
+    } else {
+        Instant::now().checked_add(timeout)
+    };
+
+    // Registered while the keyspace is locked, so no push can be missed
+    let reply =
+        state
+            .blocking()
+            .block(client.id, client.db, keys, command, timeout_reply.clone());
+    client.blocked = Some(Blocked { reply, deadline });
+
+    // The dispatcher waits for the actual reply once the command is done
+    Ok(timeout_reply)
+}
+
+/// Serves the clients blocked on keys that got elements, those blocked the
+/// longest first. Runs once a command is done, so that a transaction is
+/// never seen half done. The pops are appended to `propagated`.
+pub async fn serve_blocked_clients(state: &State, propagated: &mut Vec<(usize, Vec<Bytes>)>) {
+    while let Some((db_index, key)) = state.blocking().next_ready() {
+        let Some(db) = state.db(db_index) else {
+            continue;
+        };
+        let mut keyspace = db.write().await;
+
+        while matches!(keyspace.get(&key), Some(DbValue::ListKey(list)) if !list.data.is_empty())
+        {
+            let Some(blocked) = state.blocking().next_client(db_index, &key) else {
+                break;
+            };
+
+            let reply = match pop_ready(state, db_index, &mut keyspace, &blocked.command) {
+                Ok(Some(reply)) => {
+                    let argv = served_command(&blocked.command.args, &reply);
+                    if !argv.is_empty() {
+                        propagated.push((db_index, argv));
+                    }
+                    Ok(reply)
+                }
+                Ok(None) => Ok(blocked.timeout_reply.clone()),
+                Err(e) => Err(e),
+            };
+            blocked.reply(reply);
+        }
+
+        publish_events(state, db_index, &keyspace);
     }
+}
 
-    // If LPOP is passed without arguments, return the first element
-    if nb == 1 {
-        return Ok(ZystResponse::SimpleString(removed[0].clone()));
+// Command logged for one that popped: the pop from the key it popped from,
+// or the move it did, replaying it never blocks
+fn served_command(args: &CommandArgs, reply: &ZystResponse) -> Vec<Bytes> {
+    let end = |left: bool| Bytes::from_static(if left { b"LEFT" } else { b"RIGHT" });
+
+    match (args, reply) {
+        (
+            CommandArgs::ListPop {
+                pop_type, count, ..
+            },
+            ZystResponse::Array(popped),
+        ) => {
+            let [ZystResponse::BulkString(key), elements] = &popped[..] else {
+                return Vec::new();
+            };
+            let name: &'static [u8] = match pop_type {
+                PopType::LPOP => b"LPOP",
+                PopType::RPOP => b"RPOP",
+            };
+
+            let mut argv = vec![Bytes::from_static(name), key.clone()];
+            if let (Some(_), ZystResponse::List(elements)) = (count, elements) {
+                argv.push(Bytes::from(elements.len().to_string()));
+            }
+            argv
+        }
+        (
+            CommandArgs::ListMove {
+                source,
+                destination,
+                pop_type,
+                push_type,
+                ..
+            },
+            ZystResponse::BulkString(_),
+        ) => vec![
+            Bytes::from_static(b"LMOVE"),
+            source.clone(),
+            destination.clone(),
+            end(matches!(pop_type, PopType::LPOP)),
+            end(matches!(push_type, ListPushType::LPUSH)),
+        ],
+        _ => Vec::new(),
     }
+}
+
+/// Logs a command popping from one of several lists as the equivalent pop,
+/// and a move as LMOVE. Nothing when it didn't pop.
+pub fn propagate_blocking_pop(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    let build = match argv[0].to_ascii_lowercase().as_slice() {
+        b"blpop" => build_blpop_command,
+        b"brpop" => build_brpop_command,
+        b"blmpop" => build_blmpop_command,
+        b"lmpop" => build_lmpop_command,
+        b"lmove" => build_lmove_command,
+        b"rpoplpush" => build_rpoplpush_command,
+        _ => build_blmove_command,
+    };
 
-    Ok(ZystResponse::List(removed))
+    build(&argv[1..]).map_or_else(|_| Vec::new(), |args| served_command(&args, response))
 }
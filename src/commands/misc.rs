@@ -1,18 +1,92 @@
+use crate::client::{is_valid_attribute, Client};
 use crate::errors::ZystError;
-use crate::response::ZystResponse;
+use crate::parser::parse_number;
+use crate::response::{Protocol, ZystResponse};
+use crate::types::{Command, CommandArgs};
+use bytes::Bytes;
+
+/// A subscribed RESP2 connection can't tell a status reply from a message,
+/// it gets a `pong` message shaped array instead
+pub async fn pong(conn: &Client) -> Result<ZystResponse, ZystError> {
+    if conn.subscriptions() > 0 && conn.protocol == Protocol::Resp2 {
+        return Ok(ZystResponse::List(vec![
+            Bytes::from_static(b"pong"),
+            Bytes::new(),
+        ]));
+    }
 
-pub async fn pong() -> Result<ZystResponse, ZystError> {
     Ok(ZystResponse::SimpleString("PONG".to_string()))
 }
 
-pub async fn docs() -> Result<ZystResponse, ZystError> {
-    Ok(ZystResponse::SimpleString(
-        "DOCS is not implemented yet".to_string(),
-    ))
-}
+// Switches the connection to the requested protocol and replies with
+// the server properties. Zyst has no ACL, so like Redis without
+// `requirepass`, AUTH accepts any password for the `default` user.
+pub async fn hello(conn: &mut Client, command: Command) -> Result<ZystResponse, ZystError> {
+    let args = match command.args {
+        CommandArgs::MultipleKeys(args) => args,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut args = args.iter();
+    let mut protocol = conn.protocol;
+    let mut name = None;
+
+    if let Some(version) = args.next() {
+        protocol = match parse_number::<i64>(version) {
+            Some(2) => Protocol::Resp2,
+            Some(3) => Protocol::Resp3,
+            Some(_) => return Err(ZystError::NoProto),
+            None => return Err(ZystError::NotIntOrOutOfRange),
+        };
+
+        while let Some(option) = args.next() {
+            match option.to_ascii_uppercase().as_slice() {
+                b"AUTH" => {
+                    let (Some(username), Some(_password)) = (args.next(), args.next()) else {
+                        return Err(ZystError::Syntax);
+                    };
+                    if &username[..] != b"default" {
+                        return Err(ZystError::WrongPass);
+                    }
+                }
+                b"SETNAME" => {
+                    let Some(client_name) = args.next() else {
+                        return Err(ZystError::Syntax);
+                    };
+                    name = Some(client_name.clone());
+                }
+                _ => return Err(ZystError::Syntax),
+            }
+        }
+    }
+
+    if let Some(name) = name {
+        if !is_valid_attribute(&name) {
+            return Err(ZystError::Custom(
+                "ERR Client names cannot contain spaces, newlines or special characters."
+                    .to_string(),
+            ));
+        }
+        conn.name = Some(String::from_utf8_lossy(&name).into_owned());
+    }
+
+    conn.protocol = protocol;
+
+    let proto = match protocol {
+        Protocol::Resp2 => 2,
+        Protocol::Resp3 => 3,
+    };
+
+    let field =
+        |name: &'static str| ZystResponse::BulkString(Bytes::from_static(name.as_bytes()));
 
-pub async fn client() -> Result<ZystResponse, ZystError> {
-    Ok(ZystResponse::SimpleString(
-        "CLIENT SETINFO is not implemented yet".to_string(),
-    ))
+    Ok(ZystResponse::Map(vec![
+        (field("server"), field("zyst")),
+        (field("version"), field(env!("CARGO_PKG_VERSION"))),
+        (field("proto"), ZystResponse::Int(proto)),
+        (field("id"), ZystResponse::Int(conn.id as i64)),
+        (field("mode"), field("standalone")),
+        (field("role"), field("master")),
+        (field("modules"), ZystResponse::EmptyArray),
+    ]))
 }
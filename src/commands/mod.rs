@@ -1,7 +1,14 @@
 pub mod build;
+pub mod client;
+pub mod config;
 pub mod db;
 pub mod hashsets;
+pub mod introspection;
 pub mod keys;
 pub mod lists;
 pub mod misc;
+pub mod pubsub;
+pub mod registry;
+pub mod scan;
 pub mod sets;
+pub mod transactions;
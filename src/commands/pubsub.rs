@@ -0,0 +1,194 @@
+use crate::client::Client;
+use crate::errors::ZystError;
+use crate::response::ZystResponse;
+use crate::state::State;
+use crate::types::{Command, CommandArgs};
+use bytes::Bytes;
+
+const PUBSUB_HELP: &[&str] = &[
+    "PUBSUB <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "CHANNELS [<pattern>]",
+    "    Return the currently active channels matching a <pattern> (default: '*').",
+    "NUMPAT",
+    "    Return number of subscriptions to patterns.",
+    "NUMSUB [<channel> ...]",
+    "    Return the number of subscribers for the specified channels, excluding",
+    "    pattern subscriptions(default: no channels).",
+    "HELP",
+    "    Print this help.",
+];
+
+fn pubsub_args(command: Command) -> Result<Vec<Bytes>, ZystError> {
+    match command.args {
+        CommandArgs::NoArgs => Ok(Vec::new()),
+        CommandArgs::MultipleKeys(args) => Ok(args),
+        _ => Err(ZystError::InvalidCommand),
+    }
+}
+
+// Confirmation sent for each channel or pattern (un)subscribed, with the
+// number of subscriptions left to the client
+fn confirmation(kind: &'static [u8], name: Option<Bytes>, client: &Client) -> ZystResponse {
+    ZystResponse::Push(vec![
+        ZystResponse::BulkString(Bytes::from_static(kind)),
+        name.map_or(ZystResponse::Nil, ZystResponse::BulkString),
+        ZystResponse::Int(client.subscriptions() as i64),
+    ])
+}
+
+/// Subscribes the connection to channels. Messages are then pushed to it
+/// by the connection loop as they are published.
+pub async fn subscribe(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let channels = pubsub_args(command)?;
+    let mut replies = Vec::with_capacity(channels.len());
+
+    for channel in channels {
+        if client.channels.insert(channel.clone()) {
+            state.pubsub().subscribe(channel.clone(), &client.handle);
+        }
+        replies.push(confirmation(b"subscribe", Some(channel), client));
+    }
+
+    Ok(ZystResponse::Replies(replies))
+}
+
+/// Unsubscribes the connection from channels, or from all of them
+pub async fn unsubscribe(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let mut channels = pubsub_args(command)?;
+    if channels.is_empty() {
+        channels = client.channels.iter().cloned().collect();
+    }
+
+    if channels.is_empty() {
+        return Ok(confirmation(b"unsubscribe", None, client));
+    }
+
+    let mut replies = Vec::with_capacity(channels.len());
+    for channel in channels {
+        if client.channels.shift_remove(&channel) {
+            state.pubsub().unsubscribe(&channel, client.id);
+        }
+        replies.push(confirmation(b"unsubscribe", Some(channel), client));
+    }
+
+    Ok(ZystResponse::Replies(replies))
+}
+
+/// Subscribes the connection to the channels matching glob patterns
+pub async fn psubscribe(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let patterns = pubsub_args(command)?;
+    let mut replies = Vec::with_capacity(patterns.len());
+
+    for pattern in patterns {
+        if client.patterns.insert(pattern.clone()) {
+            state.pubsub().psubscribe(pattern.clone(), &client.handle);
+        }
+        replies.push(confirmation(b"psubscribe", Some(pattern), client));
+    }
+
+    Ok(ZystResponse::Replies(replies))
+}
+
+pub async fn punsubscribe(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let mut patterns = pubsub_args(command)?;
+    if patterns.is_empty() {
+        patterns = client.patterns.iter().cloned().collect();
+    }
+
+    if patterns.is_empty() {
+        return Ok(confirmation(b"punsubscribe", None, client));
+    }
+
+    let mut replies = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        if client.patterns.shift_remove(&pattern) {
+            state.pubsub().punsubscribe(&pattern, client.id);
+        }
+        replies.push(confirmation(b"punsubscribe", Some(pattern), client));
+    }
+
+    Ok(ZystResponse::Replies(replies))
+}
+
+/// Drops every subscription of a client, done when it disconnects
+pub fn unsubscribe_all(state: &State, client: &mut Client) {
+    for channel in client.channels.drain(..) {
+        state.pubsub().unsubscribe(&channel, client.id);
+    }
+    for pattern in client.patterns.drain(..) {
+        state.pubsub().punsubscribe(&pattern, client.id);
+    }
+}
+
+/// Replies with the number of clients the message was queued for
+pub async fn publish(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let (channel, message) = match command.args {
+        CommandArgs::KeyWithValue { key, value } => (key, value),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let receivers = state.pubsub().publish(&channel, &message);
+    Ok(ZystResponse::Int(receivers as i64))
+}
+
+pub async fn pubsub_help() -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Array(
+        PUBSUB_HELP
+            .iter()
+            .map(|line| ZystResponse::SimpleString(line.to_string()))
+            .collect(),
+    ))
+}
+
+pub async fn pubsub_channels(
+    state: &State,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let args = pubsub_args(command)?;
+    let pattern = match &args[..] {
+        [] => None,
+        [pattern] => Some(&pattern[..]),
+        _ => return Err(ZystError::Syntax),
+    };
+
+    Ok(ZystResponse::List(state.pubsub().channels(pattern)))
+}
+
+/// Replies with each channel followed by its number of subscribers,
+/// pattern subscriptions aren't counted
+pub async fn pubsub_numsub(state: &State, command: Command) -> Result<ZystResponse, ZystError> {
+    let channels = pubsub_args(command)?;
+
+    Ok(ZystResponse::Map(
+        channels
+            .into_iter()
+            .map(|channel| {
+                let subscribers = state.pubsub().numsub(&channel);
+                (
+                    ZystResponse::BulkString(channel),
+                    ZystResponse::Int(subscribers as i64),
+                )
+            })
+            .collect(),
+    ))
+}
+
+pub async fn pubsub_numpat(state: &State) -> Result<ZystResponse, ZystError> {
+    Ok(ZystResponse::Int(state.pubsub().numpat() as i64))
+}
@@ -0,0 +1,1673 @@
+use crate::client::Client;
+use crate::commands::build::*;
+use crate::commands::client::*;
+use crate::commands::config::*;
+use crate::commands::db::*;
+use crate::commands::hashsets::*;
+use crate::commands::introspection::*;
+use crate::commands::keys::*;
+use crate::commands::lists::*;
+use crate::commands::misc::*;
+use crate::commands::pubsub::*;
+use crate::commands::scan::*;
+use crate::commands::sets::*;
+use crate::commands::transactions::*;
+use crate::errors::ZystError;
+use crate::parser::parse_number;
+use crate::response::ZystResponse;
+use crate::state::State;
+use crate::types::{Command, CommandArgs, Db};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::LazyLock;
+
+/// State a command handler can act on: the server, the database selected
+/// by the connection that sent the command, and the connection itself
+pub struct Context<'a> {
+    pub state: &'a State,
+    pub db: &'a Db,
+    pub client: &'a mut Client,
+    /// Commands to append to the AOF once the command is done, with the
+    /// database each applies to
+    pub propagated: &'a mut Vec<(usize, Vec<Bytes>)>,
+}
+
+pub type HandlerFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<ZystResponse, ZystError>> + Send + 'a>>;
+
+pub type Handler = for<'a, 'b> fn(&'a mut Context<'b>, Command) -> HandlerFuture<'a>;
+
+pub type Builder = fn(&[Bytes]) -> Result<CommandArgs, ZystError>;
+
+/// Rewrites a successful write command into the commands appended to the
+/// AOF, for instance to turn a relative expiration into an absolute one.
+/// Returning nothing skips the AOF.
+pub type Propagate = fn(&[Bytes], &ZystResponse) -> Vec<Bytes>;
+
+/// Hook of the commands whose handler adds what it applied to
+/// `Context::propagated` itself, there is nothing left to log
+pub fn logged_by_handler(_argv: &[Bytes], _response: &ZystResponse) -> Vec<Bytes> {
+    Vec::new()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandFlag {
+    /// May modify the keyspace, the command is appended to the AOF
+    Write,
+    /// Only reads data
+    ReadOnly,
+    /// Administrative command
+    Admin,
+    /// Related to pub/sub
+    PubSub,
+    /// May block the client
+    Blocking,
+    /// Runs in O(1) or O(log(N))
+    Fast,
+}
+
+impl CommandFlag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandFlag::Write => "write",
+            CommandFlag::ReadOnly => "readonly",
+            CommandFlag::Admin => "admin",
+            CommandFlag::PubSub => "pubsub",
+            CommandFlag::Blocking => "blocking",
+            CommandFlag::Fast => "fast",
+        }
+    }
+}
+
+/// Group a command is documented in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandGroup {
+    Generic,
+    String,
+    List,
+    Set,
+    Hash,
+    Connection,
+    Server,
+    Transactions,
+    PubSub,
+}
+
+impl CommandGroup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandGroup::Generic => "generic",
+            CommandGroup::String => "string",
+            CommandGroup::List => "list",
+            CommandGroup::Set => "set",
+            CommandGroup::Hash => "hash",
+            CommandGroup::Connection => "connection",
+            CommandGroup::Server => "server",
+            CommandGroup::Transactions => "transactions",
+            CommandGroup::PubSub => "pubsub",
+        }
+    }
+
+    /// ACL category matching the group, if any
+    pub fn acl_category(&self) -> Option<&'static str> {
+        match self {
+            CommandGroup::Generic => Some("keyspace"),
+            CommandGroup::String => Some("string"),
+            CommandGroup::List => Some("list"),
+            CommandGroup::Set => Some("set"),
+            CommandGroup::Hash => Some("hash"),
+            CommandGroup::Connection => Some("connection"),
+            CommandGroup::Server => None,
+            CommandGroup::Transactions => Some("transaction"),
+            CommandGroup::PubSub => Some("pubsub"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    Key,
+    String,
+    Integer,
+    Double,
+    Pattern,
+    UnixTime,
+    PureToken,
+    OneOf,
+    Block,
+}
+
+impl ArgType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArgType::Key => "key",
+            ArgType::String => "string",
+            ArgType::Integer => "integer",
+            ArgType::Double => "double",
+            ArgType::Pattern => "pattern",
+            ArgType::UnixTime => "unix-time",
+            ArgType::PureToken => "pure-token",
+            ArgType::OneOf => "oneof",
+            ArgType::Block => "block",
+        }
+    }
+}
+
+/// Argument of a command as reported by `COMMAND DOCS`
+#[derive(Debug)]
+pub struct Arg {
+    pub name: &'static str,
+    pub kind: ArgType,
+    pub token: Option<&'static str>,
+    pub optional: bool,
+    pub multiple: bool,
+    pub arguments: &'static [Arg],
+}
+
+impl Arg {
+    const fn new(name: &'static str, kind: ArgType) -> Self {
+        Arg {
+            name,
+            kind,
+            token: None,
+            optional: false,
+            multiple: false,
+            arguments: &[],
+        }
+    }
+
+    pub const fn key(name: &'static str) -> Self {
+        Arg::new(name, ArgType::Key)
+    }
+
+    pub const fn string(name: &'static str) -> Self {
+        Arg::new(name, ArgType::String)
+    }
+
+    pub const fn integer(name: &'static str) -> Self {
+        Arg::new(name, ArgType::Integer)
+    }
+
+    pub const fn double(name: &'static str) -> Self {
+        Arg::new(name, ArgType::Double)
+    }
+
+    pub const fn pattern(name: &'static str) -> Self {
+        Arg::new(name, ArgType::Pattern)
+    }
+
+    pub const fn unix_time(name: &'static str) -> Self {
+        Arg::new(name, ArgType::UnixTime)
+    }
+
+    /// A keyword on its own, such as `NX`
+    pub const fn token(token: &'static str) -> Self {
+        Arg::new(token, ArgType::PureToken).with_token(token)
+    }
+
+    /// Exactly one of `arguments`
+    pub const fn one_of(name: &'static str, arguments: &'static [Arg]) -> Self {
+        let mut arg = Arg::new(name, ArgType::OneOf);
+        arg.arguments = arguments;
+        arg
+    }
+
+    /// All of `arguments`, in order
+    pub const fn block(name: &'static str, arguments: &'static [Arg]) -> Self {
+        let mut arg = Arg::new(name, ArgType::Block);
+        arg.arguments = arguments;
+        arg
+    }
+
+    pub const fn with_token(mut self, token: &'static str) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub const fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    pub const fn multiple(mut self) -> Self {
+        self.multiple = true;
+        self
+    }
+}
+
+/// Declaration of a command, following the Redis command table.
+///
+/// `arity` counts the command name: a positive value is the exact number of
+/// arguments, a negative one is the minimum. Keys are found at `first_key`,
+/// then every `step` arguments up to `last_key`, which is counted from the
+/// end when negative. Commands without keys have `first_key` set to 0.
+/// Commands whose keys follow a numkeys argument leave `first_key` to 0 and
+/// set `numkeys` to the position of that argument instead.
+///
+/// Container commands such as `COMMAND` dispatch on their first argument to
+/// `subcommands`, whose names are prefixed by the container name
+/// (`command|info`).
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub arity: i64,
+    pub group: CommandGroup,
+    pub flags: &'static [CommandFlag],
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    pub numkeys: usize,
+    pub summary: &'static str,
+    pub since: &'static str,
+    pub complexity: &'static str,
+    pub arguments: &'static [Arg],
+    pub subcommands: &'static [CommandSpec],
+    pub build: Builder,
+    pub handler: Handler,
+    pub propagate: Option<Propagate>,
+}
+
+impl CommandSpec {
+    pub const fn new(
+        name: &'static str,
+        arity: i64,
+        group: CommandGroup,
+        build: Builder,
+        handler: Handler,
+    ) -> Self {
+        CommandSpec {
+            name,
+            arity,
+            group,
+            flags: &[],
+            first_key: 0,
+            last_key: 0,
+            step: 0,
+            numkeys: 0,
+            summary: "",
+            since: "",
+            complexity: "",
+            arguments: &[],
+            subcommands: &[],
+            build,
+            handler,
+            propagate: None,
+        }
+    }
+
+    pub const fn flags(mut self, flags: &'static [CommandFlag]) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub const fn keys(mut self, first_key: i64, last_key: i64, step: i64) -> Self {
+        self.first_key = first_key;
+        self.last_key = last_key;
+        self.step = step;
+        self
+    }
+
+    pub const fn numkeys(mut self, index: usize) -> Self {
+        self.numkeys = index;
+        self
+    }
+
+    pub const fn docs(
+        mut self,
+        summary: &'static str,
+        since: &'static str,
+        complexity: &'static str,
+    ) -> Self {
+        self.summary = summary;
+        self.since = since;
+        self.complexity = complexity;
+        self
+    }
+
+    pub const fn arguments(mut self, arguments: &'static [Arg]) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    pub const fn subcommands(mut self, subcommands: &'static [CommandSpec]) -> Self {
+        self.subcommands = subcommands;
+        self
+    }
+
+    pub const fn propagate(mut self, propagate: Propagate) -> Self {
+        self.propagate = Some(propagate);
+        self
+    }
+
+    pub fn has_flag(&self, flag: CommandFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.has_flag(CommandFlag::Write)
+    }
+
+    /// Number of words naming the command: 2 for a subcommand, 1 otherwise
+    pub fn name_len(&self) -> usize {
+        self.name.split('|').count()
+    }
+
+    pub fn subcommand(&self, name: &[u8]) -> Option<&'static CommandSpec> {
+        self.subcommands.iter().find(|sub| {
+            sub.name
+                .rsplit('|')
+                .next()
+                .is_some_and(|sub_name| sub_name.as_bytes().eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// ACL categories, derived from the flags and the group
+    pub fn acl_categories(&self) -> Vec<&'static str> {
+        let mut categories = Vec::new();
+
+        if let Some(category) = self.group.acl_category() {
+            categories.push(category);
+        }
+        if self.has_flag(CommandFlag::Write) {
+            categories.push("write");
+        }
+        if self.has_flag(CommandFlag::ReadOnly) {
+            categories.push("read");
+        }
+        if self.has_flag(CommandFlag::Admin) {
+            categories.extend(["admin", "dangerous"]);
+        }
+        if self.has_flag(CommandFlag::PubSub) {
+            categories.push("pubsub");
+        }
+        if self.has_flag(CommandFlag::Blocking) {
+            categories.push("blocking");
+        }
+        categories.push(if self.has_flag(CommandFlag::Fast) {
+            "fast"
+        } else {
+            "slow"
+        });
+
+        categories
+    }
+
+    /// `argc` includes the command name
+    pub fn check_arity(&self, argc: usize) -> Result<(), ZystError> {
+        let argc = argc as i64;
+
+        if (self.arity >= 0 && argc != self.arity) || argc < -self.arity {
+            return Err(ZystError::WrongArity(self.name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the keys of the command depend on its arguments
+    pub fn has_movable_keys(&self) -> bool {
+        self.numkeys > 0
+    }
+
+    /// Returns the keys of a full command, name included
+    pub fn keys_of<'a>(&self, argv: &'a [Bytes]) -> Vec<&'a Bytes> {
+        if self.has_movable_keys() {
+            let first = self.numkeys + 1;
+            let count = argv
+                .get(self.numkeys)
+                .and_then(|numkeys| parse_number::<usize>(numkeys))
+                .unwrap_or(0);
+
+            return first
+                .checked_add(count)
+                .and_then(|end| argv.get(first..end))
+                .map_or_else(Vec::new, |keys| keys.iter().collect());
+        }
+
+        if self.first_key <= 0 {
+            return Vec::new();
+        }
+
+        let last = if self.last_key < 0 {
+            argv.len() as i64 + self.last_key
+        } else {
+            self.last_key
+        };
+
+        (self.first_key..=last)
+            .step_by(self.step.max(1) as usize)
+            .filter_map(|i| argv.get(i as usize))
+            .collect()
+    }
+}
+
+impl fmt::Debug for CommandSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandSpec")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .field("flags", &self.flags)
+            .field("first_key", &self.first_key)
+            .field("last_key", &self.last_key)
+            .field("step", &self.step)
+            .field("numkeys", &self.numkeys)
+            .finish_non_exhaustive()
+    }
+}
+
+// Turns an expression using a `Context` and a `Command` into a `Handler`,
+// boxing the future it returns
+macro_rules! handler {
+    (|$ctx:ident, $command:ident| $body:expr) => {{
+        fn handler<'a>($ctx: &'a mut Context<'_>, $command: Command) -> HandlerFuture<'a> {
+            Box::pin($body)
+        }
+        handler
+    }};
+}
+
+use CommandFlag::*;
+
+const MULTIPLE_ELEMENTS: &str = "O(1) for each element added, so O(N) to add N elements when \
+                                 the command is called with multiple arguments.";
+
+const SCAN_COMPLEXITY: &str = "O(1) for every call. O(N) for a complete iteration, including \
+                               enough command calls for the cursor to return back to 0. N is \
+                               the number of elements inside the collection.";
+
+const HASH_FIELDS: Arg = Arg::block(
+    "fields",
+    &[Arg::integer("numfields"), Arg::string("field").multiple()],
+)
+.with_token("FIELDS");
+
+const HASH_FIELDS_COMPLEXITY: &str = "O(N) where N is the number of specified fields";
+
+const EXPIRE_CONDITION: Arg = Arg::one_of(
+    "condition",
+    &[
+        Arg::token("NX"),
+        Arg::token("XX"),
+        Arg::token("GT"),
+        Arg::token("LT"),
+    ],
+)
+.optional();
+
+const LIST_END: [Arg; 2] = [Arg::token("LEFT"), Arg::token("RIGHT")];
+
+const FLUSH_MODE: Arg =
+    Arg::one_of("flush-type", &[Arg::token("ASYNC"), Arg::token("SYNC")]).optional();
+
+/// Every command known by the server
+#[rustfmt::skip]
+pub static COMMANDS: &[CommandSpec] = &[
+    // Keys
+    CommandSpec::new("get", 2, CommandGroup::String, build_single_key_command,
+        handler!(|ctx, command| get_key(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the string value of a key.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("set", -3, CommandGroup::String, build_set_command,
+        handler!(|ctx, command| set_key(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Sets the string value of a key, ignoring its type. The key is created if it \
+               doesn't exist.", "1.0.0", "O(1)")
+        .arguments(&[
+            Arg::key("key"),
+            Arg::string("value"),
+            Arg::one_of("condition", &[
+                Arg::token("NX"),
+                Arg::token("XX"),
+                Arg::string("comparison-value").with_token("IFEQ"),
+            ])
+            .optional(),
+            Arg::token("GET").optional(),
+            Arg::one_of("expiration", &[
+                Arg::integer("seconds").with_token("EX"),
+                Arg::integer("milliseconds").with_token("PX"),
+                Arg::unix_time("unix-time-seconds").with_token("EXAT"),
+                Arg::unix_time("unix-time-milliseconds").with_token("PXAT"),
+                Arg::token("KEEPTTL"),
+            ])
+            .optional(),
+        ])
+        .propagate(propagate_set),
+    CommandSpec::new("setnx", 3, CommandGroup::String, build_setnx_command,
+        handler!(|ctx, command| setnx(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Set the string value of a key only when the key doesn't exist.", "1.0.0",
+              "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("value")])
+        .propagate(propagate_if_applied),
+    CommandSpec::new("setex", 4, CommandGroup::String, build_setex_command,
+        handler!(|ctx, command| set_key(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Sets the string value and expiration time of a key. Creates the key if it \
+               doesn't exist.", "2.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::integer("seconds"), Arg::string("value")])
+        .propagate(propagate_setex),
+    CommandSpec::new("psetex", 4, CommandGroup::String, build_psetex_command,
+        handler!(|ctx, command| set_key(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Sets both string value and expiration time in milliseconds of a key. The key \
+               is created if it doesn't exist.", "2.6.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::integer("milliseconds"), Arg::string("value")])
+        .propagate(propagate_setex),
+    CommandSpec::new("getset", 3, CommandGroup::String, build_getset_command,
+        handler!(|ctx, command| set_key(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the previous string value of a key after setting it to a new value.",
+              "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("value")]),
+    CommandSpec::new("del", -2, CommandGroup::Generic, build_multiple_keys_command,
+        handler!(|ctx, command| delete_key(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, -1, 1)
+        .docs("Deletes one or more keys.", "1.0.0",
+              "O(N) where N is the number of keys that will be removed.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("unlink", -2, CommandGroup::Generic, build_multiple_keys_command,
+        handler!(|ctx, command| unlink(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, -1, 1)
+        .docs("Asynchronously deletes one or more keys.", "4.0.0",
+              "O(1) for each key removed regardless of its size. Then the command does O(N) \
+               work in a different thread in order to reclaim memory, where N is the number \
+               of allocations the deleted objects were composed of.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("keys", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| get_keys(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .docs("Returns all key names that match a pattern.", "1.0.0",
+              "O(N) with N being the number of keys in the database.")
+        .arguments(&[Arg::pattern("pattern")]),
+    CommandSpec::new("scan", -2, CommandGroup::Generic, build_scan_command,
+        handler!(|ctx, command| scan(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .docs("Iterates over the key names in the database.", "2.8.0", SCAN_COMPLEXITY)
+        .arguments(&[
+            Arg::integer("cursor"),
+            Arg::pattern("pattern").with_token("MATCH").optional(),
+            Arg::integer("count").with_token("COUNT").optional(),
+            Arg::string("type").with_token("TYPE").optional(),
+        ]),
+    CommandSpec::new("exists", -2, CommandGroup::Generic, build_multiple_keys_command,
+        handler!(|ctx, command| exists(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, -1, 1)
+        .docs("Determines whether one or more keys exist.", "1.0.0",
+              "O(N) where N is the number of keys to check.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("type", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| key_type(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Determines the type of value stored at a key.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("rename", 3, CommandGroup::Generic, build_key_with_value_command,
+        handler!(|ctx, command| rename(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 2, 1)
+        .docs("Renames a key and overwrites the destination.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::key("newkey")]),
+    CommandSpec::new("renamenx", 3, CommandGroup::Generic, build_key_with_value_command,
+        handler!(|ctx, command| renamenx(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 2, 1)
+        .docs("Renames a key only when the target key name doesn't exist.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::key("newkey")])
+        .propagate(propagate_if_applied),
+    CommandSpec::new("copy", -3, CommandGroup::Generic, build_copy_command,
+        handler!(|ctx, command| copy(ctx.state, ctx.client.db, command)))
+        .flags(&[Write])
+        .keys(1, 2, 1)
+        .docs("Copies the value of a key to a new key.", "6.2.0",
+              "O(N) worst case for collections, where N is the number of nested items. O(1) \
+               for string values.")
+        .arguments(&[
+            Arg::key("source"),
+            Arg::key("destination"),
+            Arg::integer("destination-db").with_token("DB").optional(),
+            Arg::token("REPLACE").optional(),
+        ]),
+    CommandSpec::new("move", 3, CommandGroup::Generic, build_move_command,
+        handler!(|ctx, command| move_key(ctx.state, ctx.client.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Moves a key to another database.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::integer("db")])
+        .propagate(propagate_if_applied),
+    CommandSpec::new("randomkey", 1, CommandGroup::Generic, build_no_args_command,
+        handler!(|ctx, _command| random_key(ctx.db)))
+        .flags(&[ReadOnly])
+        .docs("Returns a random key name from the database.", "1.0.0", "O(1)"),
+    CommandSpec::new("touch", -2, CommandGroup::Generic, build_multiple_keys_command,
+        handler!(|ctx, command| touch(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, -1, 1)
+        .docs("Returns the number of existing keys out of those specified after updating the \
+               time they were last accessed.", "3.2.1",
+              "O(N) where N is the number of keys that will be touched.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("expire", -3, CommandGroup::Generic, build_expire_command,
+        handler!(|ctx, command| expire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of a key in seconds.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::integer("seconds"), EXPIRE_CONDITION])
+        .propagate(logged_by_handler),
+    CommandSpec::new("pexpire", -3, CommandGroup::Generic, build_pexpire_command,
+        handler!(|ctx, command| expire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of a key in milliseconds.", "2.6.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::integer("milliseconds"), EXPIRE_CONDITION])
+        .propagate(logged_by_handler),
+    CommandSpec::new("expireat", -3, CommandGroup::Generic, build_expireat_command,
+        handler!(|ctx, command| expire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of a key to a Unix timestamp.", "1.2.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::unix_time("unix-time-seconds"), EXPIRE_CONDITION])
+        .propagate(logged_by_handler),
+    CommandSpec::new("pexpireat", -3, CommandGroup::Generic, build_pexpireat_command,
+        handler!(|ctx, command| expire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of a key to a Unix milliseconds timestamp.", "2.6.0",
+              "O(1)")
+        .arguments(&[Arg::key("key"), Arg::unix_time("unix-time-milliseconds"),
+                     EXPIRE_CONDITION])
+        .propagate(logged_by_handler),
+    CommandSpec::new("persist", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| persist(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Removes the expiration time of a key.", "2.2.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("ttl", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| ttl(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time in seconds of a key.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("pttl", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| pttl(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time in milliseconds of a key.", "2.6.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("expiretime", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| expiretime(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time of a key as a Unix timestamp.", "7.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("pexpiretime", 2, CommandGroup::Generic, build_single_key_command,
+        handler!(|ctx, command| pexpiretime(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time of a key as a Unix milliseconds timestamp.",
+              "7.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("incr", 2, CommandGroup::String, build_single_key_command,
+        handler!(|ctx, command| incr(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Increments the integer value of a key by one. Uses 0 as initial value if the \
+               key doesn't exist.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("decr", 2, CommandGroup::String, build_single_key_command,
+        handler!(|ctx, command| decr(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Decrements the integer value of a key by one. Uses 0 as initial value if the \
+               key doesn't exist.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("incrby", 3, CommandGroup::String, build_key_with_value_command,
+        handler!(|ctx, command| incrby(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Increments the integer value of a key by a number. Uses 0 as initial value if \
+               the key doesn't exist.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::integer("increment")]),
+    // Lists
+    CommandSpec::new("lpush", -3, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| lpush(ctx.state, ctx.client.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Prepends one or more elements to a list. Creates the key if it doesn't exist.",
+              "1.0.0", MULTIPLE_ELEMENTS)
+        .arguments(&[Arg::key("key"), Arg::string("element").multiple()]),
+    CommandSpec::new("rpush", -3, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| rpush(ctx.state, ctx.client.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Appends one or more elements to a list. Creates the key if it doesn't exist.",
+              "1.0.0", MULTIPLE_ELEMENTS)
+        .arguments(&[Arg::key("key"), Arg::string("element").multiple()]),
+    CommandSpec::new("lrange", 4, CommandGroup::List, build_lrange_command,
+        handler!(|ctx, command| lrange(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns a range of elements from a list.", "1.0.0",
+              "O(S+N) where S is the distance of start offset from HEAD and N is the number \
+               of elements in the specified range.")
+        .arguments(&[Arg::key("key"), Arg::integer("start"), Arg::integer("stop")]),
+    CommandSpec::new("lpop", -2, CommandGroup::List, build_lpop_command,
+        handler!(|ctx, command| lpop(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the first elements in a list after removing it. Deletes the list if \
+               the last element was popped.", "1.0.0",
+              "O(N) where N is the number of elements returned.")
+        .arguments(&[Arg::key("key"), Arg::integer("count").optional()]),
+    CommandSpec::new("rpop", -2, CommandGroup::List, build_rpop_command,
+        handler!(|ctx, command| rpop(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns and removes the last elements of the list. Deletes the list if the \
+               last element was popped.", "1.0.0",
+              "O(N) where N is the number of elements returned.")
+        .arguments(&[Arg::key("key"), Arg::integer("count").optional()]),
+    CommandSpec::new("lpushx", -3, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| lpushx(ctx.state, ctx.client.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Prepends one or more elements to a list only when the list exists.", "2.2.0",
+              MULTIPLE_ELEMENTS)
+        .arguments(&[Arg::key("key"), Arg::string("element").multiple()]),
+    CommandSpec::new("rpushx", -3, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| rpushx(ctx.state, ctx.client.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Appends an element to a list only when the list exists.", "2.2.0",
+              MULTIPLE_ELEMENTS)
+        .arguments(&[Arg::key("key"), Arg::string("element").multiple()]),
+    CommandSpec::new("llen", 2, CommandGroup::List, build_single_key_command,
+        handler!(|ctx, command| llen(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the length of a list.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("lindex", 3, CommandGroup::List, build_key_with_value_command,
+        handler!(|ctx, command| lindex(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns an element from a list by its index.", "1.0.0",
+              "O(N) where N is the number of elements to traverse to get to the element at \
+               index. This makes asking for the first or the last element of the list O(1).")
+        .arguments(&[Arg::key("key"), Arg::integer("index")]),
+    CommandSpec::new("lset", 4, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| lset(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Sets the value of an element in a list by its index.", "1.0.0",
+              "O(N) where N is the length of the list. Setting either the first or the last \
+               element of the list is O(1).")
+        .arguments(&[Arg::key("key"), Arg::integer("index"), Arg::string("element")]),
+    CommandSpec::new("linsert", 5, CommandGroup::List, build_linsert_command,
+        handler!(|ctx, command| linsert(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Inserts an element before or after another element in a list.", "2.2.0",
+              "O(N) where N is the number of elements to traverse before seeing the value \
+               pivot.")
+        .arguments(&[
+            Arg::key("key"),
+            Arg::one_of("where", &[Arg::token("BEFORE"), Arg::token("AFTER")]),
+            Arg::string("pivot"),
+            Arg::string("element"),
+        ]),
+    CommandSpec::new("lrem", 4, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| lrem(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Removes elements from a list. Deletes the list if the last element was \
+               removed.", "1.0.0",
+              "O(N+M) where N is the length of the list and M is the number of elements \
+               removed.")
+        .arguments(&[Arg::key("key"), Arg::integer("count"), Arg::string("element")]),
+    CommandSpec::new("ltrim", 4, CommandGroup::List, build_key_with_values_command,
+        handler!(|ctx, command| ltrim(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, 1, 1)
+        .docs("Removes elements from both ends a list. Deletes the list if all elements were \
+               trimmed.", "1.0.0",
+              "O(N) where N is the number of elements to be removed by the operation.")
+        .arguments(&[Arg::key("key"), Arg::integer("start"), Arg::integer("stop")]),
+    CommandSpec::new("lpos", -3, CommandGroup::List, build_lpos_command,
+        handler!(|ctx, command| lpos(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns the index of matching elements in a list.", "6.0.6",
+              "O(N) where N is the number of elements in the list, for the average case.")
+        .arguments(&[
+            Arg::key("key"),
+            Arg::string("element"),
+            Arg::integer("rank").with_token("RANK").optional(),
+            Arg::integer("num-matches").with_token("COUNT").optional(),
+            Arg::integer("len").with_token("MAXLEN").optional(),
+        ]),
+    CommandSpec::new("lmove", 5, CommandGroup::List, build_lmove_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write])
+        .keys(1, 2, 1)
+        .docs("Returns an element after popping it from one list and pushing it to another. \
+               Deletes the list if the last element was moved.", "6.2.0", "O(1)")
+        .arguments(&[
+            Arg::key("source"),
+            Arg::key("destination"),
+            Arg::one_of("wherefrom", &LIST_END),
+            Arg::one_of("whereto", &LIST_END),
+        ])
+        .propagate(propagate_blocking_pop),
+    CommandSpec::new("rpoplpush", 3, CommandGroup::List, build_rpoplpush_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write])
+        .keys(1, 2, 1)
+        .docs("Returns the last element of a list after removing and pushing it to another \
+               list. Deletes the list if the last element was popped.", "1.2.0", "O(1)")
+        .arguments(&[Arg::key("source"), Arg::key("destination")])
+        .propagate(propagate_blocking_pop),
+    CommandSpec::new("lmpop", -4, CommandGroup::List, build_lmpop_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write])
+        .numkeys(1)
+        .docs("Returns multiple elements from a list after removing them. Deletes the list if \
+               the last element was popped.", "7.0.0",
+              "O(N+M) where N is the number of provided keys and M is the number of elements \
+               returned.")
+        .arguments(&[
+            Arg::integer("numkeys"),
+            Arg::key("key").multiple(),
+            Arg::one_of("where", &LIST_END),
+            Arg::integer("count").with_token("COUNT").optional(),
+        ])
+        .propagate(propagate_blocking_pop),
+    CommandSpec::new("blpop", -3, CommandGroup::List, build_blpop_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write, Blocking])
+        .keys(1, -2, 1)
+        .docs("Removes and returns the first element in a list. Blocks until an element is \
+               available otherwise. Deletes the list if the last element was popped.",
+              "2.0.0", "O(N) where N is the number of provided keys.")
+        .arguments(&[Arg::key("key").multiple(), Arg::double("timeout")])
+        .propagate(propagate_blocking_pop),
+    CommandSpec::new("brpop", -3, CommandGroup::List, build_brpop_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write, Blocking])
+        .keys(1, -2, 1)
+        .docs("Removes and returns the last element in a list. Blocks until an element is \
+               available otherwise. Deletes the list if the last element was popped.",
+              "2.0.0", "O(N) where N is the number of provided keys.")
+        .arguments(&[Arg::key("key").multiple(), Arg::double("timeout")])
+        .propagate(propagate_blocking_pop),
+    CommandSpec::new("blmove", 6, CommandGroup::List, build_blmove_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write, Blocking])
+        .keys(1, 2, 1)
+        .docs("Pops an element from a list, pushes it to another list and returns it. Blocks \
+               until an element is available otherwise. Deletes the list if the last element \
+               was moved.", "6.2.0", "O(1)")
+        .arguments(&[
+            Arg::key("source"),
+            Arg::key("destination"),
+            Arg::one_of("wherefrom", &LIST_END),
+            Arg::one_of("whereto", &LIST_END),
+            Arg::double("timeout"),
+        ])
+        .propagate(propagate_blocking_pop),
+    CommandSpec::new("blmpop", -5, CommandGroup::List, build_blmpop_command,
+        handler!(|ctx, command| blocking_pop(ctx.state, ctx.client, command)))
+        .flags(&[Write, Blocking])
+        .numkeys(2)
+        .docs("Pops the first element from one of multiple lists. Blocks until an element is \
+               available otherwise. Deletes the list if the last element was popped.",
+              "7.0.0",
+              "O(N+M) where N is the number of provided keys and M is the number of elements \
+               returned.")
+        .arguments(&[
+            Arg::double("timeout"),
+            Arg::integer("numkeys"),
+            Arg::key("key").multiple(),
+            Arg::one_of("where", &LIST_END),
+            Arg::integer("count").with_token("COUNT").optional(),
+        ])
+        .propagate(propagate_blocking_pop),
+    // Hashes
+    CommandSpec::new("hset", -4, CommandGroup::Hash, build_hset_command,
+        handler!(|ctx, command| hset(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Creates or modifies the value of a field in a hash.", "2.0.0",
+              "O(1) for each field/value pair added, so O(N) to add N field/value pairs.")
+        .arguments(&[
+            Arg::key("key"),
+            Arg::block("data", &[Arg::string("field"), Arg::string("value")]).multiple(),
+        ]),
+    CommandSpec::new("hget", 3, CommandGroup::Hash, build_key_with_value_command,
+        handler!(|ctx, command| hget(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the value of a field in a hash.", "2.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("field")]),
+    CommandSpec::new("hgetall", 2, CommandGroup::Hash, build_single_key_command,
+        handler!(|ctx, command| hgetall(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns all fields and values in a hash.", "2.0.0",
+              "O(N) where N is the size of the hash.")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("hdel", -3, CommandGroup::Hash, build_key_with_values_command,
+        handler!(|ctx, command| hdel(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Deletes one or more fields and their values from a hash. Deletes the hash if \
+               no fields remain.", "2.0.0",
+              "O(N) where N is the number of fields to be removed.")
+        .arguments(&[Arg::key("key"), Arg::string("field").multiple()]),
+    CommandSpec::new("hexists", 3, CommandGroup::Hash, build_key_with_value_command,
+        handler!(|ctx, command| hexists(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Determines whether a field exists in a hash.", "2.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("field")]),
+    CommandSpec::new("hlen", 2, CommandGroup::Hash, build_single_key_command,
+        handler!(|ctx, command| hlen(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the number of fields in a hash.", "2.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("hkeys", 2, CommandGroup::Hash, build_single_key_command,
+        handler!(|ctx, command| hkeys(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns all fields in a hash.", "2.0.0", "O(N) where N is the size of the hash.")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("hvals", 2, CommandGroup::Hash, build_single_key_command,
+        handler!(|ctx, command| hvals(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns all values in a hash.", "2.0.0", "O(N) where N is the size of the hash.")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("hmget", -3, CommandGroup::Hash, build_key_with_values_command,
+        handler!(|ctx, command| hmget(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the values of all fields in a hash.", "2.0.0",
+              "O(N) where N is the number of fields being requested.")
+        .arguments(&[Arg::key("key"), Arg::string("field").multiple()]),
+    CommandSpec::new("hsetnx", 4, CommandGroup::Hash, build_key_with_values_command,
+        handler!(|ctx, command| hsetnx(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the value of a field in a hash only when the field doesn't exist.",
+              "2.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("field"), Arg::string("value")])
+        .propagate(propagate_if_applied),
+    CommandSpec::new("hstrlen", 3, CommandGroup::Hash, build_key_with_value_command,
+        handler!(|ctx, command| hstrlen(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the length of the value of a field.", "3.2.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("field")]),
+    CommandSpec::new("hincrby", 4, CommandGroup::Hash, build_key_with_values_command,
+        handler!(|ctx, command| hincrby(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Increments the integer value of a field in a hash by a number. Uses 0 as \
+               initial value if the field doesn't exist.", "2.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("field"), Arg::integer("increment")]),
+    CommandSpec::new("hincrbyfloat", 4, CommandGroup::Hash, build_key_with_values_command,
+        handler!(|ctx, command| hincrbyfloat(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Increments the floating point value of a field by a number. Uses 0 as initial \
+               value if the field doesn't exist.", "2.6.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("field"), Arg::double("increment")])
+        .propagate(propagate_hincrbyfloat),
+    CommandSpec::new("hrandfield", -2, CommandGroup::Hash, build_hrandfield_command,
+        handler!(|ctx, command| hrandfield(ctx.db, command, ctx.client.protocol)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns one or more random fields from a hash.", "6.2.0",
+              "O(N) where N is the number of fields returned")
+        .arguments(&[
+            Arg::key("key"),
+            Arg::block("options", &[
+                Arg::integer("count"),
+                Arg::token("WITHVALUES").optional(),
+            ])
+            .optional(),
+        ]),
+    CommandSpec::new("hexpire", -6, CommandGroup::Hash, build_hexpire_command,
+        handler!(|ctx, command| hexpire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of hash fields in seconds.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), Arg::integer("seconds"),
+                     EXPIRE_CONDITION, HASH_FIELDS])
+        .propagate(logged_by_handler),
+    CommandSpec::new("hpexpire", -6, CommandGroup::Hash, build_hpexpire_command,
+        handler!(|ctx, command| hexpire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of hash fields in milliseconds.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), Arg::integer("milliseconds"),
+                     EXPIRE_CONDITION, HASH_FIELDS])
+        .propagate(logged_by_handler),
+    CommandSpec::new("hexpireat", -6, CommandGroup::Hash, build_hexpireat_command,
+        handler!(|ctx, command| hexpire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of hash fields to a Unix timestamp.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), Arg::unix_time("unix-time-seconds"),
+                     EXPIRE_CONDITION, HASH_FIELDS])
+        .propagate(logged_by_handler),
+    CommandSpec::new("hpexpireat", -6, CommandGroup::Hash, build_hpexpireat_command,
+        handler!(|ctx, command| hexpire(ctx, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the expiration time of hash fields to a Unix milliseconds timestamp.",
+              "7.4.0", HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), Arg::unix_time("unix-time-milliseconds"),
+                     EXPIRE_CONDITION, HASH_FIELDS])
+        .propagate(logged_by_handler),
+    CommandSpec::new("httl", -5, CommandGroup::Hash, build_hash_fields_command,
+        handler!(|ctx, command| httl(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time in seconds of hash fields.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), HASH_FIELDS]),
+    CommandSpec::new("hpttl", -5, CommandGroup::Hash, build_hash_fields_command,
+        handler!(|ctx, command| hpttl(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time in milliseconds of hash fields.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), HASH_FIELDS]),
+    CommandSpec::new("hexpiretime", -5, CommandGroup::Hash, build_hash_fields_command,
+        handler!(|ctx, command| hexpiretime(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time of hash fields as a Unix timestamp.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), HASH_FIELDS]),
+    CommandSpec::new("hpexpiretime", -5, CommandGroup::Hash, build_hash_fields_command,
+        handler!(|ctx, command| hpexpiretime(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the expiration time of hash fields as a Unix milliseconds timestamp.",
+              "7.4.0", HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), HASH_FIELDS]),
+    CommandSpec::new("hpersist", -5, CommandGroup::Hash, build_hash_fields_command,
+        handler!(|ctx, command| hpersist(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Removes the expiration time of hash fields.", "7.4.0",
+              HASH_FIELDS_COMPLEXITY)
+        .arguments(&[Arg::key("key"), HASH_FIELDS]),
+    CommandSpec::new("hgetex", -5, CommandGroup::Hash, build_hgetex_command,
+        handler!(|ctx, command| hgetex(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the values of hash fields and optionally sets or removes their \
+               expiration time.", "8.0.0", HASH_FIELDS_COMPLEXITY)
+        .arguments(&[
+            Arg::key("key"),
+            Arg::one_of("expiration", &[
+                Arg::integer("seconds").with_token("EX"),
+                Arg::integer("milliseconds").with_token("PX"),
+                Arg::unix_time("unix-time-seconds").with_token("EXAT"),
+                Arg::unix_time("unix-time-milliseconds").with_token("PXAT"),
+                Arg::token("PERSIST"),
+            ])
+            .optional(),
+            HASH_FIELDS,
+        ])
+        .propagate(propagate_hgetex),
+    CommandSpec::new("hsetex", -6, CommandGroup::Hash, build_hsetex_command,
+        handler!(|ctx, command| hsetex(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Sets the values of hash fields and optionally their expiration time.",
+              "8.0.0", HASH_FIELDS_COMPLEXITY)
+        .arguments(&[
+            Arg::key("key"),
+            Arg::one_of("condition", &[Arg::token("FNX"), Arg::token("FXX")]).optional(),
+            Arg::one_of("expiration", &[
+                Arg::integer("seconds").with_token("EX"),
+                Arg::integer("milliseconds").with_token("PX"),
+                Arg::unix_time("unix-time-seconds").with_token("EXAT"),
+                Arg::unix_time("unix-time-milliseconds").with_token("PXAT"),
+                Arg::token("KEEPTTL"),
+            ])
+            .optional(),
+            Arg::block("fields", &[
+                Arg::integer("numfields"),
+                Arg::block("data", &[Arg::string("field"), Arg::string("value")]).multiple(),
+            ])
+            .with_token("FIELDS"),
+        ])
+        .propagate(propagate_hsetex),
+    CommandSpec::new("hscan", -3, CommandGroup::Hash, build_hscan_command,
+        handler!(|ctx, command| hscan(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Iterates over fields and values of a hash.", "2.8.0", SCAN_COMPLEXITY)
+        .arguments(&[
+            Arg::key("key"),
+            Arg::integer("cursor"),
+            Arg::pattern("pattern").with_token("MATCH").optional(),
+            Arg::integer("count").with_token("COUNT").optional(),
+            Arg::token("NOVALUES").optional(),
+        ]),
+    // Sets
+    CommandSpec::new("sadd", -3, CommandGroup::Set, build_key_with_values_command,
+        handler!(|ctx, command| sadd(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Adds one or more members to a set. Creates the key if it doesn't exist.",
+              "1.0.0", MULTIPLE_ELEMENTS)
+        .arguments(&[Arg::key("key"), Arg::string("member").multiple()]),
+    CommandSpec::new("smembers", 2, CommandGroup::Set, build_single_key_command,
+        handler!(|ctx, command| smembers(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Returns all members of a set.", "1.0.0",
+              "O(N) where N is the set cardinality.")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("srem", -3, CommandGroup::Set, build_key_with_values_command,
+        handler!(|ctx, command| srem(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Removes one or more members from a set. Deletes the set if the last member \
+               was removed.", "1.0.0",
+              "O(N) where N is the number of members to be removed.")
+        .arguments(&[Arg::key("key"), Arg::string("member").multiple()]),
+    CommandSpec::new("sismember", 3, CommandGroup::Set, build_key_with_value_command,
+        handler!(|ctx, command| sismember(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Determines whether a member belongs to a set.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key"), Arg::string("member")]),
+    CommandSpec::new("smismember", -3, CommandGroup::Set, build_key_with_values_command,
+        handler!(|ctx, command| smismember(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Determines whether multiple members belong to a set.", "6.2.0",
+              "O(N) where N is the number of elements being checked for membership")
+        .arguments(&[Arg::key("key"), Arg::string("member").multiple()]),
+    CommandSpec::new("scard", 2, CommandGroup::Set, build_single_key_command,
+        handler!(|ctx, command| scard(ctx.db, command)))
+        .flags(&[ReadOnly, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns the number of members in a set.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("key")]),
+    CommandSpec::new("spop", -2, CommandGroup::Set, build_pop_command,
+        handler!(|ctx, command| spop(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 1, 1)
+        .docs("Returns one or more random members from a set after removing them. Deletes the \
+               set if the last member was popped.", "1.0.0",
+              "Without the count argument O(1), otherwise O(N) where N is the value of the \
+               passed count.")
+        .arguments(&[Arg::key("key"), Arg::integer("count").optional()])
+        .propagate(propagate_spop),
+    CommandSpec::new("srandmember", -2, CommandGroup::Set, build_srandmember_command,
+        handler!(|ctx, command| srandmember(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Get one or multiple random members from a set", "1.0.0",
+              "Without the count argument O(1), otherwise O(N) where N is the absolute value \
+               of the passed count.")
+        .arguments(&[Arg::key("key"), Arg::integer("count").optional()]),
+    CommandSpec::new("smove", 4, CommandGroup::Set, build_smove_command,
+        handler!(|ctx, command| smove(ctx.db, command)))
+        .flags(&[Write, Fast])
+        .keys(1, 2, 1)
+        .docs("Moves a member from one set to another.", "1.0.0", "O(1)")
+        .arguments(&[Arg::key("source"), Arg::key("destination"), Arg::string("member")]),
+    CommandSpec::new("sinter", -2, CommandGroup::Set, build_multiple_keys_command,
+        handler!(|ctx, command| sinter(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, -1, 1)
+        .docs("Returns the intersect of multiple sets.", "1.0.0",
+              "O(N*M) worst case where N is the cardinality of the smallest set and M is \
+               the number of sets.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("sinterstore", -3, CommandGroup::Set, build_key_with_values_command,
+        handler!(|ctx, command| sinterstore(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, -1, 1)
+        .docs("Stores the intersect of multiple sets in a key.", "1.0.0",
+              "O(N*M) worst case where N is the cardinality of the smallest set and M is \
+               the number of sets.")
+        .arguments(&[Arg::key("destination"), Arg::key("key").multiple()]),
+    CommandSpec::new("sunion", -2, CommandGroup::Set, build_multiple_keys_command,
+        handler!(|ctx, command| sunion(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, -1, 1)
+        .docs("Returns the union of multiple sets.", "1.0.0",
+              "O(N) where N is the total number of elements in all given sets.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("sunionstore", -3, CommandGroup::Set, build_key_with_values_command,
+        handler!(|ctx, command| sunionstore(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, -1, 1)
+        .docs("Stores the union of multiple sets in a key.", "1.0.0",
+              "O(N) where N is the total number of elements in all given sets.")
+        .arguments(&[Arg::key("destination"), Arg::key("key").multiple()]),
+    CommandSpec::new("sdiff", -2, CommandGroup::Set, build_multiple_keys_command,
+        handler!(|ctx, command| sdiff(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, -1, 1)
+        .docs("Returns the difference of multiple sets.", "1.0.0",
+              "O(N) where N is the total number of elements in all given sets.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("sdiffstore", -3, CommandGroup::Set, build_key_with_values_command,
+        handler!(|ctx, command| sdiffstore(ctx.db, command)))
+        .flags(&[Write])
+        .keys(1, -1, 1)
+        .docs("Stores the difference of multiple sets in a key.", "1.0.0",
+              "O(N) where N is the total number of elements in all given sets.")
+        .arguments(&[Arg::key("destination"), Arg::key("key").multiple()]),
+    CommandSpec::new("sintercard", -3, CommandGroup::Set, build_sintercard_command,
+        handler!(|ctx, command| sintercard(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .numkeys(1)
+        .docs("Returns the number of members of the intersect of multiple sets.", "7.0.0",
+              "O(N*M) worst case where N is the cardinality of the smallest set and M is \
+               the number of sets.")
+        .arguments(&[
+            Arg::integer("numkeys"),
+            Arg::key("key").multiple(),
+            Arg::integer("limit").with_token("LIMIT").optional(),
+        ]),
+    CommandSpec::new("sscan", -3, CommandGroup::Set, build_sscan_command,
+        handler!(|ctx, command| sscan(ctx.db, command)))
+        .flags(&[ReadOnly])
+        .keys(1, 1, 1)
+        .docs("Iterates over members of a set.", "2.8.0", SCAN_COMPLEXITY)
+        .arguments(&[
+            Arg::key("key"),
+            Arg::integer("cursor"),
+            Arg::pattern("pattern").with_token("MATCH").optional(),
+            Arg::integer("count").with_token("COUNT").optional(),
+        ]),
+    // Connection
+    CommandSpec::new("ping", -1, CommandGroup::Connection, build_no_args_command,
+        handler!(|ctx, _command| pong(ctx.client)))
+        .flags(&[Fast])
+        .docs("Returns the server's liveliness response.", "1.0.0", "O(1)"),
+    CommandSpec::new("hello", -1, CommandGroup::Connection, build_multiple_keys_command,
+        handler!(|ctx, command| hello(ctx.client, command)))
+        .flags(&[Fast])
+        .docs("Handshakes with the server.", "6.0.0", "O(1)")
+        .arguments(&[
+            Arg::block("arguments", &[
+                Arg::integer("protover"),
+                Arg::block("auth", &[Arg::string("username"), Arg::string("password")])
+                    .with_token("AUTH")
+                    .optional(),
+                Arg::string("clientname").with_token("SETNAME").optional(),
+            ])
+            .optional(),
+        ]),
+    CommandSpec::new("select", 2, CommandGroup::Connection, build_select_command,
+        handler!(|ctx, command| select(ctx.state, ctx.client, command)))
+        .flags(&[Fast])
+        .docs("Changes the selected database.", "1.0.0", "O(1)")
+        .arguments(&[Arg::integer("index")]),
+    CommandSpec::new("client", -2, CommandGroup::Connection, build_no_args_command,
+        handler!(|_ctx, _command| client_help()))
+        .docs("A container for client connection commands.", "2.4.0",
+              "Depends on subcommand.")
+        .subcommands(&[
+            CommandSpec::new("client|getname", 2, CommandGroup::Connection,
+                build_no_args_command,
+                handler!(|ctx, _command| client_getname(ctx.client)))
+                .docs("Returns the name of the connection.", "2.6.9", "O(1)"),
+            CommandSpec::new("client|help", 2, CommandGroup::Connection, build_no_args_command,
+                handler!(|_ctx, _command| client_help()))
+                .docs("Returns helpful text about the different subcommands.", "5.0.0", "O(1)"),
+            CommandSpec::new("client|id", 2, CommandGroup::Connection, build_no_args_command,
+                handler!(|ctx, _command| client_id(ctx.client)))
+                .docs("Returns the unique client ID of the connection.", "5.0.0", "O(1)"),
+            CommandSpec::new("client|info", 2, CommandGroup::Connection, build_no_args_command,
+                handler!(|ctx, _command| client_info(ctx.client)))
+                .docs("Returns information about the connection.", "6.2.0", "O(1)"),
+            CommandSpec::new("client|kill", -3, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_kill(ctx.state, ctx.client, command)))
+                .flags(&[Admin])
+                .docs("Terminates open connections.", "2.4.0",
+                      "O(N) where N is the number of client connections")
+                .arguments(&[
+                    Arg::one_of("filter", &[
+                        Arg::string("ip:port"),
+                        Arg::block("new-format", &[
+                            Arg::integer("client-id").with_token("ID").optional(),
+                            Arg::one_of("client-type", &[
+                                Arg::token("NORMAL"),
+                                Arg::token("MASTER"),
+                                Arg::token("SLAVE"),
+                                Arg::token("REPLICA"),
+                                Arg::token("PUBSUB"),
+                            ])
+                            .with_token("TYPE")
+                            .optional(),
+                            Arg::string("username").with_token("USER").optional(),
+                            Arg::string("ip:port").with_token("ADDR").optional(),
+                            Arg::string("ip:port").with_token("LADDR").optional(),
+                            Arg::one_of("skipme", &[Arg::token("YES"), Arg::token("NO")])
+                                .with_token("SKIPME")
+                                .optional(),
+                        ]),
+                    ]),
+                ]),
+            CommandSpec::new("client|list", -2, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_list(ctx.state, ctx.client, command)))
+                .flags(&[Admin])
+                .docs("Lists open connections.", "2.4.0",
+                      "O(N) where N is the number of client connections")
+                .arguments(&[
+                    Arg::one_of("client-type", &[
+                        Arg::token("NORMAL"),
+                        Arg::token("MASTER"),
+                        Arg::token("REPLICA"),
+                        Arg::token("PUBSUB"),
+                    ])
+                    .with_token("TYPE")
+                    .optional(),
+                    Arg::integer("client-id").with_token("ID").optional().multiple(),
+                ]),
+            CommandSpec::new("client|pause", -3, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_pause(ctx.state, command)))
+                .flags(&[Admin])
+                .docs("Suspends commands processing.", "3.0.0", "O(1)")
+                .arguments(&[
+                    Arg::integer("timeout"),
+                    Arg::one_of("mode", &[Arg::token("WRITE"), Arg::token("ALL")]).optional(),
+                ]),
+            CommandSpec::new("client|reply", 3, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_reply(ctx.client, command)))
+                .docs("Instructs the server whether to reply to commands.", "3.2.0", "O(1)")
+                .arguments(&[
+                    Arg::one_of("action", &[
+                        Arg::token("ON"),
+                        Arg::token("OFF"),
+                        Arg::token("SKIP"),
+                    ]),
+                ]),
+            CommandSpec::new("client|setinfo", 4, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_setinfo(ctx.client, command)))
+                .docs("Sets information specific to the client or connection.", "7.2.0", "O(1)")
+                .arguments(&[
+                    Arg::one_of("attr", &[
+                        Arg::string("libname").with_token("LIB-NAME"),
+                        Arg::string("libver").with_token("LIB-VER"),
+                    ]),
+                ]),
+            CommandSpec::new("client|setname", 3, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_setname(ctx.client, command)))
+                .docs("Sets the connection name.", "2.6.9", "O(1)")
+                .arguments(&[Arg::string("connection-name")]),
+            CommandSpec::new("client|unblock", -3, CommandGroup::Connection,
+                build_multiple_keys_command,
+                handler!(|ctx, command| client_unblock(ctx.state, command)))
+                .flags(&[Admin])
+                .docs("Unblocks a client blocked by a blocking command from a different \
+                       connection.", "5.0.0",
+                      "O(log N) where N is the number of client connections")
+                .arguments(&[
+                    Arg::integer("client-id"),
+                    Arg::one_of("unblock-type", &[Arg::token("TIMEOUT"), Arg::token("ERROR")])
+                        .optional(),
+                ]),
+            CommandSpec::new("client|unpause", 2, CommandGroup::Connection,
+                build_no_args_command,
+                handler!(|ctx, _command| client_unpause(ctx.state)))
+                .flags(&[Admin])
+                .docs("Resumes processing commands from paused clients.", "6.2.0",
+                      "O(N) Where N is the number of paused clients"),
+        ]),
+    // Transactions
+    CommandSpec::new("multi", 1, CommandGroup::Transactions, build_no_args_command,
+        handler!(|ctx, _command| multi(ctx.client)))
+        .flags(&[Fast])
+        .docs("Starts a transaction.", "1.2.0", "O(1)"),
+    CommandSpec::new("exec", 1, CommandGroup::Transactions, build_no_args_command,
+        handler!(|ctx, _command| exec(ctx)))
+        .docs("Executes all commands in a transaction.", "1.2.0",
+              "Depends on commands in the transaction"),
+    CommandSpec::new("discard", 1, CommandGroup::Transactions, build_no_args_command,
+        handler!(|ctx, _command| discard(ctx.state, ctx.client)))
+        .flags(&[Fast])
+        .docs("Discards a transaction.", "2.0.0",
+              "O(N), when N is the number of queued commands"),
+    CommandSpec::new("watch", -2, CommandGroup::Transactions, build_multiple_keys_command,
+        handler!(|ctx, command| watch(ctx.state, ctx.client, command)))
+        .flags(&[Fast])
+        .keys(1, -1, 1)
+        .docs("Monitors changes to keys to determine the execution of a transaction.",
+              "2.2.0", "O(1) for every key.")
+        .arguments(&[Arg::key("key").multiple()]),
+    CommandSpec::new("unwatch", 1, CommandGroup::Transactions, build_no_args_command,
+        handler!(|ctx, _command| unwatch(ctx.state, ctx.client)))
+        .flags(&[Fast])
+        .docs("Forgets about watched keys of a transaction.", "2.2.0", "O(1)"),
+    // Pub/Sub
+    CommandSpec::new("subscribe", -2, CommandGroup::PubSub, build_multiple_keys_command,
+        handler!(|ctx, command| subscribe(ctx.state, ctx.client, command)))
+        .flags(&[PubSub])
+        .docs("Listens for messages published to channels.", "2.0.0",
+              "O(N) where N is the number of channels to subscribe to.")
+        .arguments(&[Arg::string("channel").multiple()]),
+    CommandSpec::new("unsubscribe", -1, CommandGroup::PubSub, build_multiple_keys_command,
+        handler!(|ctx, command| unsubscribe(ctx.state, ctx.client, command)))
+        .flags(&[PubSub])
+        .docs("Stops listening to messages posted to channels.", "2.0.0",
+              "O(N) where N is the number of channels to unsubscribe.")
+        .arguments(&[Arg::string("channel").multiple().optional()]),
+    CommandSpec::new("psubscribe", -2, CommandGroup::PubSub, build_multiple_keys_command,
+        handler!(|ctx, command| psubscribe(ctx.state, ctx.client, command)))
+        .flags(&[PubSub])
+        .docs("Listens for messages published to channels that match one or more patterns.",
+              "2.0.0", "O(N) where N is the number of patterns to subscribe to.")
+        .arguments(&[Arg::pattern("pattern").multiple()]),
+    CommandSpec::new("punsubscribe", -1, CommandGroup::PubSub, build_multiple_keys_command,
+        handler!(|ctx, command| punsubscribe(ctx.state, ctx.client, command)))
+        .flags(&[PubSub])
+        .docs("Stops listening to messages published to channels that match one or more \
+               patterns.", "2.0.0",
+              "O(N) where N is the number of patterns to unsubscribe.")
+        .arguments(&[Arg::pattern("pattern").multiple().optional()]),
+    CommandSpec::new("publish", 3, CommandGroup::PubSub, build_key_with_value_command,
+        handler!(|ctx, command| publish(ctx.state, command)))
+        .flags(&[PubSub, Fast])
+        .docs("Posts a message to a channel.", "2.0.0",
+              "O(N+M) where N is the number of clients subscribed to the receiving channel \
+               and M is the total number of subscribed patterns (by any client).")
+        .arguments(&[Arg::string("channel"), Arg::string("message")]),
+    CommandSpec::new("pubsub", -2, CommandGroup::PubSub, build_no_args_command,
+        handler!(|_ctx, _command| pubsub_help()))
+        .docs("A container for Pub/Sub commands.", "2.8.0", "Depends on subcommand.")
+        .subcommands(&[
+            CommandSpec::new("pubsub|channels", -2, CommandGroup::PubSub,
+                build_multiple_keys_command,
+                handler!(|ctx, command| pubsub_channels(ctx.state, command)))
+                .flags(&[PubSub])
+                .docs("Returns the active channels.", "2.8.0",
+                      "O(N) where N is the number of active channels, and assuming constant \
+                       time pattern matching (relatively short channels and patterns)")
+                .arguments(&[Arg::pattern("pattern").optional()]),
+            CommandSpec::new("pubsub|help", 2, CommandGroup::PubSub, build_no_args_command,
+                handler!(|_ctx, _command| pubsub_help()))
+                .docs("Returns helpful text about the different subcommands.", "6.2.0", "O(1)"),
+            CommandSpec::new("pubsub|numpat", 2, CommandGroup::PubSub, build_no_args_command,
+                handler!(|ctx, _command| pubsub_numpat(ctx.state)))
+                .flags(&[PubSub])
+                .docs("Returns a count of unique pattern subscriptions.", "2.8.0", "O(1)"),
+            CommandSpec::new("pubsub|numsub", -2, CommandGroup::PubSub,
+                build_multiple_keys_command,
+                handler!(|ctx, command| pubsub_numsub(ctx.state, command)))
+                .flags(&[PubSub])
+                .docs("Returns a count of subscribers to channels.", "2.8.0",
+                      "O(N) for the NUMSUB subcommand, where N is the number of requested \
+                       channels")
+                .arguments(&[Arg::string("channel").multiple().optional()]),
+        ]),
+    // Server
+    CommandSpec::new("flushdb", -1, CommandGroup::Server, build_flush_command,
+        handler!(|ctx, command| flush_db(ctx.db, command)))
+        .flags(&[Write])
+        .docs("Removes all keys from the current database.", "1.0.0",
+              "O(N) where N is the number of keys in the selected database.")
+        .arguments(&[FLUSH_MODE]),
+    CommandSpec::new("flushall", -1, CommandGroup::Server, build_flush_command,
+        handler!(|ctx, command| flush_all(ctx.state, command)))
+        .flags(&[Write])
+        .docs("Removes all keys from all databases.", "1.0.0",
+              "O(N) where N is the total number of keys in all databases.")
+        .arguments(&[FLUSH_MODE]),
+    CommandSpec::new("swapdb", 3, CommandGroup::Server, build_swapdb_command,
+        handler!(|ctx, command| swap_db(ctx.state, command)))
+        .flags(&[Write, Fast])
+        .docs("Swaps two Redis databases.", "4.0.0",
+              "O(N) where N is the count of clients watching or blocking on keys from both \
+               databases.")
+        .arguments(&[Arg::integer("index1"), Arg::integer("index2")]),
+    CommandSpec::new("config", -2, CommandGroup::Server, build_no_args_command,
+        handler!(|_ctx, _command| config_help()))
+        .docs("A container for server configuration commands.", "2.0.0",
+              "Depends on subcommand.")
+        .subcommands(&[
+            CommandSpec::new("config|get", -3, CommandGroup::Server,
+                build_multiple_keys_command,
+                handler!(|ctx, command| config_get(ctx.state, command)))
+                .flags(&[Admin])
+                .docs("Returns the effective values of configuration parameters.", "2.0.0",
+                      "O(N) when N is the number of configuration parameters provided")
+                .arguments(&[Arg::string("parameter").multiple()]),
+            CommandSpec::new("config|help", 2, CommandGroup::Server, build_no_args_command,
+                handler!(|_ctx, _command| config_help()))
+                .docs("Returns helpful text about the different subcommands.", "5.0.0", "O(1)"),
+            CommandSpec::new("config|set", -4, CommandGroup::Server,
+                build_multiple_keys_command,
+                handler!(|ctx, command| config_set(ctx.state, command)))
+                .flags(&[Admin])
+                .docs("Sets configuration parameters in-flight.", "2.0.0",
+                      "O(N) when N is the number of configuration parameters provided")
+                .arguments(&[
+                    Arg::block("data", &[Arg::string("parameter"), Arg::string("value")])
+                        .multiple(),
+                ]),
+        ]),
+    CommandSpec::new("dbsize", 1, CommandGroup::Server, build_no_args_command,
+        handler!(|ctx, _command| db_size(ctx.db)))
+        .flags(&[ReadOnly, Fast])
+        .docs("Returns the number of keys in the database.", "1.0.0", "O(1)"),
+    CommandSpec::new("command", -1, CommandGroup::Server, build_no_args_command,
+        handler!(|_ctx, command| command_info(command)))
+        .docs("Returns detailed information about all commands.", "2.8.13",
+              "O(N) where N is the total number of commands.")
+        .subcommands(&[
+            CommandSpec::new("command|count", 2, CommandGroup::Server, build_no_args_command,
+                handler!(|_ctx, _command| command_count()))
+                .docs("Returns a count of commands.", "2.8.13", "O(1)"),
+            CommandSpec::new("command|docs", -2, CommandGroup::Server,
+                build_multiple_keys_command,
+                handler!(|_ctx, command| command_docs(command)))
+                .docs("Returns documentary information about one, multiple or all commands.",
+                      "7.0.0", "O(N) where N is the number of commands to look up.")
+                .arguments(&[Arg::string("command-name").optional().multiple()]),
+            CommandSpec::new("command|getkeys", -3, CommandGroup::Server,
+                build_multiple_keys_command,
+                handler!(|_ctx, command| command_getkeys(command)))
+                .docs("Extracts the key names from an arbitrary command.", "2.8.13",
+                      "O(N) where N is the number of arguments to the command.")
+                .arguments(&[Arg::string("command"), Arg::string("arg").optional().multiple()]),
+            CommandSpec::new("command|help", 2, CommandGroup::Server, build_no_args_command,
+                handler!(|_ctx, _command| command_help()))
+                .docs("Returns helpful text about the different subcommands.", "5.0.0", "O(1)"),
+            CommandSpec::new("command|info", -2, CommandGroup::Server,
+                build_multiple_keys_command,
+                handler!(|_ctx, command| command_info(command)))
+                .docs("Returns information about one, multiple or all commands.", "2.8.13",
+                      "O(N) where N is the number of commands to look up.")
+                .arguments(&[Arg::string("command-name").optional().multiple()]),
+            CommandSpec::new("command|list", -2, CommandGroup::Server,
+                build_multiple_keys_command,
+                handler!(|_ctx, command| command_list(command)))
+                .docs("Returns a list of command names.", "7.0.0",
+                      "O(N) where N is the total number of commands.")
+                .arguments(&[
+                    Arg::one_of("filterby", &[
+                        Arg::string("module-name").with_token("MODULE"),
+                        Arg::string("category").with_token("ACLCAT"),
+                        Arg::pattern("pattern").with_token("PATTERN"),
+                    ])
+                    .with_token("FILTERBY")
+                    .optional(),
+                ]),
+        ]),
+];
+
+static COMMANDS_BY_NAME: LazyLock<HashMap<&'static str, &'static CommandSpec>> =
+    LazyLock::new(|| COMMANDS.iter().map(|spec| (spec.name, spec)).collect());
+
+/// Finds a command by name, ignoring the case
+pub fn lookup_command(name: &[u8]) -> Option<&'static CommandSpec> {
+    let name = std::str::from_utf8(name).ok()?.to_ascii_lowercase();
+    COMMANDS_BY_NAME.get(name.as_str()).copied()
+}
+
+/// Finds the command, or the subcommand of a container command, `argv` is
+/// calling. Container commands called without a subcommand are returned
+/// as is.
+pub fn resolve_command(argv: &[Bytes]) -> Result<&'static CommandSpec, ZystError> {
+    let Some(name) = argv.first() else {
+        return Err(ZystError::InvalidCommand);
+    };
+
+    let Some(spec) = lookup_command(name) else {
+        let quoted = argv[1..]
+            .iter()
+            .map(|arg| format!("'{}' ", String::from_utf8_lossy(arg)))
+            .collect::<String>();
+
+        return Err(ZystError::UnknownCommand(
+            String::from_utf8_lossy(name).into_owned(),
+            quoted,
+        ));
+    };
+
+    match argv.get(1) {
+        Some(sub) if !spec.subcommands.is_empty() => spec.subcommand(sub).ok_or_else(|| {
+            ZystError::UnknownSubcommand(
+                String::from_utf8_lossy(sub).into_owned(),
+                spec.name.to_uppercase(),
+            )
+        }),
+        _ => Ok(spec),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::errors::ZystError;
+use crate::glob::string_match;
+use crate::response::ZystResponse;
+use crate::types::{Command, CommandArgs, Db, DbValue, ScanOptions};
+use bytes::Bytes;
+use std::ops::Range;
+
+// Keys, set members and hash fields are all stored in insertion ordered
+// collections, and the cursor is a position in them. Positions are walked
+// from the end towards the start: removing an element only moves the last
+// one into its slot, and new elements are appended at the end. Elements
+// that weren't visited yet can only move to a lower position, so they are
+// still returned, and elements present during the whole scan are never
+// missed. An element can be returned twice when it moves, as in Redis.
+
+/// Positions a call visits, given the length of the collection. Every
+/// position below the returned cursor is left to the next calls, a cursor
+/// of 0 starts or ends the iteration.
+fn scan_positions(len: usize, cursor: u64, count: usize) -> (u64, Range<usize>) {
+    let end = match cursor {
+        0 => len,
+        cursor => usize::try_from(cursor).unwrap_or(usize::MAX).min(len),
+    };
+    let start = end.saturating_sub(count);
+
+    (start as u64, start..end)
+}
+
+fn scan_args(command: Command) -> Result<(Option<Bytes>, u64, ScanOptions), ZystError> {
+    match command.args {
+        CommandArgs::Scan {
+            key,
+            cursor,
+            options,
+        } => Ok((key, cursor, options)),
+        _ => Err(ZystError::InvalidCommand),
+    }
+}
+
+fn matches(options: &ScanOptions, element: &[u8]) -> bool {
+    options
+        .pattern
+        .as_ref()
+        .is_none_or(|pattern| string_match(pattern, element, false))
+}
+
+fn scan_reply(cursor: u64, elements: Vec<Bytes>) -> ZystResponse {
+    ZystResponse::Array(vec![
+        ZystResponse::BulkString(Bytes::from(cursor.to_string())),
+        ZystResponse::List(elements),
+    ])
+}
+
+/// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+pub async fn scan(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (_, cursor, options) = scan_args(command)?;
+
+    let db_read = db.read().await;
+    let (cursor, positions) = scan_positions(db_read.len(), cursor, options.count);
+
+    let keys = positions
+        .rev()
+        .filter_map(|index| db_read.get_index(index))
+        .filter(|(_, value)| options.kind.is_none_or(|kind| value.type_name() == kind))
+        .filter(|(key, _)| matches(&options, key))
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    Ok(scan_reply(cursor, keys))
+}
+
+/// SSCAN key cursor [MATCH pattern] [COUNT count]
+pub async fn sscan(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, cursor, options) = scan_args(command)?;
+    let key_name = key_name.ok_or(ZystError::InvalidCommand)?;
+
+    let db_read = db.read().await;
+
+    let set = match db_read.get(&key_name) {
+        Some(DbValue::SetKey(set)) => &set.data,
+        None => return Ok(scan_reply(0, Vec::new())),
+        Some(_) => return Err(ZystError::WrongType),
+    };
+
+    let (cursor, positions) = scan_positions(set.len(), cursor, options.count);
+
+    let members = positions
+        .rev()
+        .filter_map(|index| set.get_index(index))
+        .filter(|member| matches(&options, member))
+        .cloned()
+        .collect();
+
+    Ok(scan_reply(cursor, members))
+}
+
+/// HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+///
+/// Replies with fields and their values one after the other, or only the
+/// fields with NOVALUES
+pub async fn hscan(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, cursor, options) = scan_args(command)?;
+    let key_name = key_name.ok_or(ZystError::InvalidCommand)?;
+
+    let db_read = db.read().await;
+
+    let hash = match db_read.get(&key_name) {
+        Some(DbValue::HashKey(hash)) => &hash.data,
+        None => return Ok(scan_reply(0, Vec::new())),
+        Some(_) => return Err(ZystError::WrongType),
+    };
+
+    let (cursor, positions) = scan_positions(hash.len(), cursor, options.count);
+
+    let mut elements = Vec::new();
+    for (field, value) in positions.rev().filter_map(|index| hash.get_index(index)) {
+        if matches(&options, field) {
+            elements.push(field.clone());
+            if !options.no_values {
+                elements.push(value.clone());
+            }
+        }
+    }
+
+    Ok(scan_reply(cursor, elements))
+}
@@ -1,7 +1,12 @@
 use crate::errors::ZystError;
+use crate::keys::{random_index, random_positions};
+use crate::keyspace::Keyspace;
+use crate::notify::NotifyFlags;
+use crate::parser::parse_number;
 use crate::response::ZystResponse;
 use crate::types::{Command, CommandArgs, Db, DbValue, KeySet};
-use std::collections::HashSet;
+use bytes::Bytes;
+use indexmap::IndexSet;
 
 pub async fn sadd(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
     let (set_name, values) = match command.args {
@@ -11,24 +16,32 @@ pub async fn sadd(db: &Db, command: Command) -> Result<ZystResponse, ZystError>
 
     let mut db_write = db.write().await;
 
-    match db_write.get_mut(&set_name) {
+    let (reply, added) = match db_write.get_mut(&set_name) {
         Some(DbValue::SetKey(db_set)) => {
+            let before = db_set.data.len();
             db_set.data.extend(values);
-            let nb = db_set.data.len() as i64;
-            Ok(ZystResponse::Int(nb))
+            let added = db_set.data.len() - before;
+            (added as i64, added > 0)
         }
         None => {
+            let data = IndexSet::from_iter(values);
+            let added = data.len();
             let new_set = DbValue::SetKey(KeySet {
                 name: set_name.clone(),
-                data: HashSet::from_iter(values.clone()),
+                data,
                 ..Default::default()
             });
-            db_write.insert(set_name, new_set);
-            let nb = values.len() as i64;
-            Ok(ZystResponse::Int(nb))
+            db_write.insert(set_name.clone(), new_set);
+            (added as i64, true)
         }
-        Some(_) => Err(ZystError::WrongType),
+        Some(_) => return Err(ZystError::WrongType),
+    };
+
+    if added {
+        db_write.signal_modified(&set_name);
+        db_write.notify(NotifyFlags::SET, "sadd", set_name);
     }
+    Ok(ZystResponse::Int(reply))
 }
 
 pub async fn smembers(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
@@ -40,12 +53,19 @@ pub async fn smembers(db: &Db, command: Command) -> Result<ZystResponse, ZystErr
     let db_read = db.read().await;
 
     let results = match db_read.get(key_name) {
-        Some(DbValue::SetKey(key)) => key.data.iter().cloned().collect::<Vec<String>>(),
-        None => return Ok(ZystResponse::EmptyArray),
+        Some(DbValue::SetKey(key)) => key
+            .data
+            .iter()
+            .map(|member| ZystResponse::BulkString(member.clone()))
+            .collect::<Vec<_>>(),
+        None => {
+            db_read.notify(NotifyFlags::KEY_MISS, "keymiss", key_name.clone());
+            Vec::new()
+        }
         Some(_) => return Err(ZystError::WrongType),
     };
 
-    Ok(ZystResponse::List(results))
+    Ok(ZystResponse::Set(results))
 }
 
 pub async fn srem(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
@@ -60,13 +80,21 @@ pub async fn srem(db: &Db, command: Command) -> Result<ZystResponse, ZystError>
         Some(DbValue::SetKey(key)) => {
             let mut deleted_count = 0;
             for member in members {
-                if key.data.remove(&member) {
+                if key.data.swap_remove(&member) {
                     deleted_count += 1;
                 }
             }
 
-            if key.data.is_empty() {
+            let emptied = key.data.is_empty();
+
+            if deleted_count > 0 {
+                db_write.notify(NotifyFlags::SET, "srem", set_name.clone());
+            }
+            if emptied {
                 db_write.swap_remove(&set_name);
+                db_write.notify(NotifyFlags::GENERIC, "del", set_name);
+            } else if deleted_count > 0 {
+                db_write.signal_modified(&set_name);
             }
             Ok(ZystResponse::Int(deleted_count))
         }
@@ -74,3 +102,399 @@ pub async fn srem(db: &Db, command: Command) -> Result<ZystResponse, ZystError>
         None => Ok(ZystResponse::Int(0)),
     }
 }
+
+// Members of the set at a key, None when the key doesn't exist
+fn lookup_set<'a>(
+    keyspace: &'a Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a IndexSet<Bytes>>, ZystError> {
+    match keyspace.get(key) {
+        Some(DbValue::SetKey(set)) => Ok(Some(&set.data)),
+        Some(_) => Err(ZystError::WrongType),
+        None => {
+            keyspace.notify(NotifyFlags::KEY_MISS, "keymiss", key.clone());
+            Ok(None)
+        }
+    }
+}
+
+pub async fn sismember(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, member) = match command.args {
+        CommandArgs::KeyWithValue { key, value } => (key, value),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let found = lookup_set(&db_read, &key_name)?.is_some_and(|set| set.contains(&member));
+
+    Ok(ZystResponse::Int(found as i64))
+}
+
+pub async fn smismember(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, members) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let set = lookup_set(&db_read, &key_name)?;
+
+    Ok(ZystResponse::Array(
+        members
+            .iter()
+            .map(|member| ZystResponse::Int(set.is_some_and(|set| set.contains(member)) as i64))
+            .collect(),
+    ))
+}
+
+pub async fn scard(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let key_name = match command.args {
+        CommandArgs::SingleKey(key) => key,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let len = lookup_set(&db_read, &key_name)?.map_or(0, IndexSet::len);
+
+    Ok(ZystResponse::Int(len as i64))
+}
+
+/// SPOP removes random members, it is logged as the SREM of the members it
+/// removed so that replaying it removes the same ones
+pub async fn spop(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, count) = match command.args {
+        CommandArgs::SingleKey(key) => (key, None),
+        CommandArgs::KeyWithValue { key, value } => (key, Some(value)),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let count = match count {
+        Some(count) => {
+            let count = parse_number::<i64>(&count).ok_or(ZystError::NotIntOrOutOfRange)?;
+            Some(usize::try_from(count).map_err(|_| {
+                ZystError::Custom("ERR value is out of range, must be positive".to_string())
+            })?)
+        }
+        None => None,
+    };
+
+    let mut db_write = db.write().await;
+
+    let set = match db_write.get_mut(&key_name) {
+        Some(DbValue::SetKey(set)) => set,
+        Some(_) => return Err(ZystError::WrongType),
+        None if count.is_some() => return Ok(ZystResponse::Set(Vec::new())),
+        None => return Ok(ZystResponse::Nil),
+    };
+
+    let mut popped = Vec::new();
+    while popped.len() < count.unwrap_or(1) && !set.data.is_empty() {
+        let Some(member) = set.data.swap_remove_index(random_index(set.data.len())) else {
+            break;
+        };
+        popped.push(member);
+    }
+    let emptied = set.data.is_empty();
+
+    if !popped.is_empty() {
+        db_write.signal_modified(&key_name);
+        db_write.notify(NotifyFlags::SET, "spop", key_name.clone());
+    }
+    if emptied {
+        db_write.swap_remove(&key_name);
+        db_write.notify(NotifyFlags::GENERIC, "del", key_name);
+    }
+
+    match count {
+        Some(_) => Ok(ZystResponse::Set(
+            popped.into_iter().map(ZystResponse::BulkString).collect(),
+        )),
+        None => Ok(popped
+            .pop()
+            .map_or(ZystResponse::Nil, ZystResponse::BulkString)),
+    }
+}
+
+/// Logs SPOP as the removal of the members it popped, nothing when it
+/// didn't pop any
+pub fn propagate_spop(argv: &[Bytes], response: &ZystResponse) -> Vec<Bytes> {
+    let popped = match response {
+        ZystResponse::BulkString(member) => vec![member.clone()],
+        ZystResponse::Set(members) => members
+            .iter()
+            .filter_map(|member| match member {
+                ZystResponse::BulkString(member) => Some(member.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if popped.is_empty() {
+        return Vec::new();
+    }
+
+    let mut srem = vec![Bytes::from_static(b"SREM"), argv[1].clone()];
+    srem.extend(popped);
+    srem
+}
+
+/// SRANDMEMBER returns distinct members with a positive count, and may
+/// return the same member several times with a negative one
+pub async fn srandmember(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (key_name, count) = match command.args {
+        CommandArgs::Srandmember { key, count } => (key, count),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+
+    let members = match lookup_set(&db_read, &key_name)? {
+        Some(members) => members,
+        None if count.is_some() => return Ok(ZystResponse::EmptyArray),
+        None => return Ok(ZystResponse::Nil),
+    };
+
+    let Some(count) = count else {
+        return Ok(members
+            .get_index(random_index(members.len()))
+            .map_or(ZystResponse::Nil, |member| {
+                ZystResponse::BulkString(member.clone())
+            }));
+    };
+
+    let picked: Vec<Bytes> = random_positions(members.len(), count)
+        .into_iter()
+        .filter_map(|position| members.get_index(position).cloned())
+        .collect();
+
+    if picked.is_empty() {
+        return Ok(ZystResponse::EmptyArray);
+    }
+    Ok(ZystResponse::List(picked))
+}
+
+pub async fn smove(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (source, destination, member) = match command.args {
+        CommandArgs::SetMove {
+            source,
+            destination,
+            member,
+        } => (source, destination, member),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+
+    match db_write.get(&source) {
+        Some(DbValue::SetKey(_)) => {}
+        Some(_) => return Err(ZystError::WrongType),
+        None => return Ok(ZystResponse::Int(0)),
+    }
+    let found = match db_write.get(&destination) {
+        Some(DbValue::SetKey(set)) => set.data.contains(&member),
+        Some(_) => return Err(ZystError::WrongType),
+        None => false,
+    };
+
+    // Moving to the same set changes nothing
+    if source == destination {
+        return Ok(ZystResponse::Int(found as i64));
+    }
+
+    let Some(DbValue::SetKey(set)) = db_write.get_mut(&source) else {
+        return Ok(ZystResponse::Int(0));
+    };
+    if !set.data.swap_remove(&member) {
+        return Ok(ZystResponse::Int(0));
+    }
+    let emptied = set.data.is_empty();
+
+    db_write.signal_modified(&source);
+    db_write.notify(NotifyFlags::SET, "srem", source.clone());
+    if emptied {
+        db_write.swap_remove(&source);
+        db_write.notify(NotifyFlags::GENERIC, "del", source);
+    }
+
+    let added = match db_write.get_mut(&destination) {
+        Some(DbValue::SetKey(set)) => set.data.insert(member),
+        _ => {
+            let set = KeySet {
+                name: destination.clone(),
+                data: IndexSet::from([member]),
+                ..Default::default()
+            };
+            db_write.insert(destination.clone(), DbValue::SetKey(set));
+            true
+        }
+    };
+    if added {
+        db_write.signal_modified(&destination);
+        db_write.notify(NotifyFlags::SET, "sadd", destination);
+    }
+
+    Ok(ZystResponse::Int(1))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SetOperation {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl SetOperation {
+    // Event of the STORE variant
+    fn store_event(self) -> &'static str {
+        match self {
+            SetOperation::Inter => "sinterstore",
+            SetOperation::Union => "sunionstore",
+            SetOperation::Diff => "sdiffstore",
+        }
+    }
+}
+
+// Members of every set, starting from the smallest one
+fn intersection<'a: 'b, 'b>(
+    sets: &'b [&'a IndexSet<Bytes>],
+) -> impl Iterator<Item = &'a Bytes> + 'b {
+    let smallest = sets.iter().min_by_key(|set| set.len()).copied();
+
+    smallest
+        .into_iter()
+        .flatten()
+        .filter(|member| sets.iter().all(|set| set.contains(*member)))
+}
+
+// Combines the sets at the keys, a missing key counts as an empty set
+fn combine_sets(
+    keyspace: &Keyspace,
+    keys: &[Bytes],
+    operation: SetOperation,
+) -> Result<IndexSet<Bytes>, ZystError> {
+    let sets = keys
+        .iter()
+        .map(|key| lookup_set(keyspace, key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let members = match operation {
+        SetOperation::Inter => match sets.iter().copied().collect::<Option<Vec<_>>>() {
+            Some(sets) => intersection(&sets).cloned().collect(),
+            None => IndexSet::new(),
+        },
+        SetOperation::Union => sets
+            .iter()
+            .flatten()
+            .flat_map(|set| set.iter())
+            .cloned()
+            .collect(),
+        SetOperation::Diff => match sets.split_first() {
+            Some((Some(first), others)) => first
+                .iter()
+                .filter(|member| !others.iter().flatten().any(|set| set.contains(*member)))
+                .cloned()
+                .collect(),
+            _ => IndexSet::new(),
+        },
+    };
+
+    Ok(members)
+}
+
+async fn set_operation(
+    db: &Db,
+    command: Command,
+    operation: SetOperation,
+) -> Result<ZystResponse, ZystError> {
+    let keys = match command.args {
+        CommandArgs::MultipleKeys(keys) => keys,
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let members = combine_sets(&db_read, &keys, operation)?;
+
+    Ok(ZystResponse::Set(
+        members.into_iter().map(ZystResponse::BulkString).collect(),
+    ))
+}
+
+// Stores the result at the destination, whatever it held, or deletes it
+// when the result is empty. Replies with the size of the result.
+async fn store_set_operation(
+    db: &Db,
+    command: Command,
+    operation: SetOperation,
+) -> Result<ZystResponse, ZystError> {
+    let (destination, keys) = match command.args {
+        CommandArgs::KeyWithValues { key, values } => (key, values),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let mut db_write = db.write().await;
+    let members = combine_sets(&db_write, &keys, operation)?;
+    let len = members.len();
+
+    if members.is_empty() {
+        if db_write.swap_remove(&destination).is_some() {
+            db_write.notify(NotifyFlags::GENERIC, "del", destination);
+        }
+    } else {
+        let set = KeySet {
+            name: destination.clone(),
+            data: members,
+            ..Default::default()
+        };
+        db_write.insert(destination.clone(), DbValue::SetKey(set));
+        db_write.notify(NotifyFlags::SET, operation.store_event(), destination);
+    }
+
+    Ok(ZystResponse::Int(len as i64))
+}
+
+pub async fn sinter(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    set_operation(db, command, SetOperation::Inter).await
+}
+
+pub async fn sunion(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    set_operation(db, command, SetOperation::Union).await
+}
+
+pub async fn sdiff(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    set_operation(db, command, SetOperation::Diff).await
+}
+
+pub async fn sinterstore(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    store_set_operation(db, command, SetOperation::Inter).await
+}
+
+pub async fn sunionstore(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    store_set_operation(db, command, SetOperation::Union).await
+}
+
+pub async fn sdiffstore(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    store_set_operation(db, command, SetOperation::Diff).await
+}
+
+/// SINTERCARD stops counting once it reaches the limit
+pub async fn sintercard(db: &Db, command: Command) -> Result<ZystResponse, ZystError> {
+    let (keys, limit) = match command.args {
+        CommandArgs::Sintercard { keys, limit } => (keys, limit),
+        _ => return Err(ZystError::InvalidCommand),
+    };
+
+    let db_read = db.read().await;
+    let sets = keys
+        .iter()
+        .map(|key| lookup_set(&db_read, key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let limit = if limit == 0 { usize::MAX } else { limit };
+    let count = match sets.into_iter().collect::<Option<Vec<_>>>() {
+        Some(sets) => intersection(&sets).take(limit).count(),
+        None => 0,
+    };
+
+    Ok(ZystResponse::Int(count as i64))
+}
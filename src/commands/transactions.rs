@@ -0,0 +1,150 @@
+use crate::client::{Client, Transaction, WatchedKey};
+use crate::commands::registry::Context;
+use crate::errors::ZystError;
+use crate::parser::check_command;
+use crate::process::execute;
+use crate::response::ZystResponse;
+use crate::state::State;
+use crate::types::{Command, CommandArgs};
+use bytes::Bytes;
+
+/// Queues the next commands of the connection until EXEC or DISCARD
+pub async fn multi(client: &mut Client) -> Result<ZystResponse, ZystError> {
+    if client.transaction.is_some() {
+        return Err(ZystError::Custom(
+            "ERR MULTI calls can not be nested".to_string(),
+        ));
+    }
+
+    client.transaction = Some(Transaction::default());
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn discard(state: &State, client: &mut Client) -> Result<ZystResponse, ZystError> {
+    if client.transaction.take().is_none() {
+        return Err(ZystError::Custom("ERR DISCARD without MULTI".to_string()));
+    }
+
+    unwatch_all(state, client).await;
+    Ok(ZystResponse::Ok)
+}
+
+/// Makes the next EXEC fail if one of the keys is modified, expires or is
+/// deleted in the meantime
+pub async fn watch(
+    state: &State,
+    client: &mut Client,
+    command: Command,
+) -> Result<ZystResponse, ZystError> {
+    let CommandArgs::MultipleKeys(keys) = command.args else {
+        return Err(ZystError::InvalidCommand);
+    };
+
+    if client.transaction.is_some() {
+        return Err(ZystError::Custom(
+            "ERR WATCH inside MULTI is not allowed".to_string(),
+        ));
+    }
+
+    let db = state.db(client.db).ok_or(ZystError::DatabaseError)?;
+    let mut keyspace = db.write().await;
+
+    for key in keys {
+        let watching = client
+            .watched
+            .iter()
+            .any(|watched| watched.db == client.db && watched.key == key);
+        if !watching {
+            let version = keyspace.watch(&key);
+            client.watched.push(WatchedKey {
+                db: client.db,
+                key,
+                version,
+            });
+        }
+    }
+
+    Ok(ZystResponse::Ok)
+}
+
+pub async fn unwatch(state: &State, client: &mut Client) -> Result<ZystResponse, ZystError> {
+    unwatch_all(state, client).await;
+    Ok(ZystResponse::Ok)
+}
+
+/// Forgets the keys watched by a client, done after EXEC or DISCARD and
+/// when it disconnects
+pub async fn unwatch_all(state: &State, client: &mut Client) {
+    for watched in client.watched.drain(..) {
+        if let Some(db) = state.db(watched.db) {
+            db.write().await.unwatch(&watched.key);
+        }
+    }
+}
+
+// Whether a watched key changed since WATCH
+async fn is_dirty(state: &State, client: &Client) -> bool {
+    for watched in &client.watched {
+        let Some(db) = state.db(watched.db) else {
+            return true;
+        };
+        if db.write().await.version(&watched.key) != Some(watched.version) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Runs the queued commands and replies with their replies. Errors don't
+/// stop the transaction, they are replied in place. Nothing runs when a
+/// watched key changed, the reply is then a null array.
+///
+/// The dispatcher runs EXEC holding the command gate exclusively, so no
+/// other client sees the transaction half done. Its writes are logged
+/// between MULTI and EXEC, restoring the AOF replays all of them or none.
+pub async fn exec(ctx: &mut Context<'_>) -> Result<ZystResponse, ZystError> {
+    let Some(transaction) = ctx.client.transaction.take() else {
+        return Err(ZystError::Custom("ERR EXEC without MULTI".to_string()));
+    };
+
+    let dirty = is_dirty(ctx.state, ctx.client).await;
+    unwatch_all(ctx.state, ctx.client).await;
+
+    if transaction.aborted {
+        return Err(ZystError::Custom(
+            "EXECABORT Transaction discarded because of previous errors.".to_string(),
+        ));
+    }
+
+    if dirty {
+        return Ok(ZystResponse::NilArray);
+    }
+
+    let mut replies = Vec::with_capacity(transaction.commands.len());
+    let mut propagated = Vec::new();
+
+    // Blocking commands reply right away, the transaction can't wait
+    let deny_blocking = std::mem::replace(&mut ctx.client.deny_blocking, true);
+    for command in transaction.commands {
+        let result = match check_command(&command) {
+            Ok(spec) => execute(spec, command, ctx.state, ctx.client, &mut propagated).await,
+            Err(e) => Err(e),
+        };
+        replies.push(result.unwrap_or_else(ZystResponse::Error));
+    }
+    ctx.client.deny_blocking = deny_blocking;
+
+    if let [(first_db, _), .., (last_db, _)] = &propagated[..] {
+        let (first_db, last_db) = (*first_db, *last_db);
+        ctx.propagated
+            .push((first_db, vec![Bytes::from_static(b"MULTI")]));
+        ctx.propagated.append(&mut propagated);
+        ctx.propagated
+            .push((last_db, vec![Bytes::from_static(b"EXEC")]));
+    } else {
+        ctx.propagated.append(&mut propagated);
+    }
+
+    Ok(ZystResponse::Array(replies))
+}
@@ -1,3 +1,6 @@
+use crate::database::DEFAULT_HZ;
+use crate::resp::DEFAULT_MAX_BULK_LEN;
+use crate::state::DEFAULT_DATABASES;
 use clap::Parser;
 use config::{Config, File};
 use dirs::config_dir;
@@ -14,6 +17,24 @@ struct Cli {
 
     #[arg(long, short, default_value_t = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))]
     pub bind: IpAddr,
+
+    /// Maximum size of a single bulk string in a request, in bytes
+    #[arg(long, default_value_t = DEFAULT_MAX_BULK_LEN)]
+    pub proto_max_bulk_len: usize,
+
+    /// How many times per second background tasks, such as deleting
+    /// expired keys, run. Between 1 and 500.
+    #[arg(long, default_value_t = DEFAULT_HZ)]
+    pub hz: u64,
+
+    /// Number of logical databases, selected by index with SELECT
+    #[arg(long, default_value_t = DEFAULT_DATABASES)]
+    pub databases: usize,
+
+    /// Classes of keyspace events published, such as `Ex` for expired
+    /// keys. Empty disables notifications.
+    #[arg(long, default_value = "")]
+    pub notify_keyspace_events: String,
 }
 
 fn get_config_path() -> PathBuf {
@@ -52,6 +73,14 @@ static CONFIG: Lazy<Config> = Lazy::new(|| {
         .expect("Failed to set port override")
         .set_override("bind", cli.bind.to_string())
         .expect("Failed to set bind override")
+        .set_override("proto-max-bulk-len", cli.proto_max_bulk_len as u64)
+        .expect("Failed to set proto-max-bulk-len override")
+        .set_override("hz", cli.hz)
+        .expect("Failed to set hz override")
+        .set_override("databases", cli.databases as u64)
+        .expect("Failed to set databases override")
+        .set_override("notify-keyspace-events", cli.notify_keyspace_events)
+        .expect("Failed to set notify-keyspace-events override")
         .build()
         .expect("Failed to load config")
 });
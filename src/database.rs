@@ -1,27 +1,82 @@
 use crate::aof::get_aof_log_dir;
+use crate::client::Client;
+use crate::notify::publish_events;
 use crate::process::process_command;
-use crate::types::{Db, DbValue};
-use tokio::time::{self, Duration};
-use tracing::info;
+use crate::resp::RespDecoder;
+use crate::state::State;
+use bytes::BytesMut;
+use std::sync::Arc;
+use tokio::task;
+use tokio::time::{self, Duration, Instant};
+use tracing::{error, info};
 
-pub async fn delete_expired_keys(db: Db) {
-    let mut interval = time::interval(Duration::from_secs(60));
+/// Keys sampled from the expires index per loop of the cycle
+const EXPIRE_KEYS_PER_LOOP: usize = 20;
+
+/// The cycle stops once at most this percentage of the sampled keys were
+/// expired, more are likely to be left otherwise
+const EXPIRE_ACCEPTABLE_STALE_PERCENT: usize = 10;
+
+/// Share of each `1/hz` period the cycle may spend deleting keys
+const EXPIRE_CYCLE_BUDGET_PERCENT: u32 = 25;
+
+pub const DEFAULT_HZ: u64 = 10;
+
+/// Actively deletes expired keys `hz` times per second, like Redis does,
+/// so keys that are never read again don't linger in memory.
+///
+/// Each run samples keys from the expires index of every database by
+/// batches, taking the write lock for one batch at a time, and keeps going
+/// on a database while a batch has too many expired keys and the time
+/// budget, shared by all databases, isn't spent.
+pub async fn active_expire_cycle(state: Arc<State>, hz: u64) {
+    let period = Duration::from_secs(1) / hz.clamp(1, 500) as u32;
+    let budget = period * EXPIRE_CYCLE_BUDGET_PERCENT / 100;
+    let mut interval = time::interval(period);
 
     loop {
         interval.tick().await;
-        info!("Deleting expired keys");
-
-        let mut db_write = db.write().await;
-        db_write.retain(|_, value| match value {
-            DbValue::StringKey(key) => !key.is_expired(),
-            DbValue::ListKey(key) => !key.is_expired(),
-            DbValue::SetKey(key) => !key.is_expired(),
-            DbValue::HashKey(key) => !key.is_expired(),
-        });
+
+        // Expiring deletes keys, which a CLIENT PAUSE for writes must hold
+        // back as well
+        if state.client_pause().is_paused(true) {
+            continue;
+        }
+
+        let started = Instant::now();
+
+        for (index, db) in state.databases().iter().enumerate() {
+            if started.elapsed() >= budget {
+                break;
+            }
+
+            loop {
+                // Keys don't expire in the middle of a transaction
+                let (sampled, expired) = {
+                    let _gate = state.command_gate().read().await;
+                    let mut keyspace = db.write().await;
+                    let sample = keyspace.expire_sample(EXPIRE_KEYS_PER_LOOP);
+                    publish_events(&state, index, &keyspace);
+                    sample
+                };
+
+                if sampled == 0
+                    || expired * 100 <= sampled * EXPIRE_ACCEPTABLE_STALE_PERCENT
+                    || started.elapsed() >= budget
+                {
+                    break;
+                }
+
+                // Lets waiting clients in between batches
+                task::yield_now().await;
+            }
+        }
     }
 }
 
-pub async fn restore_from_aof(db: Db) {
+/// Replays the AOF file, whose SELECT commands switch the database the
+/// following commands apply to.
+pub async fn restore_from_aof(state: Arc<State>) {
     info!("Restoring DB from AOF file");
     let log_path = get_aof_log_dir();
     let file_path = log_path.join("appendonly.aof");
@@ -30,21 +85,35 @@ pub async fn restore_from_aof(db: Db) {
         return;
     }
 
-    let content = tokio::fs::read_to_string(file_path)
+    let content = tokio::fs::read(file_path)
         .await
         .expect("Failed to read AOF file");
 
-    let commands: Vec<&str> = content.split("\n").collect();
+    // Commands are stored in RESP. Files written by older versions hold one
+    // space separated command per line, which the decoder reads as inline
+    // commands.
+    let mut buffer = BytesMut::from(&content[..]);
+    let mut decoder = RespDecoder::default();
+    let mut client = Client::new();
+    // Pops logged by blocking commands found elements when they ran
+    client.deny_blocking = true;
+
+    loop {
+        let command = match decoder.decode(&mut buffer) {
+            Ok(Some(command)) => command,
+            // A truncated last command is ignored
+            Ok(None) => break,
+            Err(e) => {
+                error!("Corrupted AOF file: {e}");
+                break;
+            }
+        };
 
-    for command in commands {
         if command.is_empty() {
             continue;
         }
 
-        let command_vec: Vec<String> =
-            command.split_whitespace().map(|s| s.to_string()).collect();
-
-        let _ = process_command(command_vec, &db, false).await;
+        let _ = process_command(command, &state, &mut client, true).await;
     }
 
     info!("DB restored!");
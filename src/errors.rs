@@ -2,28 +2,40 @@ use thiserror::Error;
 
 #[derive(Debug, Clone, Error)]
 pub enum ZystError {
-    #[error("Invalid command")]
+    #[error("ERR unknown command")]
     InvalidCommand,
+    #[error("ERR unknown command '{0}', with args beginning with: {1}")]
+    UnknownCommand(String, String),
     #[error("(nil)")]
     Nil,
     #[error("(empty array)")]
     EmptyArray,
-    #[error("ERR WRONGTYPE Operation against a key holding the wrong kind of value")]
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
     WrongType,
     #[error("ERR value is not an integer")]
     NotInt,
-    #[error("value is not an integer or out of range")]
+    #[error("ERR value is not an integer or out of range")]
     NotIntOrOutOfRange,
     #[error("ERR unexpected database error")]
     DatabaseError,
-    #[error("ERR regex error")]
-    RegexError,
-    #[error("Error: TTL is required")]
+    #[error("ERR value is not an integer or out of range")]
     TTL,
     #[error("{0}")]
     Custom(String),
-    #[error("Wrong number of argument")]
+    #[error("ERR wrong number of arguments for command")]
     WrongNumberArgs,
+    #[error("ERR unknown subcommand '{0}'. Try {1} HELP.")]
+    UnknownSubcommand(String, String),
+    #[error("ERR wrong number of arguments for '{0}' command")]
+    WrongArity(String),
+    #[error("ERR invalid expire time in '{0}' command")]
+    InvalidExpireTime(String),
+    #[error("ERR syntax error")]
+    Syntax,
+    #[error("NOPROTO unsupported protocol version")]
+    NoProto,
+    #[error("WRONGPASS invalid username-password pair or user is disabled.")]
+    WrongPass,
 
     // RESP Parsing Errors
     #[error("ERR Protocol error: empty request")]
@@ -34,10 +46,10 @@ pub enum ZystError {
     InvalidArrayLength,
     #[error("ERR Protocol error: expected '$', got something else")]
     InvalidBulkStringPrefix,
+    #[error("ERR Protocol error: invalid bulk length")]
+    InvalidBulkLength,
+    #[error("ERR Protocol error: too big inline request")]
+    InlineTooBig,
     #[error("ERR Protocol error: wrong number of elements")]
     WrongElementCount,
 }
-
-pub fn format_redis_error(error: ZystError) -> String {
-    format!("-{error}\r\n")
-}
@@ -0,0 +1,145 @@
+/// Patterns with more `*` than this never match, which bounds recursion
+const MAX_NESTING: usize = 1000;
+
+/// Whether `string` matches the glob `pattern`, ignoring ASCII case when
+/// `nocase` is set. This follows Redis' `stringmatchlen`:
+///
+/// - `*` matches any sequence of bytes, `?` matches a single byte
+/// - `[abc]` matches one of the bytes, `[a-z]` a range, in any order
+/// - `[^abc]` or `[!abc]` matches a byte that isn't in the class
+/// - `\` escapes the next byte, outside or inside a class
+///
+/// Patterns are never rejected: an unterminated class ends with the pattern
+/// and a trailing `\` matches itself.
+pub fn string_match(pattern: &[u8], string: &[u8], nocase: bool) -> bool {
+    let mut skip_longer_matches = false;
+    match_from(pattern, string, nocase, &mut skip_longer_matches, 0)
+}
+
+fn match_from(
+    pattern: &[u8],
+    string: &[u8],
+    nocase: bool,
+    skip_longer_matches: &mut bool,
+    nesting: usize,
+) -> bool {
+    if nesting > MAX_NESTING {
+        return false;
+    }
+
+    let same = |a: u8, b: u8| {
+        if nocase {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+    let (mut p, mut s) = (0, 0);
+
+    while p < pattern.len() && s < string.len() {
+        match pattern[p] {
+            b'*' => {
+                while pattern.get(p + 1) == Some(&b'*') {
+                    p += 1;
+                }
+                if p + 1 == pattern.len() {
+                    return true;
+                }
+
+                for start in s..string.len() {
+                    let rest = &pattern[p + 1..];
+                    if match_from(
+                        rest,
+                        &string[start..],
+                        nocase,
+                        skip_longer_matches,
+                        nesting + 1,
+                    ) {
+                        return true;
+                    }
+                    if *skip_longer_matches {
+                        return false;
+                    }
+                }
+
+                // The rest of the pattern matches nowhere in the rest of the
+                // string. Letting an earlier `*` match more bytes would only
+                // make the rest of the pattern start later, so every caller
+                // can give up right away. This avoids exponential
+                // backtracking on patterns like `*a*a*a*a*b`.
+                *skip_longer_matches = true;
+                return false;
+            }
+            b'?' => s += 1,
+            b'[' => {
+                let c = string[s];
+                p += 1;
+                let negate = matches!(pattern.get(p), Some(b'^' | b'!'));
+                if negate {
+                    p += 1;
+                }
+
+                let mut matched = false;
+                loop {
+                    match pattern.get(p) {
+                        Some(b'\\') if p + 1 < pattern.len() => {
+                            p += 1;
+                            matched |= pattern[p] == c;
+                        }
+                        Some(b']') => break,
+                        // An unterminated class ends with the pattern
+                        None => {
+                            p -= 1;
+                            break;
+                        }
+                        Some(&start) if p + 2 < pattern.len() && pattern[p + 1] == b'-' => {
+                            let (mut low, mut high, mut c) = (start, pattern[p + 2], c);
+                            if low > high {
+                                std::mem::swap(&mut low, &mut high);
+                            }
+                            if nocase {
+                                low = low.to_ascii_lowercase();
+                                high = high.to_ascii_lowercase();
+                                c = c.to_ascii_lowercase();
+                            }
+                            p += 2;
+                            matched |= (low..=high).contains(&c);
+                        }
+                        Some(&byte) => matched |= same(byte, c),
+                    }
+                    p += 1;
+                }
+
+                if matched == negate {
+                    return false;
+                }
+                s += 1;
+            }
+            byte => {
+                let byte = if byte == b'\\' && p + 1 < pattern.len() {
+                    p += 1;
+                    pattern[p]
+                } else {
+                    byte
+                };
+
+                if !same(byte, string[s]) {
+                    return false;
+                }
+                s += 1;
+            }
+        }
+
+        p += 1;
+    }
+
+    // Trailing `*` match the empty end of the string. Redis special cases a
+    // lone `*` in KEYS and SCAN instead, which has the same effect.
+    if s == string.len() {
+        while pattern.get(p) == Some(&b'*') {
+            p += 1;
+        }
+    }
+
+    p == pattern.len() && s == string.len()
+}
@@ -1,8 +1,54 @@
-use crate::types::KeyBase;
+use crate::types::{DbValue, ExpireCondition, HashData, KeyBase};
+use bytes::Bytes;
+use indexmap::{Equivalent, IndexMap};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Current Unix time in milliseconds, the unit expirations are stored in
+pub fn current_timestamp_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as i64
+}
+
+/// Random position in a collection of `len` elements, `len` must not be 0
+pub fn random_index(len: usize) -> usize {
+    // Every `RandomState` is seeded differently, which is random enough to
+    // pick keys or members
+    (RandomState::new().hash_one(current_timestamp_ms()) % len as u64) as usize
+}
+
+/// Random positions in a collection of `len` elements, `count` distinct ones
+/// at most, or `-count` ones that may repeat when negative
+pub fn random_positions(len: usize, count: i64) -> Vec<usize> {
+    if len == 0 {
+        return Vec::new();
+    }
+
+    if count < 0 {
+        return (0..count.unsigned_abs())
+            .map(|_| random_index(len))
+            .collect();
+    }
+
+    // Shuffles the first `count` positions, the ones swapped away are kept
+    // in a map rather than in a vector of all `len` positions
+    let count = usize::try_from(count).unwrap_or(usize::MAX).min(len);
+    let mut swapped: HashMap<usize, usize> = HashMap::with_capacity(count);
+    (0..count)
+        .map(|i| {
+            let j = i + random_index(len - i);
+            let picked = swapped.get(&j).copied().unwrap_or(j);
+            swapped.insert(j, swapped.get(&i).copied().unwrap_or(i));
+            picked
+        })
+        .collect()
+}
+
 impl<T> KeyBase<T> {
-    pub fn new(name: String, data: T, expires_at: Option<i64>) -> Self {
+    pub fn new(name: Bytes, data: T, expires_at: Option<i64>) -> Self {
         KeyBase {
             name,
             data,
@@ -10,27 +56,266 @@ impl<T> KeyBase<T> {
         }
     }
 
-    fn get_current_timestamp() -> i64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs() as i64
+    // A key without ttl returns -1 and is not expired
+    pub fn is_expired(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at <= current_timestamp_ms(),
+            None => false,
+        }
+    }
+}
+
+impl HashData {
+    /// Value of a field, an expired field reads as missing
+    pub fn get<Q>(&self, field: &Q) -> Option<&Bytes>
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        self.fields
+            .get(field)
+            .filter(|_| !self.is_field_expired(field, current_timestamp_ms()))
     }
 
-    pub fn get_ttl(&self) -> i64 {
-        self.expires_at
-            .map_or(-1, |expires_at| expires_at - Self::get_current_timestamp())
+    pub fn contains_key<Q>(&self, field: &Q) -> bool
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        self.get(field).is_some()
     }
 
-    pub fn set_ttl(&mut self, ttl: i64) {
-        self.expires_at = Some(Self::get_current_timestamp() + ttl);
+    /// Field and value at a position, an expired field reads as missing
+    pub fn get_index(&self, index: usize) -> Option<(&Bytes, &Bytes)> {
+        self.fields
+            .get_index(index)
+            .filter(|(field, _)| !self.is_field_expired(*field, current_timestamp_ms()))
     }
 
-    // A key without ttl returns -1 and is not expired
+    /// A random field that isn't expired, if there is one
+    pub fn random_field(&self) -> Option<(&Bytes, &Bytes)> {
+        // Expired fields are skipped, when most fields are expired the first
+        // live one is taken rather than trying forever
+        (0..100)
+            .take_while(|_| !self.fields.is_empty())
+            .find_map(|_| self.get_index(random_index(self.fields.len())))
+            .or_else(|| self.iter().next())
+    }
+
+    /// Random fields that aren't expired, `count` distinct ones at most, or
+    /// `-count` ones that may repeat when negative
+    pub fn random_fields(&self, count: i64) -> Vec<(&Bytes, &Bytes)> {
+        if count < 0 {
+            return (0..count.unsigned_abs())
+                .map_while(|_| self.random_field())
+                .collect();
+        }
+
+        // Positions of expired fields that weren't deleted yet are skipped,
+        // one more is drawn for each field with a TTL to make up for them
+        let extra = i64::try_from(self.expires.len()).unwrap_or(i64::MAX);
+        random_positions(self.fields.len(), count.saturating_add(extra))
+            .into_iter()
+            .filter_map(|position| self.get_index(position))
+            .take(usize::try_from(count).unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, &Bytes)> {
+        let now = current_timestamp_ms();
+        self.fields
+            .iter()
+            .filter(move |(field, _)| !self.is_field_expired(*field, now))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Bytes> {
+        self.iter().map(|(field, _)| field)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Bytes> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Number of fields. As in Redis, expired fields count until they are
+    /// deleted, and the positions `get_index` takes range over them.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Sets a field, which loses its TTL
+    pub fn insert(&mut self, field: Bytes, value: Bytes) -> Option<Bytes> {
+        self.expires.swap_remove(&field);
+        self.fields.insert(field, value)
+    }
+
+    /// Sets a field, which keeps its TTL if it has one
+    pub fn insert_keep_ttl(&mut self, field: Bytes, value: Bytes) -> Option<Bytes> {
+        self.fields.insert(field, value)
+    }
+
+    pub fn swap_remove(&mut self, field: &Bytes) -> Option<Bytes> {
+        self.expires.swap_remove(field);
+        self.fields.swap_remove(field)
+    }
+
+    /// Unix time in milliseconds a field expires at
+    pub fn expires_at<Q>(&self, field: &Q) -> Option<i64>
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        self.expires.get(field).copied()
+    }
+
+    /// Sets or removes the expiration of a field, returns false when the
+    /// field doesn't exist
+    pub fn set_expires_at(&mut self, field: &Bytes, expires_at: Option<i64>) -> bool {
+        if !self.fields.contains_key(field) {
+            return false;
+        }
+
+        match expires_at {
+            Some(expires_at) => {
+                self.expires.insert(field.clone(), expires_at);
+            }
+            None => {
+                self.expires.swap_remove(field);
+            }
+        }
+        true
+    }
+
+    /// Earliest expiry of the fields, None when no field has a TTL
+    pub fn next_expiry(&self) -> Option<i64> {
+        self.expires.values().min().copied()
+    }
+
+    /// Whether the hash has fields and all of them are expired
+    pub fn all_expired(&self) -> bool {
+        let now = current_timestamp_ms();
+        !self.fields.is_empty()
+            && self.expires.len() == self.fields.len()
+            && self.expires.values().all(|expires_at| *expires_at <= now)
+    }
+
+    /// Deletes the expired fields, returns how many there were
+    pub fn remove_expired(&mut self) -> usize {
+        let now = current_timestamp_ms();
+        let before = self.fields.len();
+        let fields = &mut self.fields;
+
+        self.expires.retain(|field, expires_at| {
+            let expired = *expires_at <= now;
+            if expired {
+                fields.swap_remove(field);
+            }
+            !expired
+        });
+
+        before - self.fields.len()
+    }
+
+    fn is_field_expired<Q>(&self, field: &Q, now: i64) -> bool
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        self.expires
+            .get(field)
+            .is_some_and(|expires_at| *expires_at <= now)
+    }
+}
+
+impl From<IndexMap<Bytes, Bytes>> for HashData {
+    fn from(fields: IndexMap<Bytes, Bytes>) -> Self {
+        HashData {
+            fields,
+            expires: IndexMap::new(),
+        }
+    }
+}
+
+impl DbValue {
+    /// Name of the type, as TYPE replies it
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DbValue::StringKey(_) => "string",
+            DbValue::ListKey(_) => "list",
+            DbValue::SetKey(_) => "set",
+            DbValue::HashKey(_) => "hash",
+        }
+    }
+
+    pub fn expires_at(&self) -> Option<i64> {
+        match self {
+            DbValue::StringKey(key) => key.expires_at,
+            DbValue::ListKey(key) => key.expires_at,
+            DbValue::SetKey(key) => key.expires_at,
+            DbValue::HashKey(key) => key.expires_at,
+        }
+    }
+
+    pub fn set_name(&mut self, name: Bytes) {
+        match self {
+            DbValue::StringKey(key) => key.name = name,
+            DbValue::ListKey(key) => key.name = name,
+            DbValue::SetKey(key) => key.name = name,
+            DbValue::HashKey(key) => key.name = name,
+        }
+    }
+
+    /// Rough cost of freeing the value, the number of allocations it holds
+    pub fn free_effort(&self) -> usize {
+        match self {
+            DbValue::StringKey(_) => 1,
+            DbValue::ListKey(key) => key.data.len(),
+            DbValue::SetKey(key) => key.data.len(),
+            DbValue::HashKey(key) => key.data.len(),
+        }
+    }
+
+    /// Earliest expiry of the key or, for a hash, of one of its fields
+    pub fn next_expiry(&self) -> Option<i64> {
+        match self {
+            DbValue::HashKey(key) => match (key.expires_at, key.data.next_expiry()) {
+                (Some(key), Some(field)) => Some(key.min(field)),
+                (key, field) => key.or(field),
+            },
+            _ => self.expires_at(),
+        }
+    }
+
+    pub fn set_expires_at(&mut self, expires_at: Option<i64>) {
+        match self {
+            DbValue::StringKey(key) => key.expires_at = expires_at,
+            DbValue::ListKey(key) => key.expires_at = expires_at,
+            DbValue::SetKey(key) => key.expires_at = expires_at,
+            DbValue::HashKey(key) => key.expires_at = expires_at,
+        }
+    }
+
+    /// A key that expired, or a hash whose fields all expired, reads as
+    /// missing
     pub fn is_expired(&self) -> bool {
-        match self.expires_at {
-            Some(expires_at) => expires_at <= Self::get_current_timestamp(),
-            None => false,
+        match self {
+            DbValue::HashKey(key) => key.is_expired() || key.data.all_expired(),
+            _ => self
+                .expires_at()
+                .is_some_and(|expires_at| expires_at <= current_timestamp_ms()),
+        }
+    }
+}
+
+impl ExpireCondition {
+    /// Whether a key expiring at `current` may be given the new expiry
+    pub fn holds(&self, current: Option<i64>, expires_at: i64) -> bool {
+        match (self, current) {
+            (ExpireCondition::NX, current) => current.is_none(),
+            (ExpireCondition::XX, current) => current.is_some(),
+            (ExpireCondition::GT, Some(current)) => expires_at > current,
+            (ExpireCondition::GT, None) => false,
+            (ExpireCondition::LT, Some(current)) => expires_at < current,
+            (ExpireCondition::LT, None) => true,
         }
     }
 }
@@ -0,0 +1,394 @@
+use crate::client::lock;
+use crate::keys::{current_timestamp_ms, random_index};
+use crate::notify::{KeyspaceEvent, NotifyFlags};
+use crate::types::DbValue;
+use bytes::Bytes;
+use indexmap::{Equivalent, IndexMap, IndexSet};
+use std::hash::Hash;
+use std::mem;
+use std::sync::Mutex;
+
+/// The keys of a database, along with an index of the keys carrying a TTL,
+/// or hashes with fields carrying one, that the active expire cycle samples
+/// from.
+///
+/// Every command looks keys up through this type, which hides expired keys:
+/// `get_mut` deletes them, while `get` and the iterators, which only
+/// borrow the keyspace, skip them and leave them to the next write or to
+/// the active expire cycle. Expired hash fields go the same way, `get_mut`
+/// deletes them along with the hash once none is left.
+///
+/// Writes keep the index in sync so it never misses a key with a TTL. The
+/// index may still hold keys that lost their TTL, they are dropped when
+/// sampled.
+///
+/// Keys watched by WATCH carry a version, bumped whenever the key is
+/// created, modified, expired or deleted. The methods writing keys bump it
+/// themselves, commands changing a value through `get_mut` call
+/// `signal_modified` once they did change it.
+///
+/// Keyspace events are collected as they happen, the dispatcher publishes
+/// them once the command is done. Creating a key and deleting an expired
+/// one are recorded here, commands add their own events with `notify`.
+#[derive(Debug, Default)]
+pub struct Keyspace {
+    entries: IndexMap<Bytes, DbValue>,
+    expires: IndexSet<Bytes>,
+    /// Where the next expire cycle resumes in `expires`
+    expires_cursor: usize,
+    watched: IndexMap<Bytes, WatchedKey>,
+    /// Behind a lock so that reads, which only borrow the keyspace, can
+    /// record key misses
+    events: Mutex<Vec<KeyspaceEvent>>,
+}
+
+#[derive(Debug, Default)]
+struct WatchedKey {
+    version: u64,
+    /// Number of clients watching the key, it's forgotten at 0
+    watchers: usize,
+}
+
+impl Keyspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: Bytes, value: DbValue) -> Option<DbValue> {
+        self.signal_modified(&key);
+
+        if value.next_expiry().is_some() {
+            self.expires.insert(key.clone());
+        }
+
+        let previous = self.entries.insert(key.clone(), value);
+        match &previous {
+            Some(previous) if !previous.is_expired() => {}
+            Some(_) => {
+                self.notify(NotifyFlags::EXPIRED, "expired", key.clone());
+                self.notify(NotifyFlags::NEW, "new", key);
+            }
+            None => self.notify(NotifyFlags::NEW, "new", key),
+        }
+
+        previous
+    }
+
+    /// Looks a key up, an expired key reads as missing
+    pub fn get<Q>(&self, key: &Q) -> Option<&DbValue>
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        self.entries.get(key).filter(|value| !value.is_expired())
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Looks a key up to modify it, an expired key is deleted first, as are
+    /// the expired fields of a hash. Expirations must be changed with
+    /// `set_expires_at` and `set_field_expires_at`, not through the returned
+    /// reference, and changes must be followed by `signal_modified`.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut DbValue>
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        if self.remove_expired(key) {
+            return None;
+        }
+
+        self.entries.get_mut(key)
+    }
+
+    /// Key and value at a position of the keyspace, an expired key reads
+    /// as missing
+    pub fn get_index(&self, index: usize) -> Option<(&Bytes, &DbValue)> {
+        self.entries
+            .get_index(index)
+            .filter(|(_, value)| !value.is_expired())
+    }
+
+    /// A random key that isn't expired, if there is one
+    pub fn random_key(&self) -> Option<&Bytes> {
+        // Expired keys are skipped, when most keys are expired the first
+        // live one is taken rather than trying forever
+        (0..100)
+            .take_while(|_| !self.entries.is_empty())
+            .find_map(|_| self.get_index(random_index(self.entries.len())))
+            .or_else(|| self.iter().next())
+            .map(|(key, _)| key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, &DbValue)> {
+        self.entries.iter().filter(|(_, value)| !value.is_expired())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Bytes> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Number of keys, including expired keys that weren't deleted yet
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sets or removes the expiration of a key, returns false when the key
+    /// doesn't exist
+    pub fn set_expires_at(&mut self, key: &Bytes, expires_at: Option<i64>) -> bool {
+        let Some(value) = self.get_mut(key) else {
+            return false;
+        };
+
+        value.set_expires_at(expires_at);
+        if expires_at.is_some() {
+            self.expires.insert(key.clone());
+        }
+        self.signal_modified(key);
+
+        true
+    }
+
+    /// Sets or removes the expiration of a field of a hash, returns false
+    /// when the key isn't a hash or doesn't have the field
+    pub fn set_field_expires_at(
+        &mut self,
+        key: &Bytes,
+        field: &Bytes,
+        expires_at: Option<i64>,
+    ) -> bool {
+        let Some(DbValue::HashKey(hash)) = self.get_mut(key) else {
+            return false;
+        };
+
+        if !hash.data.set_expires_at(field, expires_at) {
+            return false;
+        }
+        if expires_at.is_some() {
+            self.expires.insert(key.clone());
+        }
+        self.signal_modified(key);
+
+        true
+    }
+
+    /// Removes a key, returns its value unless it was expired. A hash loses
+    /// its expired fields first, and counts as expired when none is left.
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<DbValue>
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        if self.remove_expired_fields(key) {
+            return None;
+        }
+
+        self.expires.swap_remove(key);
+        let (key, value) = self.entries.swap_remove_entry(key)?;
+        self.signal_modified(&key);
+
+        if value.is_expired() {
+            self.notify(NotifyFlags::EXPIRED, "expired", key);
+            return None;
+        }
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        drop(self.take());
+    }
+
+    /// Moves every key out, for FLUSHDB to free them elsewhere. Watches stay
+    /// with the keyspace, watched keys that existed count as modified.
+    pub fn take(&mut self) -> Keyspace {
+        let taken = Keyspace {
+            entries: mem::take(&mut self.entries),
+            expires: mem::take(&mut self.expires),
+            ..Keyspace::default()
+        };
+        self.expires_cursor = 0;
+        touch_existing(&mut self.watched, &taken.entries);
+
+        taken
+    }
+
+    /// Exchanges the keys of two keyspaces, for SWAPDB. Watches stay with
+    /// their keyspace, watched keys that exist in either count as modified.
+    pub fn swap(&mut self, other: &mut Keyspace) {
+        mem::swap(&mut self.entries, &mut other.entries);
+        mem::swap(&mut self.expires, &mut other.expires);
+        mem::swap(&mut self.expires_cursor, &mut other.expires_cursor);
+
+        for watched in [&mut self.watched, &mut other.watched] {
+            touch_existing(watched, &self.entries);
+            touch_existing(watched, &other.entries);
+        }
+    }
+
+    /// Starts watching a key, returns its version. An expired key is
+    /// deleted first, so that it isn't seen expiring later.
+    pub fn watch(&mut self, key: &Bytes) -> u64 {
+        self.remove_expired(key);
+
+        let watched = self.watched.entry(key.clone()).or_default();
+        watched.watchers += 1;
+        watched.version
+    }
+
+    pub fn unwatch(&mut self, key: &Bytes) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.watchers -= 1;
+            if watched.watchers == 0 {
+                self.watched.swap_remove(key);
+            }
+        }
+    }
+
+    /// Current version of a watched key. An expired key is deleted first,
+    /// which bumps the version.
+    pub fn version(&mut self, key: &Bytes) -> Option<u64> {
+        self.remove_expired(key);
+        self.watched.get(key).map(|watched| watched.version)
+    }
+
+    // Deletes a key if it's expired, or the expired fields of a hash, returns
+    // whether the key was deleted
+    fn remove_expired<Q>(&mut self, key: &Q) -> bool
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        if self.remove_expired_fields(key) {
+            return true;
+        }
+
+        let expired = self.entries.get(key).is_some_and(DbValue::is_expired);
+        if expired {
+            self.swap_remove(key);
+        }
+        expired
+    }
+
+    // Deletes the expired fields of a hash that isn't expired itself, and the
+    // hash once none is left. Returns whether the hash was deleted.
+    fn remove_expired_fields<Q>(&mut self, key: &Q) -> bool
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        let Some((_, name, DbValue::HashKey(hash))) = self.entries.get_full_mut(key) else {
+            return false;
+        };
+        if hash.is_expired() || hash.data.remove_expired() == 0 {
+            return false;
+        }
+
+        let name = name.clone();
+        let emptied = hash.data.is_empty();
+
+        self.signal_modified(&name);
+        self.notify(NotifyFlags::HASH, "hexpired", name.clone());
+        if emptied {
+            self.expires.swap_remove(&name);
+            self.entries.swap_remove(&name);
+            self.notify(NotifyFlags::GENERIC, "del", name);
+        }
+        emptied
+    }
+
+    /// Bumps the version of a watched key, so that the transactions
+    /// watching it abort
+    pub fn signal_modified<Q>(&mut self, key: &Q)
+    where
+        Q: ?Sized + Hash + Equivalent<Bytes>,
+    {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.version += 1;
+        }
+    }
+
+    /// Records a keyspace event, published after the current command
+    pub fn notify(&self, class: NotifyFlags, event: &'static str, key: Bytes) {
+        lock(&self.events).push(KeyspaceEvent { class, event, key });
+    }
+
+    /// Takes the events recorded since the last call, oldest first
+    pub fn take_events(&self) -> Vec<KeyspaceEvent> {
+        mem::take(&mut *lock(&self.events))
+    }
+
+    /// Number of keys that may carry a TTL, or hold fields that do
+    pub fn expires_len(&self) -> usize {
+        self.expires.len()
+    }
+
+    /// Looks at up to `count` keys of the expires index, resuming where the
+    /// previous call stopped, and deletes the expired ones, or the expired
+    /// fields of hashes. Returns how many keys were sampled and how many of
+    /// them had expired.
+    pub fn expire_sample(&mut self, count: usize) -> (usize, usize) {
+        let now = current_timestamp_ms();
+        let count = count.min(self.expires.len());
+        let mut expired = 0;
+
+        for _ in 0..count {
+            if self.expires_cursor >= self.expires.len() {
+                self.expires_cursor = 0;
+            }
+
+            let Some(key) = self.expires.get_index(self.expires_cursor) else {
+                break;
+            };
+
+            let key_expired = self
+                .entries
+                .get(key)
+                .and_then(DbValue::expires_at)
+                .is_some_and(|expires_at| expires_at <= now);
+
+            match self.entries.get(key).and_then(DbValue::next_expiry) {
+                Some(expires_at) if expires_at > now => self.expires_cursor += 1,
+                // Fields expired, the hash stays in the index while it has
+                // some left
+                Some(_) if !key_expired => {
+                    let key = key.clone();
+                    if !self.remove_expired_fields(&key) {
+                        self.expires_cursor += 1;
+                    }
+                    expired += 1;
+                }
+                // Removing swaps the last key in at the cursor, which is
+                // sampled next
+                Some(_) => {
+                    if let Some(key) = self.expires.swap_remove_index(self.expires_cursor) {
+                        self.entries.swap_remove(&key);
+                        self.signal_modified(&key);
+                        self.notify(NotifyFlags::EXPIRED, "expired", key);
+                    }
+                    expired += 1;
+                }
+                None => {
+                    self.expires.swap_remove_index(self.expires_cursor);
+                }
+            }
+        }
+
+        (count, expired)
+    }
+}
+
+// Bumps the version of the watched keys that exist in `entries`
+fn touch_existing(
+    watched: &mut IndexMap<Bytes, WatchedKey>,
+    entries: &IndexMap<Bytes, DbValue>,
+) {
+    for (key, watched) in watched.iter_mut() {
+        if entries.contains_key(key) {
+            watched.version += 1;
+        }
+    }
+}
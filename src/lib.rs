@@ -7,14 +7,21 @@
 #![deny(dead_code)]
 
 pub mod aof;
+pub mod blocking;
+pub mod client;
 pub mod commands;
 pub mod config;
 pub mod database;
 pub mod errors;
+pub mod glob;
 pub mod keys;
+pub mod keyspace;
+pub mod notify;
 pub mod parser;
 pub mod process;
+pub mod pubsub;
 pub mod resp;
 pub mod response;
 pub mod server;
+pub mod state;
 pub mod types;
@@ -1,27 +1,34 @@
-use indexmap::IndexMap;
 use std::error::Error;
 use std::net::Ipv4Addr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
-use tokio::sync::RwLock;
 use tracing::{error, info};
 use zyst::aof::clean_up_db;
 use zyst::config::get_config;
-use zyst::database::delete_expired_keys;
+use zyst::database::active_expire_cycle;
 use zyst::database::restore_from_aof;
+use zyst::notify::NotifyFlags;
 use zyst::server::handle_client;
-use zyst::types::Db;
+use zyst::state::State;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     tracing_subscriber::fmt::init();
 
-    let db: Db = Arc::new(RwLock::new(IndexMap::new()));
-
     // Config
     let config = get_config();
     let port: u16 = config.get("port").expect("Port is missing");
     let bind: Ipv4Addr = config.get("bind").expect("Bind is missing");
+    let max_bulk_len: usize = config
+        .get("proto-max-bulk-len")
+        .expect("proto-max-bulk-len is missing");
+    let hz: u64 = config.get("hz").expect("hz is missing");
+    let databases: usize = config.get("databases").expect("databases is missing");
+    let keyspace_events: String = config
+        .get("notify-keyspace-events")
+        .expect("notify-keyspace-events is missing");
+    let keyspace_events =
+        NotifyFlags::parse(keyspace_events.as_bytes()).expect("Invalid notify-keyspace-events");
     let full_address = format!("{bind}:{port}");
 
     let listener = TcpListener::bind(full_address.to_string()).await?;
@@ -29,22 +36,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     info!(message);
 
+    let state = Arc::new(State::new(databases));
+    state.set_keyspace_events(keyspace_events);
+
     // Restoring DB from AOF file at start up
-    tokio::spawn(restore_from_aof(db.clone()));
+    tokio::spawn(restore_from_aof(state.clone()));
 
-    // Delete expired keys every 60 seconds
-    tokio::spawn(delete_expired_keys(db.clone()));
+    // Delete expired keys in the background, `hz` times per second
+    tokio::spawn(active_expire_cycle(state.clone(), hz));
 
     // Clean database every 60 seconds
-    tokio::spawn(clean_up_db(db.clone()));
+    tokio::spawn(clean_up_db(state.clone()));
 
     loop {
         let (socket, addr) = listener.accept().await?;
 
-        let db = Arc::clone(&db);
+        let state = Arc::clone(&state);
 
         tokio::spawn(async move {
-            if let Err(e) = handle_client(socket, db).await {
+            if let Err(e) = handle_client(socket, state, max_bulk_len).await {
                 error!("Error handling client {}: {:?}", addr, e);
             }
         });
@@ -0,0 +1,169 @@
+use crate::keyspace::Keyspace;
+use crate::state::State;
+use bytes::Bytes;
+use std::ops::BitOr;
+
+/// Classes of keyspace events set by `notify-keyspace-events`, one flag
+/// character each
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NotifyFlags(u32);
+
+impl NotifyFlags {
+    /// `K`, publish on `__keyspace@<db>__:<key>`
+    pub const KEYSPACE: Self = Self(1);
+    /// `E`, publish on `__keyevent@<db>__:<event>`
+    pub const KEYEVENT: Self = Self(1 << 1);
+    /// `g`, commands not specific to a type such as DEL, EXPIRE or RENAME
+    pub const GENERIC: Self = Self(1 << 2);
+    /// `$`
+    pub const STRING: Self = Self(1 << 3);
+    /// `l`
+    pub const LIST: Self = Self(1 << 4);
+    /// `s`
+    pub const SET: Self = Self(1 << 5);
+    /// `h`
+    pub const HASH: Self = Self(1 << 6);
+    /// `z`
+    pub const ZSET: Self = Self(1 << 7);
+    /// `x`, keys deleted because their TTL passed
+    pub const EXPIRED: Self = Self(1 << 8);
+    /// `e`, keys evicted for maxmemory
+    pub const EVICTED: Self = Self(1 << 9);
+    /// `t`
+    pub const STREAM: Self = Self(1 << 10);
+    /// `m`, reads of a missing key
+    pub const KEY_MISS: Self = Self(1 << 11);
+    /// `n`, keys created
+    pub const NEW: Self = Self(1 << 12);
+    /// `A`, alias for `g$lshzxet`
+    pub const ALL: Self = Self(
+        Self::GENERIC.0
+            | Self::STRING.0
+            | Self::LIST.0
+            | Self::SET.0
+            | Self::HASH.0
+            | Self::ZSET.0
+            | Self::EXPIRED.0
+            | Self::EVICTED.0
+            | Self::STREAM.0,
+    );
+
+    // Flag characters of the classes in the order Redis lists them
+    const CLASSES: [(Self, char); 10] = [
+        (Self::GENERIC, 'g'),
+        (Self::STRING, '$'),
+        (Self::LIST, 'l'),
+        (Self::SET, 's'),
+        (Self::HASH, 'h'),
+        (Self::ZSET, 'z'),
+        (Self::EXPIRED, 'x'),
+        (Self::EVICTED, 'e'),
+        (Self::STREAM, 't'),
+        (Self::NEW, 'n'),
+    ];
+
+    /// Parses flag characters, None when one isn't a known class
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        value.iter().try_fold(Self::default(), |flags, c| {
+            let flag = match c {
+                b'A' => Self::ALL,
+                b'K' => Self::KEYSPACE,
+                b'E' => Self::KEYEVENT,
+                b'm' => Self::KEY_MISS,
+                _ => Self::CLASSES
+                    .iter()
+                    .find(|(_, class)| *class as u8 == *c)
+                    .map(|(flag, _)| *flag)?,
+            };
+            Some(flags | flag)
+        })
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for NotifyFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Formats the flags the way CONFIG GET shows them, `A` standing for all
+/// the classes it covers
+impl std::fmt::Display for NotifyFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let all = self.contains(Self::ALL);
+        if all {
+            write!(f, "A")?;
+        }
+        for (flag, class) in Self::CLASSES {
+            let covered = all && Self::ALL.contains(flag);
+            if self.contains(flag) && !covered {
+                write!(f, "{class}")?;
+            }
+        }
+        if self.contains(Self::KEYSPACE) {
+            write!(f, "K")?;
+        }
+        if self.contains(Self::KEYEVENT) {
+            write!(f, "E")?;
+        }
+        if self.contains(Self::KEY_MISS) {
+            write!(f, "m")?;
+        }
+        Ok(())
+    }
+}
+
+/// Something that happened to a key, waiting in its keyspace to be
+/// published
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyspaceEvent {
+    pub class: NotifyFlags,
+    pub event: &'static str,
+    pub key: Bytes,
+}
+
+/// Publishes the events a keyspace collected, when their class is enabled.
+/// Events are always taken out, so they don't pile up while disabled.
+pub fn publish_events(state: &State, db: usize, keyspace: &Keyspace) {
+    let events = keyspace.take_events();
+    let flags = state.keyspace_events();
+    if events.is_empty() || !flags.intersects(NotifyFlags::KEYSPACE | NotifyFlags::KEYEVENT) {
+        return;
+    }
+
+    for event in events
+        .into_iter()
+        .filter(|event| flags.intersects(event.class))
+    {
+        if flags.contains(NotifyFlags::KEYSPACE) {
+            let mut channel = format!("__keyspace@{db}__:").into_bytes();
+            channel.extend_from_slice(&event.key);
+            state.pubsub().publish(
+                &Bytes::from(channel),
+                &Bytes::from_static(event.event.as_bytes()),
+            );
+        }
+        if flags.contains(NotifyFlags::KEYEVENT) {
+            let channel = format!("__keyevent@{db}__:{}", event.event);
+            state.pubsub().publish(&Bytes::from(channel), &event.key);
+        }
+    }
+}
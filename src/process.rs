@@ -1,49 +1,159 @@
-use crate::parser::parse_command;
-use crate::types::CommandType;
-use crate::types::Db;
-
-use crate::commands::db::*;
-use crate::commands::hashsets::*;
-use crate::commands::keys::*;
-use crate::commands::lists::*;
-use crate::commands::misc::*;
-use crate::commands::sets::*;
+use crate::aof::write_aof;
+use crate::blocking::wait_unblocked;
+use crate::client::Client;
+use crate::commands::lists::serve_blocked_clients;
+use crate::commands::registry::{CommandSpec, Context};
 use crate::errors::ZystError;
-use crate::response::ZystResponse;
+use crate::notify::publish_events;
+use crate::parser::{build_command, check_command};
+use crate::response::{Protocol, ZystResponse};
+use crate::state::State;
+use bytes::Bytes;
+use std::time::Instant;
+
+/// Commands run right away between MULTI and EXEC instead of being queued
+const TRANSACTION_CONTROL: &[&str] = &["multi", "exec", "discard", "watch"];
+
+/// Commands a RESP2 connection can still send once subscribed, its replies
+/// would be mixed up with the messages otherwise
+const SUBSCRIBED_COMMANDS: &[&str] = &[
+    "subscribe",
+    "unsubscribe",
+    "psubscribe",
+    "punsubscribe",
+    "ping",
+];
 
 pub async fn process_command(
-    command: Vec<String>,
-    db: &Db,
+    command: Vec<Bytes>,
+    state: &State,
+    conn: &mut Client,
     restore: bool,
 ) -> Result<ZystResponse, ZystError> {
-    let command = parse_command(command, restore).await?;
-
-    match command.command_type {
-        CommandType::DOCS => docs().await,
-        CommandType::PONG => pong().await,
-        CommandType::GET => get_key(db, command).await,
-        CommandType::SET => set_key(db, command).await,
-        CommandType::DEL => delete_key(db, command).await,
-        CommandType::FLUSHDB => flush_db(db).await,
-        CommandType::KEYS => get_keys(db, command).await,
-        CommandType::EXISTS => exists(db, command).await,
-        CommandType::EXPIRE => expire(db, command).await,
-        CommandType::TTL => ttl(db, command).await,
-        CommandType::INCR => incr(db, command).await,
-        CommandType::DECR => decr(db, command).await,
-        CommandType::INCRBY => incrby(db, command).await,
-        CommandType::LPUSH => lpush(db, command).await,
-        CommandType::LRANGE => lrange(db, command).await,
-        CommandType::RPUSH => rpush(db, command).await,
-        CommandType::LPOP => lpop(db, command).await,
-        CommandType::RPOP => rpop(db, command).await,
-        CommandType::HSET => hset(db, command).await,
-        CommandType::HGET => hget(db, command).await,
-        CommandType::HGETALL => hgetall(db, command).await,
-        CommandType::HDEL => hdel(db, command).await,
-        CommandType::CLIENT => client().await,
-        CommandType::SADD => sadd(db, command).await,
-        CommandType::SMEMBERS => smembers(db, command).await,
-        CommandType::SREM => srem(db, command).await,
+    let spec = match check_command(&command) {
+        Ok(spec) => spec,
+        Err(e) => {
+            // EXEC refuses a transaction with a command that couldn't be
+            // queued
+            if let Some(transaction) = &mut conn.transaction {
+                transaction.aborted = true;
+            }
+            return Err(e);
+        }
+    };
+
+    if conn.subscriptions() > 0
+        && conn.protocol == Protocol::Resp2
+        && !SUBSCRIBED_COMMANDS.contains(&spec.name)
+    {
+        return Err(ZystError::Custom(format!(
+            "ERR Can't execute '{}': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING are allowed \
+             in this context",
+            spec.name
+        )));
+    }
+
+    if let Some(transaction) = &mut conn.transaction {
+        if !TRANSACTION_CONTROL.contains(&spec.name) {
+            transaction.commands.push(command);
+            conn.publish();
+            return Ok(ZystResponse::SimpleString("QUEUED".to_string()));
+        }
+    }
+
+    // Commands wait for the end of a CLIENT PAUSE, the AOF is replayed
+    // before any client can pause
+    if !restore {
+        state.client_pause().wait(writes(spec, conn)).await;
     }
+
+    let response = {
+        // EXEC runs alone, and the gate is kept until the commands are logged
+        // so that they reach the AOF in the order they ran in
+        let _shared;
+        let _exclusive;
+        if spec.name == "exec" {
+            _exclusive = state.command_gate().write().await;
+        } else {
+            _shared = state.command_gate().read().await;
+        }
+
+        let mut propagated = Vec::new();
+        let response = execute(spec, command, state, conn, &mut propagated).await;
+        serve_blocked_clients(state, &mut propagated).await;
+
+        if !restore && !propagated.is_empty() {
+            write_aof(&propagated)
+                .await
+                .expect("Error writing to AOF file!");
+        }
+
+        response
+    };
+
+    // A blocked client waits outside of the gate, other commands and EXEC
+    // in particular have to run meanwhile
+    if conn.blocked.is_some() {
+        return wait_unblocked(state, conn).await;
+    }
+
+    response
+}
+
+// Whether a command held back by CLIENT PAUSE WRITE, EXEC is when one of
+// the queued commands is
+fn writes(spec: &CommandSpec, conn: &Client) -> bool {
+    match &conn.transaction {
+        Some(transaction) if spec.name == "exec" => transaction
+            .commands
+            .iter()
+            .any(|command| check_command(command).is_ok_and(CommandSpec::is_write)),
+        _ => spec.is_write(),
+    }
+}
+
+/// Runs a command that passed the checks of `check_command`. The commands
+/// to append to the AOF, with the database each applies to, are added to
+/// `propagated`.
+pub async fn execute(
+    spec: &'static CommandSpec,
+    command: Vec<Bytes>,
+    state: &State,
+    conn: &mut Client,
+    propagated: &mut Vec<(usize, Vec<Bytes>)>,
+) -> Result<ZystResponse, ZystError> {
+    let parsed = build_command(spec, &command)?;
+
+    conn.last_command = spec.name;
+    conn.last_interaction = Instant::now();
+
+    // Commands are logged against the database selected when they start
+    let db_index = conn.db;
+    let db = state.db(db_index).ok_or(ZystError::DatabaseError)?;
+
+    let mut ctx = Context {
+        state,
+        db,
+        client: conn,
+        propagated,
+    };
+    let result = (spec.handler)(&mut ctx, parsed).await;
+    conn.publish();
+    publish_events(state, db_index, &*db.read().await);
+    let response = result?;
+
+    // Only write commands that succeeded are logged, a propagation hook
+    // returns nothing when the command didn't change the dataset
+    if spec.is_write() {
+        let argv = match spec.propagate {
+            Some(propagate) => propagate(&command, &response),
+            None => command,
+        };
+
+        if !argv.is_empty() {
+            propagated.push((db_index, argv));
+        }
+    }
+
+    Ok(response)
 }
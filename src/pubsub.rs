@@ -0,0 +1,115 @@
+use crate::client::{lock, ClientHandle};
+use crate::glob::string_match;
+use crate::response::ZystResponse;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use std::sync::{Arc, Mutex};
+
+type Subscribers = IndexMap<u64, Arc<ClientHandle>>;
+
+/// Subscribers of every channel and pattern, by client id. A client keeps
+/// its own subscriptions too, to reply to UNSUBSCRIBE without arguments
+/// and clean up when it disconnects.
+#[derive(Debug, Default)]
+pub struct PubSub {
+    channels: Mutex<IndexMap<Bytes, Subscribers>>,
+    patterns: Mutex<IndexMap<Bytes, Subscribers>>,
+}
+
+impl PubSub {
+    pub fn subscribe(&self, channel: Bytes, handle: &Arc<ClientHandle>) {
+        add(&self.channels, channel, handle);
+    }
+
+    pub fn unsubscribe(&self, channel: &Bytes, id: u64) {
+        remove(&self.channels, channel, id);
+    }
+
+    pub fn psubscribe(&self, pattern: Bytes, handle: &Arc<ClientHandle>) {
+        add(&self.patterns, pattern, handle);
+    }
+
+    pub fn punsubscribe(&self, pattern: &Bytes, id: u64) {
+        remove(&self.patterns, pattern, id);
+    }
+
+    /// Queues a message for the subscribers of the channel and of the
+    /// patterns matching it, returns how many messages were queued. Slow
+    /// subscribers are disconnected rather than waited for.
+    pub fn publish(&self, channel: &Bytes, message: &Bytes) -> usize {
+        let bulk = |value: &Bytes| ZystResponse::BulkString(value.clone());
+        let mut receivers = 0;
+
+        if let Some(subscribers) = lock(&self.channels).get(channel) {
+            for handle in subscribers.values() {
+                handle.push(ZystResponse::Push(vec![
+                    bulk(&Bytes::from_static(b"message")),
+                    bulk(channel),
+                    bulk(message),
+                ]));
+                receivers += 1;
+            }
+        }
+
+        for (pattern, subscribers) in lock(&self.patterns).iter() {
+            if !string_match(pattern, channel, false) {
+                continue;
+            }
+            for handle in subscribers.values() {
+                handle.push(ZystResponse::Push(vec![
+                    bulk(&Bytes::from_static(b"pmessage")),
+                    bulk(pattern),
+                    bulk(channel),
+                    bulk(message),
+                ]));
+                receivers += 1;
+            }
+        }
+
+        receivers
+    }
+
+    /// Channels with at least one subscriber, optionally only those
+    /// matching a pattern
+    pub fn channels(&self, pattern: Option<&[u8]>) -> Vec<Bytes> {
+        lock(&self.channels)
+            .keys()
+            .filter(|channel| {
+                pattern.is_none_or(|pattern| string_match(pattern, channel, false))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn numsub(&self, channel: &Bytes) -> usize {
+        lock(&self.channels).get(channel).map_or(0, IndexMap::len)
+    }
+
+    /// Number of patterns subscribed, by any client
+    pub fn numpat(&self) -> usize {
+        lock(&self.patterns).len()
+    }
+}
+
+fn add(
+    subscriptions: &Mutex<IndexMap<Bytes, Subscribers>>,
+    name: Bytes,
+    handle: &Arc<ClientHandle>,
+) {
+    lock(subscriptions)
+        .entry(name)
+        .or_default()
+        .insert(handle.id, handle.clone());
+}
+
+// A channel or pattern without subscribers is forgotten, so that PUBSUB
+// CHANNELS and NUMPAT only count live ones
+fn remove(subscriptions: &Mutex<IndexMap<Bytes, Subscribers>>, name: &Bytes, id: u64) {
+    let mut subscriptions = lock(subscriptions);
+    if let Some(subscribers) = subscriptions.get_mut(name) {
+        subscribers.shift_remove(&id);
+        if subscribers.is_empty() {
+            subscriptions.shift_remove(name);
+        }
+    }
+}
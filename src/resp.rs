@@ -1,28 +1,218 @@
 use crate::errors::ZystError;
+use bytes::{BufMut, Bytes, BytesMut};
 
-pub fn parse_resp_command(resp_command: &str) -> Result<Vec<Vec<String>>, ZystError> {
-    let lines = resp_command.split_terminator("\r\n");
-    let mut commands: Vec<Vec<String>> = Vec::new();
-    let mut cmd_nb: Option<usize> = None;
-
-    for line in lines {
-        // Check if the line starts with '*' and is followed immediately by a number
-        if let Some(rest) = line.strip_prefix('*') {
-            if !rest.is_empty() && rest.chars().all(|c| c.is_numeric()) {
-                commands.push(Vec::new());
-                cmd_nb = Some(commands.len() - 1);
-                continue;
+/// Same default as Redis `proto-max-bulk-len` (512MB)
+pub const DEFAULT_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+
+// Redis refuses more than 1024 * 1024 arguments in a single command
+const MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+
+// Maximum size of an inline command or of a `*N`/`$len` header line
+const MAX_INLINE_LEN: usize = 64 * 1024;
+
+// Arguments reserved up front for a multibulk frame, larger frames grow as
+// their arguments arrive rather than on the word of the `*N` header
+const MAX_PREALLOCATED_ARGS: usize = 1024;
+
+// Number of bytes used by a frame and the position of each argument in it
+type FrameBounds = (usize, Vec<(usize, usize)>);
+
+/// Incremental RESP2 request decoder.
+///
+/// Bytes read from the socket are appended to a per-connection buffer and
+/// `decode` is called until it returns `Ok(None)`, meaning the next frame is
+/// not complete yet. Arguments are sliced out of the buffer without copying
+/// and may contain any byte, CRLF included.
+///
+/// A decoder belongs to one connection: it remembers the arguments of an
+/// incomplete frame, so that they aren't parsed again as more bytes arrive.
+#[derive(Debug, Clone)]
+pub struct RespDecoder {
+    max_bulk_len: usize,
+    pending: Option<PendingFrame>,
+}
+
+// Multibulk frame whose arguments haven't all been received
+#[derive(Debug, Clone)]
+struct PendingFrame {
+    count: usize,
+    /// Where the next argument starts in the buffer
+    pos: usize,
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Default for RespDecoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BULK_LEN)
+    }
+}
+
+impl RespDecoder {
+    pub fn new(max_bulk_len: usize) -> Self {
+        RespDecoder {
+            max_bulk_len,
+            pending: None,
+        }
+    }
+
+    /// Decodes the first command of `buf` and removes it from the buffer.
+    ///
+    /// Returns `Ok(None)` and leaves the buffer untouched when more bytes are
+    /// needed. An empty `Vec` is returned for empty frames (`*0`, blank
+    /// inline lines) which must simply be skipped.
+    pub fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Vec<Bytes>>, ZystError> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        let parsed = if buf[0] == b'*' {
+            self.parse_multibulk(buf)?
+        } else {
+            parse_inline(buf)?
+        };
+
+        let Some((consumed, ranges)) = parsed else {
+            return Ok(None);
+        };
+
+        let frame = buf.split_to(consumed).freeze();
+        let args = ranges
+            .into_iter()
+            .map(|(start, end)| frame.slice(start..end))
+            .collect();
+
+        Ok(Some(args))
+    }
+
+    fn parse_multibulk(&mut self, buf: &[u8]) -> Result<Option<FrameBounds>, ZystError> {
+        let mut frame = match self.pending.take() {
+            Some(frame) => frame,
+            None => {
+                let Some((header, pos)) = read_line(buf, 1)? else {
+                    return Ok(None);
+                };
+
+                let count = parse_length(header).ok_or(ZystError::InvalidArrayLength)?;
+                if count > MAX_MULTIBULK_LEN as i64 {
+                    return Err(ZystError::InvalidArrayLength);
+                }
+
+                // `*0` and `*-1` carry no command
+                if count <= 0 {
+                    return Ok(Some((pos, Vec::new())));
+                }
+
+                let count = count as usize;
+                PendingFrame {
+                    count,
+                    pos,
+                    ranges: Vec::with_capacity(count.min(MAX_PREALLOCATED_ARGS)),
+                }
+            }
+        };
+
+        while frame.ranges.len() < frame.count {
+            match self.parse_bulk(buf, frame.pos)? {
+                Some((start, end)) => {
+                    frame.ranges.push((start, end));
+                    frame.pos = end + 2;
+                }
+                None => {
+                    self.pending = Some(frame);
+                    return Ok(None);
+                }
             }
         }
 
-        if line.starts_with('$') {
-            continue;
+        Ok(Some((frame.pos, frame.ranges)))
+    }
+
+    // Bounds of the bulk string starting at `pos`, None until it's complete
+    fn parse_bulk(&self, buf: &[u8], pos: usize) -> Result<Option<(usize, usize)>, ZystError> {
+        match buf.get(pos) {
+            None => return Ok(None),
+            Some(b'$') => {}
+            Some(_) => return Err(ZystError::InvalidBulkStringPrefix),
         }
 
-        if let Some(idx) = cmd_nb {
-            commands[idx].push(line.to_string());
+        let Some((header, start)) = read_line(buf, pos + 1)? else {
+            return Ok(None);
+        };
+
+        let len = match parse_length(header) {
+            Some(len) if len >= 0 && len as usize <= self.max_bulk_len => len as usize,
+            _ => return Err(ZystError::InvalidBulkLength),
+        };
+
+        let end = start + len;
+        if buf.len() < end + 2 {
+            return Ok(None);
         }
+
+        if &buf[end..end + 2] != b"\r\n" {
+            return Err(ZystError::InvalidBulkLength);
+        }
+
+        Ok(Some((start, end)))
     }
+}
+
+// Inline commands are what `nc` or `telnet` send: space separated arguments
+// terminated by a newline.
+fn parse_inline(buf: &[u8]) -> Result<Option<FrameBounds>, ZystError> {
+    let Some(newline) = buf.iter().position(|&b| b == b'\n') else {
+        if buf.len() > MAX_INLINE_LEN {
+            return Err(ZystError::InlineTooBig);
+        }
+        return Ok(None);
+    };
+
+    let mut ranges = Vec::new();
+    let mut start = None;
 
-    Ok(commands)
+    for (i, byte) in buf[..newline].iter().enumerate() {
+        match (byte.is_ascii_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                ranges.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        ranges.push((s, newline));
+    }
+
+    Ok(Some((newline + 1, ranges)))
+}
+
+// Reads a CRLF terminated line starting at `from`. Returns the line content
+// and the position right after the CRLF.
+fn read_line(buf: &[u8], from: usize) -> Result<Option<(&[u8], usize)>, ZystError> {
+    let rest = buf.get(from..).unwrap_or_default();
+
+    match rest.windows(2).position(|w| w == b"\r\n") {
+        Some(end) => Ok(Some((&rest[..end], from + end + 2))),
+        None if rest.len() > MAX_INLINE_LEN => Err(ZystError::InlineTooBig),
+        None => Ok(None),
+    }
+}
+
+fn parse_length(line: &[u8]) -> Option<i64> {
+    std::str::from_utf8(line).ok()?.parse::<i64>().ok()
+}
+
+/// Serializes a command as a RESP array of bulk strings, the format the
+/// decoder reads back
+pub fn encode_command<T: AsRef<[u8]>>(buf: &mut BytesMut, args: &[T]) {
+    buf.put_slice(format!("*{}\r\n", args.len()).as_bytes());
+
+    for arg in args {
+        let arg = arg.as_ref();
+        buf.put_slice(format!("${}\r\n", arg.len()).as_bytes());
+        buf.put_slice(arg);
+        buf.put_slice(b"\r\n");
+    }
 }
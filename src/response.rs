@@ -1,34 +1,197 @@
 use crate::errors::ZystError;
+use bytes::{BufMut, Bytes, BytesMut};
 use std::fmt;
+use std::fmt::Write;
+
+/// Protocol version negotiated by a connection with `HELLO`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
 
 #[derive(Debug, Clone)]
 pub enum ZystResponse {
-    Ok,                   // "OK"
-    Int(i64),             // "(integer) 123"
-    SimpleString(String), // "foo"
-    List(Vec<String>),    // "1) foo\n2) bar\n"
-    Nil,                  // "(nil)"
-    EmptyArray,           // "(empty array)"
-    Error(ZystError),     // Handles errors gracefully
+    Ok,                                     // "+OK"
+    Int(i64),                               // ":123"
+    SimpleString(String),                   // "+PONG", status replies only
+    BulkString(Bytes),                      // "$3\r\nfoo", any stored value
+    List(Vec<Bytes>),                       // "*2\r\n$3\r\nfoo\r\n$3\r\nbar"
+    Array(Vec<ZystResponse>),               // "*2\r\n:1\r\n$-1", mixed or nested replies
+    Map(Vec<(ZystResponse, ZystResponse)>), // "%1\r\n...", flat array in RESP2
+    Set(Vec<ZystResponse>),                 // "~2\r\n...", array in RESP2
+    Push(Vec<ZystResponse>),                // ">3\r\n...", out of band, array in RESP2
+    Double(f64),                            // ",1.5", bulk string in RESP2
+    Boolean(bool),                          // "#t", 1 or 0 in RESP2
+    BigNumber(String),                      // "(3492890328409238509324850943850943825024385"
+    Nil,                                    // "$-1", null bulk string, "_" in RESP3
+    NilArray,                               // "*-1", null array, "_" in RESP3
+    EmptyArray,                             // "*0"
+    Error(ZystError),                       // "-ERR ..."
+    Replies(Vec<ZystResponse>), // several replies in a row, one per SUBSCRIBE channel
 }
 
-impl fmt::Display for ZystResponse {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl ZystResponse {
+    /// Serializes the reply at the end of `buf`. RESP3 only types are
+    /// downgraded to their RESP2 equivalent when `protocol` is `Resp2`.
+    pub fn encode(&self, buf: &mut BytesMut, protocol: Protocol) {
+        let resp3 = protocol == Protocol::Resp3;
+
         match self {
-            ZystResponse::Ok => write!(f, "+OK\r\n"),
-            ZystResponse::Int(value) => write!(f, "+(integer) {value}\r\n"),
-            ZystResponse::SimpleString(value) => write!(f, "+{value}\r\n"),
+            ZystResponse::Ok => buf.put_slice(b"+OK\r\n"),
+            ZystResponse::Int(value) => encode_int(buf, b':', *value),
+            ZystResponse::SimpleString(value) => {
+                buf.put_u8(b'+');
+                put_line(buf, value);
+            }
+            ZystResponse::BulkString(value) => encode_bulk(buf, value),
             ZystResponse::List(values) => {
-                let mut response = format!("*{}\r\n", values.len());
-
+                encode_int(buf, b'*', values.len() as i64);
                 for value in values {
-                    response.push_str(&format!("${}\r\n{}\r\n", value.len(), value));
+                    encode_bulk(buf, value);
+                }
+            }
+            ZystResponse::Array(values) => encode_aggregate(buf, b'*', values, protocol),
+            ZystResponse::Map(entries) => {
+                if resp3 {
+                    encode_int(buf, b'%', entries.len() as i64);
+                } else {
+                    encode_int(buf, b'*', entries.len() as i64 * 2);
+                }
+                for (key, value) in entries {
+                    key.encode(buf, protocol);
+                    value.encode(buf, protocol);
+                }
+            }
+            ZystResponse::Set(values) => {
+                encode_aggregate(buf, if resp3 { b'~' } else { b'*' }, values, protocol)
+            }
+            ZystResponse::Push(values) => {
+                encode_aggregate(buf, if resp3 { b'>' } else { b'*' }, values, protocol)
+            }
+            ZystResponse::Double(value) => {
+                let mut formatted = BytesMut::new();
+                let _ = write!(formatted, "{}", FormatDouble(*value));
+                if resp3 {
+                    buf.put_u8(b',');
+                    buf.put_slice(&formatted);
+                    buf.put_slice(b"\r\n");
+                } else {
+                    encode_bulk(buf, &formatted);
+                }
+            }
+            ZystResponse::Boolean(value) => match (resp3, value) {
+                (true, true) => buf.put_slice(b"#t\r\n"),
+                (true, false) => buf.put_slice(b"#f\r\n"),
+                (false, value) => encode_int(buf, b':', *value as i64),
+            },
+            ZystResponse::BigNumber(value) => {
+                if resp3 {
+                    buf.put_u8(b'(');
+                    put_line(buf, value);
+                } else {
+                    encode_bulk(buf, value.as_bytes());
+                }
+            }
+            ZystResponse::Nil if resp3 => buf.put_slice(b"_\r\n"),
+            ZystResponse::Nil => buf.put_slice(b"$-1\r\n"),
+            ZystResponse::NilArray if resp3 => buf.put_slice(b"_\r\n"),
+            ZystResponse::NilArray => buf.put_slice(b"*-1\r\n"),
+            ZystResponse::EmptyArray => buf.put_slice(b"*0\r\n"),
+            ZystResponse::Error(err) => encode_error(buf, err),
+            ZystResponse::Replies(replies) => {
+                for reply in replies {
+                    reply.encode(buf, protocol);
                 }
-                write!(f, "{response}")
             }
-            ZystResponse::Nil => write!(f, "+(nil)\r\n"),
-            ZystResponse::EmptyArray => write!(f, "+(empty array)\r\n"),
-            ZystResponse::Error(err) => write!(f, "-{err}\r\n"),
         }
     }
 }
+
+fn encode_aggregate(
+    buf: &mut BytesMut,
+    prefix: u8,
+    values: &[ZystResponse],
+    protocol: Protocol,
+) {
+    encode_int(buf, prefix, values.len() as i64);
+    for value in values {
+        value.encode(buf, protocol);
+    }
+}
+
+// Errors are sent verbatim when they start with an error code such as
+// `WRONGTYPE`, `ERR` is used otherwise
+fn encode_error(buf: &mut BytesMut, err: &ZystError) {
+    let start = buf.len();
+    buf.put_u8(b'-');
+    let _ = write!(buf, "{err}");
+
+    let code = buf[start + 1..]
+        .split(|b| *b == b' ')
+        .next()
+        .unwrap_or_default();
+
+    if code.is_empty() || !code.iter().all(|b| b.is_ascii_uppercase()) {
+        let message = buf.split_off(start + 1);
+        buf.put_slice(b"ERR ");
+        buf.unsplit(message);
+    }
+
+    // A newline would end the error early and desync the client
+    for byte in buf[start..].iter_mut() {
+        if *byte == b'\r' || *byte == b'\n' {
+            *byte = b' ';
+        }
+    }
+
+    buf.put_slice(b"\r\n");
+}
+
+fn encode_int(buf: &mut BytesMut, prefix: u8, value: i64) {
+    buf.put_u8(prefix);
+    let _ = write!(buf, "{value}");
+    buf.put_slice(b"\r\n");
+}
+
+fn encode_bulk(buf: &mut BytesMut, value: &[u8]) {
+    encode_int(buf, b'$', value.len() as i64);
+    buf.put_slice(value);
+    buf.put_slice(b"\r\n");
+}
+
+fn put_line(buf: &mut BytesMut, value: &str) {
+    // Simple strings can't hold a newline
+    buf.extend(
+        value
+            .bytes()
+            .map(|b| if b == b'\r' || b == b'\n' { b' ' } else { b }),
+    );
+    buf.put_slice(b"\r\n");
+}
+
+/// Formats a double the way Redis replies with it: `inf`, `-inf`, `nan`,
+/// no trailing `.0` on integral values
+pub struct FormatDouble(pub f64);
+
+impl fmt::Display for FormatDouble {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            v if v.is_nan() => write!(f, "nan"),
+            v if v == f64::INFINITY => write!(f, "inf"),
+            v if v == f64::NEG_INFINITY => write!(f, "-inf"),
+            v => write!(f, "{v}"),
+        }
+    }
+}
+
+// Mostly useful for tests and debugging, the server writes `encode` output
+// straight to the socket
+impl fmt::Display for ZystResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = BytesMut::new();
+        self.encode(&mut buf, Protocol::Resp2);
+        write!(f, "{}", String::from_utf8_lossy(&buf))
+    }
+}
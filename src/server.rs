@@ -1,49 +1,145 @@
-use crate::errors::format_redis_error;
+use crate::client::{Client, ReplyMode};
+use crate::commands::pubsub::unsubscribe_all;
+use crate::commands::transactions::unwatch_all;
+use crate::errors::ZystError;
 use crate::process::process_command;
-use crate::resp::parse_resp_command;
-use crate::types::Db;
+use crate::resp::RespDecoder;
+use crate::response::ZystResponse;
+use crate::state::State;
+use bytes::{Bytes, BytesMut};
 use std::error::Error;
+use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
-pub async fn handle_client(mut socket: TcpStream, db: Db) -> Result<(), Box<dyn Error>> {
-    let mut buffer = [0; 1024];
+pub async fn handle_client(
+    socket: TcpStream,
+    state: Arc<State>,
+    max_bulk_len: usize,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut client = Client::connected(socket.peer_addr().ok(), socket.local_addr().ok());
+
+    state.clients().register(client.handle.clone());
+    let result = serve(socket, &state, &mut client, max_bulk_len).await;
+    unwatch_all(&state, &mut client).await;
+    unsubscribe_all(&state, &mut client);
+    state.clients().unregister(client.id);
+
+    result
+}
+
+async fn serve(
+    mut socket: TcpStream,
+    state: &State,
+    client: &mut Client,
+    max_bulk_len: usize,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut decoder = RespDecoder::new(max_bulk_len);
+    let mut buffer = BytesMut::with_capacity(4096);
+    let mut output = BytesMut::with_capacity(4096);
 
     loop {
-        let bytes_read = socket.read(&mut buffer).await?;
+        // Run every complete command already buffered (pipelining) before
+        // waiting for more bytes
+        while !client.handle.is_killed() {
+            let frame = match decoder.decode(&mut buffer) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(e) => {
+                    // The stream can't be resynchronised after a protocol
+                    // error, Redis closes the connection as well
+                    ZystResponse::Error(e).encode(&mut output, client.protocol);
+                    socket.write_all(&output).await?;
+                    socket.flush().await?;
+                    return Ok(());
+                }
+            };
 
-        if bytes_read == 0 {
-            // Client disconnected
-            return Ok(());
+            if frame.is_empty() {
+                continue;
+            }
+
+            // A CLIENT REPLY SKIP drops the reply of the command after it
+            let skip = client.reply == ReplyMode::Skip;
+            if skip {
+                client.reply = ReplyMode::On;
+            }
+
+            let response =
+                match run_command(frame, state, client, &mut socket, &mut buffer).await {
+                    Ok(resp) => resp,
+                    Err(e) => ZystResponse::Error(e),
+                };
+
+            if client.reply == ReplyMode::On && !skip {
+                response.encode(&mut output, client.protocol);
+            }
+        }
+
+        if !output.is_empty() {
+            socket.write_all(&output).await?;
+            socket.flush().await?;
+            output.clear();
         }
 
-        let raw_command = String::from_utf8_lossy(&buffer[..bytes_read])
-            .trim()
-            .to_string();
+        // Killed by CLIENT KILL, after replying to the current command
+        if client.handle.is_killed() {
+            return Ok(());
+        }
 
-        let parsed_commands = match parse_resp_command(&raw_command) {
-            Ok(parsed) => parsed,
-            Err(e) => {
-                let error_response = format_redis_error(e);
-                socket.write_all(error_response.as_bytes()).await?;
-                socket.flush().await?;
+        // Pub/sub messages are sent as they are published, while still
+        // reading commands
+        let bytes_read = tokio::select! {
+            read = socket.read_buf(&mut buffer) => read?,
+            Some(message) = client.messages.recv() => {
+                push(&mut output, client, message);
                 continue;
             }
+            _ = client.handle.killed() => return Ok(()),
         };
 
-        for parsed in parsed_commands {
-            match process_command(parsed, &db, false).await {
-                Ok(resp) => {
-                    let response = resp.to_string();
-                    socket.write_all(response.as_bytes()).await?;
-                    socket.flush().await?;
-                }
-                Err(e) => {
-                    let error_response = format_redis_error(e);
-                    socket.write_all(error_response.as_bytes()).await?;
-                    socket.flush().await?;
+        if bytes_read == 0 {
+            // Client disconnected
+            return Ok(());
+        }
+    }
+}
+
+// Runs a command while still reading the connection, so that a client
+// blocked by a command stops waiting when it disconnects. What is read is
+// kept for the next commands.
+async fn run_command(
+    frame: Vec<Bytes>,
+    state: &State,
+    client: &mut Client,
+    socket: &mut TcpStream,
+    buffer: &mut BytesMut,
+) -> Result<ZystResponse, ZystError> {
+    let handle = client.handle.clone();
+    let command = process_command(frame, state, client, false);
+    tokio::pin!(command);
+
+    let mut open = true;
+    loop {
+        tokio::select! {
+            response = &mut command => return response,
+            read = socket.read_buf(buffer), if open => {
+                if !matches!(read, Ok(n) if n > 0) {
+                    open = false;
+                    handle.close();
                 }
             }
         }
     }
 }
+
+// Encodes a message and the others already waiting, so that a burst is
+// written at once
+fn push(output: &mut BytesMut, client: &mut Client, message: ZystResponse) {
+    let mut message = Some(message);
+    while let Some(next) = message.take().or_else(|| client.messages.try_recv().ok()) {
+        if client.reply != ReplyMode::Off {
+            next.encode(output, client.protocol);
+        }
+    }
+}
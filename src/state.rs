@@ -0,0 +1,110 @@
+use crate::blocking::Blocking;
+use crate::client::{ClientPause, ClientRegistry};
+use crate::keyspace::Keyspace;
+use crate::notify::NotifyFlags;
+use crate::pubsub::PubSub;
+use crate::types::Db;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+pub const DEFAULT_DATABASES: usize = 16;
+
+/// State shared by every connection
+#[derive(Debug)]
+pub struct State {
+    /// Logical databases, a client selects one by its index
+    databases: Vec<Db>,
+    clients: ClientRegistry,
+    client_pause: ClientPause,
+    pubsub: PubSub,
+    blocking: Blocking,
+    /// Classes of keyspace events published, set by notify-keyspace-events
+    keyspace_events: AtomicU32,
+    command_gate: RwLock<()>,
+}
+
+impl State {
+    /// A server with `count` empty databases
+    pub fn new(count: usize) -> Self {
+        Self::with_databases(
+            (0..count.max(1))
+                .map(|_| Arc::new(RwLock::new(Keyspace::new())))
+                .collect(),
+        )
+    }
+
+    pub fn with_databases(databases: Vec<Db>) -> Self {
+        State {
+            databases,
+            clients: ClientRegistry::default(),
+            client_pause: ClientPause::default(),
+            pubsub: PubSub::default(),
+            blocking: Blocking::default(),
+            keyspace_events: AtomicU32::new(0),
+            command_gate: RwLock::new(()),
+        }
+    }
+
+    pub fn databases(&self) -> &[Db] {
+        &self.databases
+    }
+
+    pub fn db(&self, index: usize) -> Option<&Db> {
+        self.databases.get(index)
+    }
+
+    pub fn clients(&self) -> &ClientRegistry {
+        &self.clients
+    }
+
+    pub fn client_pause(&self) -> &ClientPause {
+        &self.client_pause
+    }
+
+    pub fn pubsub(&self) -> &PubSub {
+        &self.pubsub
+    }
+
+    pub fn blocking(&self) -> &Blocking {
+        &self.blocking
+    }
+
+    pub fn keyspace_events(&self) -> NotifyFlags {
+        NotifyFlags::from_bits(self.keyspace_events.load(Ordering::Relaxed))
+    }
+
+    pub fn set_keyspace_events(&self, flags: NotifyFlags) {
+        self.keyspace_events.store(flags.bits(), Ordering::Relaxed);
+    }
+
+    /// Commands run holding the gate shared. EXEC holds it exclusively, so
+    /// that no other command runs in the middle of a transaction.
+    pub fn command_gate(&self) -> &RwLock<()> {
+        &self.command_gate
+    }
+
+    /// Write locks two distinct databases, always in index order so that
+    /// commands locking the same pair concurrently can't deadlock. None when
+    /// an index is out of range or both are the same.
+    pub async fn write_pair(
+        &self,
+        first: usize,
+        second: usize,
+    ) -> Option<(
+        RwLockWriteGuard<'_, Keyspace>,
+        RwLockWriteGuard<'_, Keyspace>,
+    )> {
+        let (first_db, second_db) = (self.db(first)?, self.db(second)?);
+
+        if first < second {
+            let first_guard = first_db.write().await;
+            Some((first_guard, second_db.write().await))
+        } else if first > second {
+            let second_guard = second_db.write().await;
+            Some((first_db.write().await, second_guard))
+        } else {
+            None
+        }
+    }
+}
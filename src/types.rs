@@ -1,77 +1,220 @@
-use indexmap::IndexMap;
-use std::collections::HashSet;
+use crate::keyspace::Keyspace;
+use bytes::Bytes;
+use indexmap::{IndexMap, IndexSet};
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
-pub type Db = Arc<RwLock<IndexMap<String, DbValue>>>;
+pub type Db = Arc<RwLock<Keyspace>>;
 
 #[derive(Debug, Clone)]
 pub struct Command {
-    pub command_type: CommandType,
+    pub name: &'static str,
     pub args: CommandArgs,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub enum CommandType {
-    DOCS,
-    PONG,
-    GET,
-    SET,
-    DEL,
-    FLUSHDB,
-    KEYS,
-    EXISTS,
-    EXPIRE,
-    TTL,
-    INCR,
-    DECR,
-    INCRBY,
-    LPUSH,
-    LRANGE,
-    RPUSH,
-    LPOP,
-    RPOP,
-    HSET,
-    HGET,
-    HGETALL,
-    HDEL,
-    CLIENT,
-    SADD,
-    SMEMBERS,
-    SREM,
-}
-
 #[derive(Debug, Clone)]
 pub enum CommandArgs {
-    NoArgs,                    // PONG, FLUSHDB
-    SingleKey(String),         // GET key
-    MultipleKeys(Vec<String>), // DEL key1 key2 key3
+    NoArgs,                   // PONG, FLUSHDB
+    SingleKey(Bytes),         // GET key
+    MultipleKeys(Vec<Bytes>), // DEL key1 key2 key3
     KeyWithValue {
-        key: String,
-        value: String,
+        key: Bytes,
+        value: Bytes,
     }, // SET key value
     KeyWithValues {
-        key: String,
-        values: Vec<String>,
+        key: Bytes,
+        values: Vec<Bytes>,
     },
     HashFields {
-        key: String,
-        fields: IndexMap<String, String>,
+        key: Bytes,
+        fields: IndexMap<Bytes, Bytes>,
     }, // HSET key field1 value1 field2 value2
+    HashExpire {
+        key: Bytes,
+        /// Unix time in milliseconds
+        expires_at: i64,
+        condition: Option<ExpireCondition>,
+        fields: Vec<Bytes>,
+    }, // HEXPIRE key 60 NX FIELDS 2 field1 field2
+    HashGetEx {
+        key: Bytes,
+        /// None leaves the expiry of the fields as it is
+        expiry: Option<FieldExpiry>,
+        fields: Vec<Bytes>,
+    }, // HGETEX key EX 60 FIELDS 2 field1 field2
+    HashSetEx {
+        key: Bytes,
+        /// FXX when true, FNX when false
+        fields_exist: Option<bool>,
+        /// None removes the expiry of the fields
+        expiry: Option<FieldExpiry>,
+        fields: IndexMap<Bytes, Bytes>,
+    }, // HSETEX key FNX EX 60 FIELDS 1 field1 value1
+    Set {
+        key: Bytes,
+        value: Bytes,
+        options: SetOptions,
+    }, // SET key value NX EX 60
+    Expire {
+        key: Bytes,
+        expires_at: i64,
+        conditions: Vec<ExpireCondition>,
+    }, // EXPIRE key 60 XX GT
+    Scan {
+        key: Option<Bytes>,
+        cursor: u64,
+        options: ScanOptions,
+    }, // SCAN 0 MATCH user:* COUNT 100
+    Copy {
+        source: Bytes,
+        destination: Bytes,
+        db: Option<usize>,
+        replace: bool,
+    }, // COPY source destination DB 1 REPLACE
+    Select(usize), // SELECT 1
+    Move {
+        key: Bytes,
+        db: usize,
+    }, // MOVE key 1
+    SwapDb(usize, usize), // SWAPDB 0 1
+    Flush {
+        lazy: bool,
+    }, // FLUSHDB ASYNC
+    ListPop {
+        keys: Vec<Bytes>,
+        pop_type: PopType,
+        /// Set by the commands replying with an array of elements
+        count: Option<usize>,
+        /// How long to block when every list is empty, zero waits forever
+        timeout: Option<Duration>,
+    }, // BLPOP key1 key2 0.5, BLMPOP 0 2 key1 key2 LEFT COUNT 10
+    ListMove {
+        source: Bytes,
+        destination: Bytes,
+        pop_type: PopType,
+        push_type: ListPushType,
+        timeout: Option<Duration>,
+    }, // BLMOVE source destination LEFT RIGHT 0
+    ListInsert {
+        key: Bytes,
+        before: bool,
+        pivot: Bytes,
+        element: Bytes,
+    }, // LINSERT key BEFORE pivot element
+    Lpos {
+        key: Bytes,
+        element: Bytes,
+        options: LposOptions,
+    }, // LPOS key element RANK -1 COUNT 2
+    SetMove {
+        source: Bytes,
+        destination: Bytes,
+        member: Bytes,
+    }, // SMOVE source destination member
+    Sintercard {
+        keys: Vec<Bytes>,
+        /// Zero counts the whole intersection
+        limit: usize,
+    }, // SINTERCARD 2 key1 key2 LIMIT 10
+    Srandmember {
+        key: Bytes,
+        /// None replies with a single member instead of an array
+        count: Option<i64>,
+    }, // SRANDMEMBER key -5
+    Hrandfield {
+        key: Bytes,
+        /// None replies with a single field instead of an array
+        count: Option<i64>,
+        with_values: bool,
+    }, // HRANDFIELD key -5 WITHVALUES
+}
+
+/// Condition an `EXPIRE` is subject to, a key without a TTL counts as an
+/// infinite TTL for `GT` and `LT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireCondition {
+    NX,
+    XX,
+    GT,
+    LT,
+}
+
+/// Expiry HGETEX and HSETEX give the fields they read or set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldExpiry {
+    /// Unix time in milliseconds
+    At(i64),
+    /// HGETEX PERSIST
+    Persist,
+    /// HSETEX KEEPTTL
+    KeepTtl,
+}
+
+/// Condition a `SET` is subject to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetCondition {
+    NX,
+    XX,
+    IFEQ(Bytes),
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub pattern: Option<Bytes>,
+    /// How many elements to look at, not how many are returned
+    pub count: usize,
+    /// Only return keys of this type, such as `list`
+    pub kind: Option<&'static str>,
+    /// Only return the fields of a hash
+    pub no_values: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LposOptions {
+    /// Which match to start from, negative to search from the tail
+    pub rank: i64,
+    /// How many matches to return, zero for all of them. None replies with
+    /// a single position instead of an array.
+    pub count: Option<usize>,
+    /// How many elements to compare at most, zero for the whole list
+    pub maxlen: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SetOptions {
+    pub condition: Option<SetCondition>,
+    /// Reply with the previous value
+    pub get: bool,
+    /// Unix time in milliseconds
+    pub expires_at: Option<i64>,
+    pub keep_ttl: bool,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct KeyBase<T> {
-    pub name: String,
+    pub name: Bytes,
     pub data: T,
+    /// Unix time in milliseconds
     pub expires_at: Option<i64>,
 }
 
-pub type Key = KeyBase<Option<String>>;
-pub type KeyList = KeyBase<VecDeque<String>>;
-pub type KeySet = KeyBase<HashSet<String>>;
-pub type KeyHash = KeyBase<IndexMap<String, String>>;
+pub type Key = KeyBase<Option<Bytes>>;
+pub type KeyList = KeyBase<VecDeque<Bytes>>;
+pub type KeySet = KeyBase<IndexSet<Bytes>>;
+pub type KeyHash = KeyBase<HashData>;
+
+/// Fields of a hash, along with the expiry of the fields that have one.
+///
+/// Like keys, expired fields read as missing until a write or the active
+/// expire cycle deletes them with `remove_expired`.
+#[derive(Debug, Clone, Default)]
+pub struct HashData {
+    pub(crate) fields: IndexMap<Bytes, Bytes>,
+    /// Unix time in milliseconds each field with a TTL expires at
+    pub(crate) expires: IndexMap<Bytes, i64>,
+}
 
 #[derive(Debug, Clone)]
 pub enum DbValue {
@@ -8,11 +8,11 @@ fn test_sadd() {
     assert!(response.contains("(integer) 2"));
 
     let response = send_command("SADD myset How are you");
-    assert!(response.contains("(integer) 5"));
+    assert!(response.contains("(integer) 3"));
 
     // World is already in the set
     let response = send_command("SADD myset World");
-    assert!(response.contains("(integer) 5"));
+    assert!(response.contains("(integer) 0"));
 
     stop_server(&mut server);
 }
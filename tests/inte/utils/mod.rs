@@ -1,11 +1,11 @@
-use redis::{Client, Cmd, FromRedisValue, RedisResult, Value};
+use redis::{Client, Cmd, ErrorKind, FromRedisValue, RedisResult, Value};
 use std::net::TcpStream;
 use std::process::{Child, Command};
 use std::thread::sleep;
 use std::time::Duration;
 
 pub fn start_server() -> Child {
-    let child = Command::new("cargo")
+    let mut child = Command::new("cargo")
         .args(["run"])
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
@@ -21,6 +21,7 @@ pub fn start_server() -> Child {
         sleep(Duration::from_secs(1)); // Wait before retrying
     }
 
+    stop_server(&mut child);
     panic!("Server did not start in time");
 }
 
@@ -50,6 +51,7 @@ pub fn send_command(command: &str) -> String {
         Ok(Value::Okay) => "OK".to_string(), // Handle OK response
         Ok(Value::Int(int_value)) => format!("(integer) {}", int_value), // Handle integers (e.g., LPUSH, LLEN)
         Ok(Value::BulkString(bytes)) => String::from_utf8_lossy(&bytes).to_string(), // Handle bulk string responses
+        Ok(Value::Array(items)) if items.is_empty() => "(empty array)".to_string(), // Handle empty arrays like redis-cli
         Ok(Value::Array(items)) => {
             let strings: Vec<String> = items
                 .into_iter()
@@ -59,7 +61,12 @@ pub fn send_command(command: &str) -> String {
         }
         Ok(Value::SimpleString(s)) => s, // Handle simple string responses
         Ok(Value::Nil) => "(nil)".to_string(), // Handle nil responses
-        Err(e) => format!("{}", e),      // Handle errors
+        Err(e) => match (e.kind(), e.code(), e.detail()) {
+            // Handle errors like redis-cli does: "CODE detail"
+            (ErrorKind::TypeError, _, Some(detail)) => format!("WRONGTYPE {}", detail),
+            (_, Some(code), Some(detail)) => format!("{} {}", code, detail),
+            _ => format!("{}", e),
+        },
         _ => "-ERR Unexpected response\r\n".to_string(),
     }
 }
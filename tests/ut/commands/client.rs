@@ -0,0 +1,290 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::run;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use zyst::client::Client;
+    use zyst::server::handle_client;
+    use zyst::state::State;
+
+    /// Serves connections on a random port, returns its address
+    async fn serve(state: Arc<State>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let _ = handle_client(socket, state, 1024).await;
+                });
+            }
+        });
+
+        addr
+    }
+
+    /// Sends inline commands, returns what was read until `expected` bytes
+    /// arrived or nothing more came
+    async fn exchange(socket: &mut TcpStream, commands: &str, expected: usize) -> String {
+        socket.write_all(commands.as_bytes()).await.unwrap();
+
+        let mut reply = Vec::new();
+        let mut buffer = [0; 1024];
+        while reply.len() < expected {
+            let read =
+                tokio::time::timeout(Duration::from_millis(200), socket.read(&mut buffer));
+            match read.await {
+                Ok(Ok(n)) if n > 0 => reply.extend_from_slice(&buffer[..n]),
+                _ => break,
+            }
+        }
+
+        String::from_utf8(reply).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_name_and_info() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        let id = run(&state, &mut client, &["CLIENT", "ID"]).await;
+        assert_eq!(id, format!(":{}\r\n", client.id));
+
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "GETNAME"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "SETNAME", "worker"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["client", "getname"]).await,
+            "$6\r\nworker\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "SETNAME", "bad name"]).await,
+            "ERR Client names cannot contain spaces, newlines or special characters."
+        );
+
+        run(
+            &state,
+            &mut client,
+            &["CLIENT", "SETINFO", "LIB-NAME", "redis-py"],
+        )
+        .await;
+        run(
+            &state,
+            &mut client,
+            &["CLIENT", "SETINFO", "lib-ver", "5.0.1"],
+        )
+        .await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["CLIENT", "SETINFO", "LIB-COLOR", "red"]
+            )
+            .await,
+            "ERR Unrecognized option 'LIB-COLOR'"
+        );
+        run(&state, &mut client, &["SELECT", "2"]).await;
+
+        let info = run(&state, &mut client, &["CLIENT", "INFO"]).await;
+        assert!(info.contains(&format!("id={} ", client.id)));
+        assert!(info.contains(" name=worker "));
+        assert!(info.contains(" db=2 "));
+        assert!(info.contains(" cmd=client|info "));
+        assert!(info.contains(" lib-name=redis-py lib-ver=5.0.1\n"));
+
+        // An empty name removes it
+        run(&state, &mut client, &["CLIENT", "SETNAME", ""]).await;
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "GETNAME"]).await,
+            "$-1\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_list() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let mut other = Client::new();
+        state.clients().register(client.handle.clone());
+        state.clients().register(other.handle.clone());
+
+        run(&state, &mut other, &["CLIENT", "SETNAME", "other"]).await;
+
+        let list = run(&state, &mut client, &["CLIENT", "LIST"]).await;
+        assert_eq!(list.matches("id=").count(), 2);
+        assert!(list.contains(" name=other "));
+        assert!(list.contains(" cmd=client|setname "));
+        assert!(list.contains(" cmd=client|list "));
+
+        let id = other.id.to_string();
+        let list = run(
+            &state,
+            &mut client,
+            &["CLIENT", "LIST", "ID", &id, "999999"],
+        )
+        .await;
+        assert!(list.contains(" name=other "));
+        assert_eq!(list.matches("id=").count(), 1);
+
+        let list = run(&state, &mut client, &["CLIENT", "LIST", "TYPE", "pubsub"]).await;
+        assert_eq!(list, "$0\r\n\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "LIST", "TYPE", "robot"]).await,
+            "ERR Unknown client type 'robot'"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "LIST", "ID", "x"]).await,
+            "ERR Invalid client ID"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_kill() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let other = Client::new();
+        state.clients().register(client.handle.clone());
+        state.clients().register(other.handle.clone());
+
+        let id = other.id.to_string();
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "KILL", "ID", &id]).await,
+            ":1\r\n"
+        );
+        assert!(other.handle.is_killed());
+
+        // The caller is spared unless SKIPME no
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "KILL", "USER", "default"]).await,
+            ":1\r\n"
+        );
+        assert!(!client.handle.is_killed());
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["CLIENT", "KILL", "USER", "default", "SKIPME", "no"]
+            )
+            .await,
+            ":2\r\n"
+        );
+        assert!(client.handle.is_killed());
+
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "KILL", "USER", "admin"]).await,
+            "ERR No such user 'admin'"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "KILL", "ID", "0"]).await,
+            "ERR client-id should be greater than 0"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "KILL", "ID"]).await,
+            "ERR No such client"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["CLIENT", "KILL", "ADDR", "1.2.3.4:5", "SKIPME"]
+            )
+            .await,
+            "ERR syntax error"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_kill_closes_connection() {
+        let state = Arc::new(State::new(16));
+        let addr = serve(state.clone()).await;
+
+        let mut victim = TcpStream::connect(&addr).await.unwrap();
+        let mut killer = TcpStream::connect(&addr).await.unwrap();
+        let victim_addr = victim.local_addr().unwrap().to_string();
+        assert_eq!(exchange(&mut victim, "PING\r\n", 7).await, "+PONG\r\n");
+
+        let reply = exchange(&mut killer, &format!("CLIENT KILL {victim_addr}\r\n"), 5).await;
+        assert_eq!(reply, "+OK\r\n");
+
+        let mut buffer = [0; 16];
+        let read = tokio::time::timeout(Duration::from_secs(1), victim.read(&mut buffer));
+        assert_eq!(read.await.unwrap().unwrap(), 0);
+
+        let reply = exchange(&mut killer, &format!("CLIENT KILL {victim_addr}\r\n"), 20).await;
+        assert_eq!(reply, "-ERR No such client\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_client_reply() {
+        let state = Arc::new(State::new(16));
+        let addr = serve(state).await;
+        let mut socket = TcpStream::connect(&addr).await.unwrap();
+
+        let reply = exchange(&mut socket, "CLIENT REPLY SKIP\r\nSET a 1\r\nGET a\r\n", 7).await;
+        assert_eq!(reply, "$1\r\n1\r\n");
+
+        let reply = exchange(
+            &mut socket,
+            "CLIENT REPLY OFF\r\nSET a 2\r\nGET a\r\nCLIENT REPLY ON\r\nGET a\r\n",
+            12,
+        )
+        .await;
+        assert_eq!(reply, "+OK\r\n$1\r\n2\r\n");
+
+        assert_eq!(
+            exchange(&mut socket, "CLIENT REPLY MAYBE\r\n", 17).await,
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_pause() {
+        let state = Arc::new(State::new(16));
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "PAUSE", "-1"]).await,
+            "ERR timeout is negative"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "PAUSE", "soon"]).await,
+            "ERR timeout is not an integer or out of range"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "PAUSE", "10", "READ"]).await,
+            "ERR syntax error"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "PAUSE", "60000", "WRITE"]).await,
+            "+OK\r\n"
+        );
+        assert!(state.client_pause().is_paused(true));
+        assert!(!state.client_pause().is_paused(false));
+
+        // Reads go through, writes wait for the end of the pause
+        let addr = serve(state.clone()).await;
+        let mut socket = TcpStream::connect(&addr).await.unwrap();
+        assert_eq!(exchange(&mut socket, "GET k\r\n", 5).await, "$-1\r\n");
+        assert_eq!(exchange(&mut socket, "SET k v\r\n", 5).await, "");
+
+        run(&state, &mut client, &["CLIENT", "UNPAUSE"]).await;
+        assert_eq!(exchange(&mut socket, "", 5).await, "+OK\r\n");
+
+        // A pause ends by itself
+        run(&state, &mut client, &["CLIENT", "PAUSE", "400"]).await;
+        assert!(state.client_pause().is_paused(false));
+        assert_eq!(exchange(&mut socket, "GET k\r\n", 7).await, "");
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(exchange(&mut socket, "", 7).await, "$1\r\nv\r\n");
+    }
+}
@@ -1,13 +1,19 @@
 #[cfg(test)]
 mod tests {
-    use indexmap::IndexMap;
+    use crate::ut::commands::{argv, run};
+    use bytes::Bytes;
     use std::sync::Arc;
     use tokio::sync::RwLock;
+    use zyst::client::Client;
     use zyst::commands::db::*;
+    use zyst::keyspace::Keyspace;
+    use zyst::parser::check_command;
+    use zyst::process::execute;
+    use zyst::state::State;
     use zyst::types::*;
 
     async fn setup_db() -> Db {
-        Arc::new(RwLock::new(IndexMap::new()))
+        Arc::new(RwLock::new(Keyspace::new()))
     }
 
     #[tokio::test]
@@ -17,18 +23,18 @@ mod tests {
         {
             let mut db_write = db.write().await;
             db_write.insert(
-                "key1".to_string(),
+                Bytes::from("key1"),
                 DbValue::StringKey(Key {
-                    name: "key1".to_string(),
-                    data: Some("value1".to_string()),
+                    name: Bytes::from("key1"),
+                    data: Some(Bytes::from("value1")),
                     expires_at: None,
                 }),
             );
             db_write.insert(
-                "key2".to_string(),
+                Bytes::from("key2"),
                 DbValue::StringKey(Key {
-                    name: "key2".to_string(),
-                    data: Some("value2".to_string()),
+                    name: Bytes::from("key2"),
+                    data: Some(Bytes::from("value2")),
                     expires_at: None,
                 }),
             );
@@ -39,7 +45,16 @@ mod tests {
             assert!(!db_read.is_empty());
         }
 
-        let result = flush_db(&db).await.unwrap().to_string();
+        let result = flush_db(
+            &db,
+            Command {
+                name: "flushdb",
+                args: CommandArgs::Flush { lazy: false },
+            },
+        )
+        .await
+        .unwrap()
+        .to_string();
 
         {
             let db_read = db.read().await;
@@ -48,4 +63,199 @@ mod tests {
 
         assert_eq!(result, "+OK\r\n");
     }
+
+    #[tokio::test]
+    async fn test_select() {
+        let state = State::new(4);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["SET", "k", "zero"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["SELECT", "3"]).await, "+OK\r\n");
+        assert_eq!(client.db, 3);
+        assert_eq!(run(&state, &mut client, &["GET", "k"]).await, "$-1\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["SET", "k", "three"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["DBSIZE"]).await, ":1\r\n");
+
+        assert_eq!(
+            run(&state, &mut client, &["SELECT", "4"]).await,
+            "ERR DB index is out of range"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SELECT", "-1"]).await,
+            "ERR DB index is out of range"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SELECT", "one"]).await,
+            "ERR value is not an integer or out of range"
+        );
+        assert_eq!(client.db, 3);
+
+        assert_eq!(run(&state, &mut client, &["SELECT", "0"]).await, "+OK\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["GET", "k"]).await,
+            "$4\r\nzero\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move() {
+        let state = State::new(2);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SET", "k", "v", "EX", "100"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["MOVE", "k", "1"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "k"]).await, ":0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["MOVE", "k", "1"]).await,
+            ":0\r\n"
+        );
+
+        run(&state, &mut client, &["SELECT", "1"]).await;
+        assert_eq!(run(&state, &mut client, &["GET", "k"]).await, "$1\r\nv\r\n");
+        assert_ne!(run(&state, &mut client, &["TTL", "k"]).await, ":-1\r\n");
+
+        // The destination already holds the key
+        run(&state, &mut client, &["SELECT", "0"]).await;
+        run(&state, &mut client, &["SET", "k", "other"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["MOVE", "k", "1"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["GET", "k"]).await,
+            "$5\r\nother\r\n"
+        );
+
+        // A key that didn't move isn't logged
+        let command = argv(&["MOVE", "k", "1"]);
+        let spec = check_command(&command).unwrap();
+        let mut propagated = Vec::new();
+        execute(spec, command, &state, &mut client, &mut propagated)
+            .await
+            .unwrap();
+        assert!(propagated.is_empty());
+
+        assert_eq!(
+            run(&state, &mut client, &["MOVE", "k", "0"]).await,
+            "ERR source and destination objects are the same"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["MOVE", "k", "2"]).await,
+            "ERR DB index is out of range"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_swapdb() {
+        let state = State::new(3);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SET", "k", "zero"]).await;
+        run(&state, &mut client, &["SELECT", "2"]).await;
+        run(&state, &mut client, &["RPUSH", "l", "a"]).await;
+
+        assert_eq!(
+            run(&state, &mut client, &["SWAPDB", "0", "2"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["GET", "k"]).await,
+            "$4\r\nzero\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "l"]).await, ":0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["SWAPDB", "1", "1"]).await,
+            "+OK\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["SWAPDB", "a", "1"]).await,
+            "ERR invalid first DB index"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SWAPDB", "0", "b"]).await,
+            "ERR invalid second DB index"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SWAPDB", "0", "3"]).await,
+            "ERR DB index is out of range"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flushdb_and_flushall() {
+        let state = State::new(2);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        run(&state, &mut client, &["SELECT", "1"]).await;
+        run(&state, &mut client, &["SET", "b", "1"]).await;
+
+        assert_eq!(
+            run(&state, &mut client, &["FLUSHDB", "ASYNC"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["DBSIZE"]).await, ":0\r\n");
+        run(&state, &mut client, &["SELECT", "0"]).await;
+        assert_eq!(run(&state, &mut client, &["DBSIZE"]).await, ":1\r\n");
+
+        run(&state, &mut client, &["SELECT", "1"]).await;
+        run(&state, &mut client, &["SET", "b", "1"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["FLUSHALL", "sync"]).await,
+            "+OK\r\n"
+        );
+        for db in state.databases() {
+            assert!(db.read().await.is_empty());
+        }
+
+        assert_eq!(
+            run(&state, &mut client, &["FLUSHALL", "LAZY"]).await,
+            "ERR syntax error"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_to_another_db() {
+        let state = State::new(2);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SADD", "s", "a", "b"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["COPY", "s", "s", "DB", "1"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["COPY", "s", "s", "DB", "1"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["COPY", "s", "s", "DB", "1", "REPLACE"]
+            )
+            .await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["COPY", "s", "t", "DB", "2"]).await,
+            "ERR DB index is out of range"
+        );
+
+        run(&state, &mut client, &["SELECT", "1"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SMEMBERS", "s"]).await,
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n"
+        );
+    }
 }
@@ -1,74 +1,88 @@
 #[cfg(test)]
 mod tests {
+    use crate::ut::commands::{argv, run};
+    use bytes::Bytes;
     use indexmap::IndexMap;
     use std::sync::Arc;
     use tokio::sync::RwLock;
+    use zyst::client::Client;
     use zyst::commands::hashsets::*;
+    use zyst::keyspace::Keyspace;
+    use zyst::parser::check_command;
+    use zyst::process::{execute, process_command};
+    use zyst::response::{Protocol, ZystResponse};
+    use zyst::state::State;
     use zyst::types::*;
 
     async fn setup_db() -> Db {
-        Arc::new(RwLock::new(IndexMap::new()))
+        Arc::new(RwLock::new(Keyspace::new()))
     }
 
     #[tokio::test]
     async fn test_hset_new_hash() {
         let db = setup_db().await;
         let fields = IndexMap::from([
-            ("name".to_string(), "Smith".to_string()),
-            ("first_name".to_string(), "John".to_string()),
+            (Bytes::from("name"), Bytes::from("Smith")),
+            (Bytes::from("first_name"), Bytes::from("John")),
         ]);
 
         let command = Command {
-            command_type: CommandType::HSET,
+            name: "hset",
             args: CommandArgs::HashFields {
-                key: "user:1".to_string(),
+                key: Bytes::from("user:1"),
                 fields,
             },
         };
 
         let result = hset(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 2\r\n");
+        assert_eq!(result, ":2\r\n");
 
         let db_read = db.read().await;
-        assert!(db_read.contains_key("user:1"));
+        assert!(db_read.contains_key("user:1".as_bytes()));
     }
 
     #[tokio::test]
     async fn test_hset_add_new_fields() {
         let db = setup_db().await;
 
-        let fields = IndexMap::from([("name".to_string(), "Doe".to_string())]);
+        let fields = IndexMap::from([(Bytes::from("name"), Bytes::from("Doe"))]);
         let command = Command {
-            command_type: CommandType::HSET,
+            name: "hset",
             args: CommandArgs::HashFields {
-                key: "user:2".to_string(),
+                key: Bytes::from("user:2"),
                 fields,
             },
         };
 
         let result = hset(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 1\r\n");
+        assert_eq!(result, ":1\r\n");
 
-        let fields = IndexMap::from([("age".to_string(), "30".to_string())]);
+        let fields = IndexMap::from([(Bytes::from("age"), Bytes::from("30"))]);
         let command = Command {
-            command_type: CommandType::HSET,
+            name: "hset",
             args: CommandArgs::HashFields {
-                key: "user:2".to_string(),
+                key: Bytes::from("user:2"),
                 fields,
             },
         };
 
         let result = hset(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 1\r\n");
+        assert_eq!(result, ":1\r\n");
 
         let db_read = db.read().await;
-        let stored_hash = match db_read.get("user:2") {
+        let stored_hash = match db_read.get("user:2".as_bytes()) {
             Some(DbValue::HashKey(hash)) => hash,
             _ => panic!("Expected HashKey"),
         };
 
-        assert_eq!(stored_hash.data.get("name"), Some(&"Doe".to_string()));
-        assert_eq!(stored_hash.data.get("age"), Some(&"30".to_string()));
+        assert_eq!(
+            stored_hash.data.get("name".as_bytes()),
+            Some(&Bytes::from("Doe"))
+        );
+        assert_eq!(
+            stored_hash.data.get("age".as_bytes()),
+            Some(&Bytes::from("30"))
+        );
     }
 
     #[tokio::test]
@@ -76,67 +90,887 @@ mod tests {
         let db = setup_db().await;
 
         let fields = IndexMap::from([
-            ("last_name".to_string(), "Smith".to_string()),
-            ("first_name".to_string(), "John".to_string()),
-            ("age".to_string(), "21".to_string()),
+            (Bytes::from("last_name"), Bytes::from("Smith")),
+            (Bytes::from("first_name"), Bytes::from("John")),
+            (Bytes::from("age"), Bytes::from("21")),
         ]);
 
         let command = Command {
-            command_type: CommandType::HSET,
+            name: "hset",
             args: CommandArgs::HashFields {
-                key: "hdelhash".to_string(),
+                key: Bytes::from("hdelhash"),
                 fields,
             },
         };
 
         let result = hset(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 3\r\n");
+        assert_eq!(result, ":3\r\n");
 
         let command = Command {
-            command_type: CommandType::HDEL,
+            name: "hdel",
             args: CommandArgs::KeyWithValues {
-                key: "hdelhash".to_string(),
-                values: vec!["last_name".to_string(), "first_name".to_string()],
+                key: Bytes::from("hdelhash"),
+                values: vec![Bytes::from("last_name"), Bytes::from("first_name")],
             },
         };
 
         let result = hdel(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 2\r\n");
+        assert_eq!(result, ":2\r\n");
 
         let command = Command {
-            command_type: CommandType::HDEL,
+            name: "hdel",
             args: CommandArgs::KeyWithValues {
-                key: "hdelhash".to_string(),
-                values: vec!["non_existent_field".to_string()],
+                key: Bytes::from("hdelhash"),
+                values: vec![Bytes::from("non_existent_field")],
             },
         };
 
         let result = hdel(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 0\r\n");
+        assert_eq!(result, ":0\r\n");
 
         let command = Command {
-            command_type: CommandType::HDEL,
+            name: "hdel",
             args: CommandArgs::KeyWithValues {
-                key: "unknownhash".to_string(),
-                values: vec!["some_field".to_string()],
+                key: Bytes::from("unknownhash"),
+                values: vec![Bytes::from("some_field")],
             },
         };
 
         let result = hdel(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 0\r\n");
+        assert_eq!(result, ":0\r\n");
 
         let command = Command {
-            command_type: CommandType::HDEL,
+            name: "hdel",
             args: CommandArgs::KeyWithValues {
-                key: "hdelhash".to_string(),
-                values: vec!["age".to_string()],
+                key: Bytes::from("hdelhash"),
+                values: vec![Bytes::from("age")],
             },
         };
 
         let result = hdel(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 1\r\n");
+        assert_eq!(result, ":1\r\n");
 
         let db_read = db.read().await;
-        assert!(!db_read.contains_key("hdelhash"));
+        assert!(!db_read.contains_key("hdelhash".as_bytes()));
+    }
+
+    #[tokio::test]
+    async fn test_hash_reads() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(
+            &state,
+            &mut client,
+            &["HSET", "h", "name", "John", "age", "21"],
+        )
+        .await;
+        assert_eq!(
+            run(&state, &mut client, &["HEXISTS", "h", "age"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HEXISTS", "h", "city"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["HLEN", "h"]).await, ":2\r\n");
+        assert_eq!(run(&state, &mut client, &["HLEN", "none"]).await, ":0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["HKEYS", "h"]).await,
+            "*2\r\n$4\r\nname\r\n$3\r\nage\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HVALS", "h"]).await,
+            "*2\r\n$4\r\nJohn\r\n$2\r\n21\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["HKEYS", "none"]).await, "*0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["HMGET", "h", "age", "city", "name"]).await,
+            "*3\r\n$2\r\n21\r\n$-1\r\n$4\r\nJohn\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HMGET", "none", "age"]).await,
+            "*1\r\n$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HSTRLEN", "h", "name"]).await,
+            ":4\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HSTRLEN", "h", "city"]).await,
+            ":0\r\n"
+        );
+
+        run(&state, &mut client, &["SET", "str", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["HMGET", "str", "a"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hsetnx_and_increments() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["HSETNX", "h", "a", "1"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HSETNX", "h", "a", "2"]).await,
+            ":0\r\n"
+        );
+
+        // A field that wasn't set isn't logged
+        let command = argv(&["HSETNX", "h", "a", "2"]);
+        let spec = check_command(&command).unwrap();
+        let mut propagated = Vec::new();
+        execute(spec, command, &state, &mut client, &mut propagated)
+            .await
+            .unwrap();
+        assert!(propagated.is_empty());
+
+        assert_eq!(
+            run(&state, &mut client, &["HGET", "h", "a"]).await,
+            "$1\r\n1\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBY", "h", "a", "41"]).await,
+            ":42\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBY", "h", "b", "-5"]).await,
+            ":-5\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBY", "counters", "visits", "1"]).await,
+            ":1\r\n"
+        );
+        run(
+            &state,
+            &mut client,
+            &["HSET", "h", "max", &i64::MAX.to_string()],
+        )
+        .await;
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBY", "h", "max", "1"]).await,
+            "ERR increment or decrement would overflow"
+        );
+        run(&state, &mut client, &["HSET", "h", "name", "John"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBY", "h", "name", "1"]).await,
+            "ERR hash value is not an integer"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBY", "h", "a", "1.5"]).await,
+            "ERR value is not an integer or out of range"
+        );
+
+        run(&state, &mut client, &["HSET", "h", "f", "10.50"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "f", "0.1"]).await,
+            "$4\r\n10.6\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "f", "-5.6"]).await,
+            "$1\r\n5\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "g", "2.0e3"]).await,
+            "$4\r\n2000\r\n"
+        );
+
+        // Written with 17 decimals at most, the trailing zeros trimmed
+        run(&state, &mut client, &["HINCRBYFLOAT", "h", "x", "0.1"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "x", "0.2"]).await,
+            "$3\r\n0.3\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HINCRBYFLOAT", "h", "x", "-0.123456789012345678"]
+            )
+            .await,
+            "$19\r\n0.17654321098765432\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "x", "1.5E-1"]).await,
+            "$19\r\n0.32654321098765432\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "f", "nan"]).await,
+            "ERR value is not a valid float"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "name", "1"]).await,
+            "ERR hash value is not a float"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HINCRBYFLOAT", "h", "f", "inf"]).await,
+            "ERR increment would produce NaN or Infinity"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HGET", "h", "f"]).await,
+            "$1\r\n5\r\n"
+        );
+
+        assert_eq!(
+            propagate_hincrbyfloat(
+                &argv(&["HINCRBYFLOAT", "h", "f", "0.1"]),
+                &ZystResponse::BulkString(Bytes::from("5.1"))
+            ),
+            argv(&["HSETEX", "h", "KEEPTTL", "FIELDS", "1", "f", "5.1"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replayed_hincrbyfloat_keeps_ttl() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let commands = [
+            argv(&["HSET", "h", "f", "1.5"]),
+            argv(&["HPEXPIRE", "h", "100000", "FIELDS", "1", "f"]),
+        ];
+        for command in &commands {
+            process_command(command.clone(), &state, &mut client, true)
+                .await
+                .unwrap();
+        }
+        let command = argv(&["HINCRBYFLOAT", "h", "f", "1"]);
+        let response = process_command(command.clone(), &state, &mut client, true)
+            .await
+            .unwrap();
+
+        // Replays what the AOF holds into another server
+        let replayed = State::new(16);
+        let mut client = Client::new();
+        for command in commands
+            .into_iter()
+            .chain([propagate_hincrbyfloat(&command, &response)])
+        {
+            process_command(command, &replayed, &mut client, true)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            run(&replayed, &mut client, &["HGET", "h", "f"]).await,
+            "$3\r\n2.5\r\n"
+        );
+        let ttl = run(&replayed, &mut client, &["HPTTL", "h", "FIELDS", "1", "f"]).await;
+        assert!(ttl.starts_with("*1\r\n:") && !ttl.contains('-'));
+    }
+
+    #[tokio::test]
+    async fn test_hrandfield() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["HSET", "h", "a", "1", "b", "2"]).await;
+
+        let field = run(&state, &mut client, &["HRANDFIELD", "h"]).await;
+        assert!(["$1\r\na\r\n", "$1\r\nb\r\n"].contains(&field.as_str()));
+
+        let fields = run(&state, &mut client, &["HRANDFIELD", "h", "5"]).await;
+        assert!(fields.starts_with("*2\r\n"));
+        assert!(fields.contains("$1\r\na\r\n") && fields.contains("$1\r\nb\r\n"));
+
+        // A negative count may repeat fields, values follow their field
+        let fields = run(
+            &state,
+            &mut client,
+            &["HRANDFIELD", "h", "-3", "WITHVALUES"],
+        )
+        .await;
+        assert!(fields.starts_with("*6\r\n"));
+        for pair in fields
+            .trim_start_matches("*6\r\n")
+            .split("$1\r\na\r\n")
+            .skip(1)
+        {
+            assert!(pair.starts_with("$1\r\n1\r\n"));
+        }
+
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "none"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "none", "2"]).await,
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "h", "2", "VALUES"]).await,
+            "ERR syntax error"
+        );
+
+        // Pairs of field and value in RESP3
+        let db = state.db(0).unwrap();
+        let command = Command {
+            name: "hrandfield",
+            args: CommandArgs::Hrandfield {
+                key: Bytes::from("h"),
+                count: Some(1),
+                with_values: true,
+            },
+        };
+        let reply = hrandfield(db, command, Protocol::Resp3).await.unwrap();
+        assert!(matches!(&reply, ZystResponse::Array(pairs) if pairs.len() == 1));
+    }
+
+    #[tokio::test]
+    async fn test_hrandfield_skips_expired_fields() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["HSET", "h", "a", "1", "b", "2"]).await;
+        run(
+            &state,
+            &mut client,
+            &["HPEXPIRE", "h", "1", "FIELDS", "1", "a"],
+        )
+        .await;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "h"]).await,
+            "$1\r\nb\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "h", "5"]).await,
+            "*1\r\n$1\r\nb\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "h", "-3"]).await,
+            "*3\r\n$1\r\nb\r\n$1\r\nb\r\n$1\r\nb\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["HRANDFIELD", "h", "-10000000000"]).await,
+            "ERR value is out of range"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hexpire_and_httl() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(
+            &state,
+            &mut client,
+            &["HSET", "h", "a", "1", "b", "2", "c", "3"],
+        )
+        .await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "100", "FIELDS", "2", "a", "none"]
+            )
+            .await,
+            "*2\r\n:1\r\n:-2\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HTTL", "h", "FIELDS", "3", "a", "b", "none"]
+            )
+            .await,
+            "*3\r\n:100\r\n:-1\r\n:-2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "none", "FIELDS", "1", "a"]).await,
+            "*1\r\n:-2\r\n"
+        );
+
+        // A field without TTL counts as an infinite TTL for GT and LT
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "50", "GT", "FIELDS", "2", "a", "b"]
+            )
+            .await,
+            "*2\r\n:0\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "50", "LT", "FIELDS", "2", "a", "b"]
+            )
+            .await,
+            "*2\r\n:1\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "80", "NX", "FIELDS", "2", "a", "c"]
+            )
+            .await,
+            "*2\r\n:0\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "80", "XX", "FIELDS", "1", "none"]
+            )
+            .await,
+            "*1\r\n:-2\r\n"
+        );
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIREAT", "h", "4102444800", "FIELDS", "1", "a"]
+            )
+            .await,
+            "*1\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRETIME", "h", "FIELDS", "1", "a"]
+            )
+            .await,
+            "*1\r\n:4102444800\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HPEXPIRETIME", "h", "FIELDS", "1", "a"]
+            )
+            .await,
+            "*1\r\n:4102444800000\r\n"
+        );
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HPERSIST", "h", "FIELDS", "3", "a", "none", "b"]
+            )
+            .await,
+            "*3\r\n:1\r\n:-2\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HPERSIST", "h", "FIELDS", "1", "a"]).await,
+            "*1\r\n:-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HPTTL", "h", "FIELDS", "1", "a"]).await,
+            "*1\r\n:-1\r\n"
+        );
+
+        // An expiry in the past deletes the fields, and the hash with the
+        // last one
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HPEXPIRE", "h", "0", "FIELDS", "2", "a", "b"]
+            )
+            .await,
+            "*2\r\n:2\r\n:2\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["HLEN", "h"]).await, ":1\r\n");
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HPEXPIREAT", "h", "1", "FIELDS", "1", "c"]
+            )
+            .await,
+            "*1\r\n:2\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "h"]).await, ":0\r\n");
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "10", "FIELDS", "2", "a"]
+            )
+            .await,
+            "ERR The `numfields` parameter must match the number of arguments"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "10", "FIELDS", "0", "a"]
+            )
+            .await,
+            "ERR Parameter `numFields` should be greater than 0"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "10", "XX", "a", "b", "c"]
+            )
+            .await,
+            "ERR Mandatory argument FIELDS is missing or not at the right position"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HEXPIRE", "h", "-1", "FIELDS", "1", "a"]
+            )
+            .await,
+            "ERR invalid expire time, must be >= 0"
+        );
+
+        run(&state, &mut client, &["SET", "str", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "str", "FIELDS", "1", "a"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fields_expire() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(
+            &state,
+            &mut client,
+            &["HSET", "h", "a", "1", "b", "2", "c", "3"],
+        )
+        .await;
+        run(
+            &state,
+            &mut client,
+            &["HPEXPIRE", "h", "10", "FIELDS", "1", "a"],
+        )
+        .await;
+        run(
+            &state,
+            &mut client,
+            &["HEXPIRE", "h", "100", "FIELDS", "2", "b", "c"],
+        )
+        .await;
+
+        // Setting a field removes its TTL, incrementing it keeps it
+        run(&state, &mut client, &["HSET", "h", "b", "4"]).await;
+        run(&state, &mut client, &["HINCRBY", "h", "c", "1"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "h", "FIELDS", "2", "b", "c"]).await,
+            "*2\r\n:-1\r\n:100\r\n"
+        );
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // Reads skip the expired field, HLEN counts it until it's deleted as
+        // Redis does
+        assert_eq!(
+            run(&state, &mut client, &["HGET", "h", "a"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["HLEN", "h"]).await, ":3\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["HKEYS", "h"]).await,
+            "*2\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HEXISTS", "h", "a"]).await,
+            ":0\r\n"
+        );
+
+        // Writes find it deleted
+        assert_eq!(
+            run(&state, &mut client, &["HSET", "h", "a", "5"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "h", "FIELDS", "1", "a"]).await,
+            "*1\r\n:-1\r\n"
+        );
+
+        // The hash goes once all its fields expired
+        run(
+            &state,
+            &mut client,
+            &["HPEXPIRE", "h", "10", "FIELDS", "3", "a", "b", "c"],
+        )
+        .await;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(run(&state, &mut client, &["EXISTS", "h"]).await, ":0\r\n");
+        assert_eq!(run(&state, &mut client, &["HGETALL", "h"]).await, "*0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["HSET", "h", "a", "1"]).await,
+            ":1\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hgetex() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["HSET", "h", "a", "1", "b", "2"]).await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HGETEX", "h", "FIELDS", "2", "a", "none"]
+            )
+            .await,
+            "*2\r\n$1\r\n1\r\n$-1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HGETEX", "h", "EX", "100", "FIELDS", "1", "a"]
+            )
+            .await,
+            "*1\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "h", "FIELDS", "2", "a", "b"]).await,
+            "*2\r\n:100\r\n:-1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HGETEX", "h", "PERSIST", "FIELDS", "1", "a"]
+            )
+            .await,
+            "*1\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "h", "FIELDS", "1", "a"]).await,
+            "*1\r\n:-1\r\n"
+        );
+
+        // An expiry in the past deletes the fields after reading them
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HGETEX", "h", "PXAT", "1", "FIELDS", "2", "a", "b"]
+            )
+            .await,
+            "*2\r\n$1\r\n1\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "h"]).await, ":0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["HGETEX", "h", "FIELDS", "1", "a"]).await,
+            "*1\r\n$-1\r\n"
+        );
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HGETEX", "h", "KEEPTTL", "FIELDS", "1", "a"]
+            )
+            .await,
+            "ERR syntax error"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HGETEX", "h", "EX", "0", "FIELDS", "1", "a"]
+            )
+            .await,
+            "ERR invalid expire time in 'hgetex' command"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hsetex() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "EX", "100", "FIELDS", "2", "a", "1", "b", "2"]
+            )
+            .await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "h", "FIELDS", "2", "a", "b"]).await,
+            "*2\r\n:100\r\n:100\r\n"
+        );
+
+        // FNX only sets fields when none exists, FXX when they all do
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "FNX", "FIELDS", "2", "a", "3", "c", "3"]
+            )
+            .await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "FXX", "FIELDS", "2", "a", "3", "c", "3"]
+            )
+            .await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "FXX", "KEEPTTL", "FIELDS", "1", "a", "3"]
+            )
+            .await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "FIELDS", "1", "b", "4"]
+            )
+            .await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HTTL", "h", "FIELDS", "2", "a", "b"]).await,
+            "*2\r\n:100\r\n:-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["HMGET", "h", "a", "b"]).await,
+            "*2\r\n$1\r\n3\r\n$1\r\n4\r\n"
+        );
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "PERSIST", "FIELDS", "1", "a", "1"]
+            )
+            .await,
+            "ERR syntax error"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["HSETEX", "h", "FIELDS", "2", "a", "1"]
+            )
+            .await,
+            "ERR The `numfields` parameter must match the number of arguments"
+        );
+    }
+
+    // Runs a command, returns what it logs to the AOF
+    async fn propagated_by(
+        state: &State,
+        client: &mut Client,
+        args: &[&str],
+    ) -> Vec<(usize, Vec<Bytes>)> {
+        let command = argv(args);
+        let spec = check_command(&command).unwrap();
+        let mut propagated = Vec::new();
+        execute(spec, command, state, client, &mut propagated)
+            .await
+            .unwrap();
+        propagated
+    }
+
+    #[tokio::test]
+    async fn test_hexpire_logs_the_applied_time() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["HSET", "h", "a", "1", "b", "2"]).await;
+        run(
+            &state,
+            &mut client,
+            &["HEXPIRE", "h", "100", "FIELDS", "1", "b"],
+        )
+        .await;
+
+        // Only the fields the expiry applied to are logged
+        let propagated = propagated_by(
+            &state,
+            &mut client,
+            &["HEXPIRE", "h", "10", "NX", "FIELDS", "3", "a", "b", "none"],
+        )
+        .await;
+        let expiretime = run(
+            &state,
+            &mut client,
+            &["HPEXPIRETIME", "h", "FIELDS", "1", "a"],
+        )
+        .await;
+        let expires_at = expiretime.trim_start_matches("*1\r\n:").trim_end();
+        assert_eq!(
+            propagated,
+            vec![(
+                0,
+                argv(&["HPEXPIREAT", "h", expires_at, "FIELDS", "1", "a"])
+            )]
+        );
+
+        let propagated = propagated_by(
+            &state,
+            &mut client,
+            &["HPEXPIREAT", "h", "5000", "FIELDS", "1", "none"],
+        )
+        .await;
+        assert!(propagated.is_empty());
+    }
+
+    #[test]
+    fn test_propagate_field_expiry() {
+        assert_eq!(
+            propagate_hgetex(
+                &argv(&["HGETEX", "h", "PERSIST", "FIELDS", "2", "a", "b"]),
+                &ZystResponse::Array(vec![
+                    ZystResponse::BulkString(Bytes::from("1")),
+                    ZystResponse::Nil,
+                ])
+            ),
+            argv(&["HPERSIST", "h", "FIELDS", "1", "a"])
+        );
+        assert_eq!(
+            propagate_hgetex(
+                &argv(&["HGETEX", "h", "FIELDS", "1", "a"]),
+                &ZystResponse::Array(vec![ZystResponse::BulkString(Bytes::from("1"))])
+            ),
+            Vec::<Bytes>::new()
+        );
+
+        assert_eq!(
+            propagate_hsetex(
+                &argv(&["HSETEX", "h", "FNX", "PXAT", "5000", "FIELDS", "1", "a", "1"]),
+                &ZystResponse::Int(1)
+            ),
+            argv(&["HSETEX", "h", "PXAT", "5000", "FIELDS", "1", "a", "1"])
+        );
+        assert_eq!(
+            propagate_hsetex(
+                &argv(&["HSETEX", "h", "FNX", "FIELDS", "1", "a", "1"]),
+                &ZystResponse::Int(0)
+            ),
+            Vec::<Bytes>::new()
+        );
     }
 }
@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use zyst::commands::introspection::*;
+    use zyst::commands::registry::{Arg, ArgType, COMMANDS};
+    use zyst::response::ZystResponse;
+    use zyst::types::*;
+
+    fn command(name: &'static str, args: &[&str]) -> Command {
+        Command {
+            name,
+            args: CommandArgs::MultipleKeys(
+                args.iter().map(|a| Bytes::from(a.to_string())).collect(),
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_count() {
+        let result = command_count().await.unwrap();
+        assert_eq!(result.to_string(), format!(":{}\r\n", COMMANDS.len()));
+    }
+
+    #[tokio::test]
+    async fn test_command_info() {
+        let result = command_info(command("command|info", &["GET", "nope", "command|count"]))
+            .await
+            .unwrap();
+
+        let ZystResponse::Array(entries) = result else {
+            panic!("Expected an array");
+        };
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].to_string().starts_with(
+            "*10\r\n$3\r\nget\r\n:2\r\n*2\r\n+readonly\r\n+fast\r\n:1\r\n:1\r\n:1\r\n"
+        ));
+        assert_eq!(entries[1].to_string(), "$-1\r\n");
+        assert!(entries[2]
+            .to_string()
+            .starts_with("*10\r\n$13\r\ncommand|count\r\n:2\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_command_info_movable_keys() {
+        let result = command_info(command("command|info", &["BLMPOP"]))
+            .await
+            .unwrap()
+            .to_string();
+
+        assert!(result.starts_with(
+            "*1\r\n*10\r\n$6\r\nblmpop\r\n:-5\r\n\
+             *3\r\n+write\r\n+blocking\r\n+movablekeys\r\n:0\r\n:0\r\n:0\r\n"
+        ));
+        assert!(result.contains(
+            "$5\r\nindex\r\n:2\r\n$9\r\nfind_keys\r\n*4\r\n$4\r\ntype\r\n$6\r\nkeynum\r\n"
+        ));
+    }
+
+    // The key arguments of COMMAND DOCS point at the key spec of COMMAND INFO
+    #[test]
+    fn test_key_arguments_have_a_key_spec() {
+        fn has_key(args: &[Arg]) -> bool {
+            args.iter()
+                .any(|arg| matches!(arg.kind, ArgType::Key) || has_key(arg.arguments))
+        }
+
+        let specs = COMMANDS
+            .iter()
+            .flat_map(|spec| std::iter::once(spec).chain(spec.subcommands));
+        for spec in specs {
+            if has_key(spec.arguments) {
+                assert!(
+                    spec.first_key > 0 || spec.has_movable_keys(),
+                    "{}",
+                    spec.name
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_docs() {
+        let result = command_docs(command("command|docs", &["lpop", "nope"]))
+            .await
+            .unwrap();
+
+        let ZystResponse::Map(entries) = result else {
+            panic!("Expected a map");
+        };
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0.to_string(), "$4\r\nlpop\r\n");
+
+        let docs = entries[0].1.to_string();
+        assert!(docs.contains("$5\r\ngroup\r\n$4\r\nlist\r\n"));
+        assert!(docs.contains("$5\r\ncount\r\n$4\r\ntype\r\n$7\r\ninteger\r\n"));
+        assert!(docs.contains("+optional\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_command_list_filterby() {
+        let all = command_list(command("command|list", &[])).await.unwrap();
+        assert!(all.to_string().contains("$12\r\ncommand|info\r\n"));
+
+        let result = command_list(command("command|list", &["FILTERBY", "PATTERN", "h*l*"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.to_string(),
+            "*10\r\n$7\r\nhgetall\r\n$4\r\nhdel\r\n$4\r\nhlen\r\n$5\r\nhvals\r\n\
+             $7\r\nhstrlen\r\n$12\r\nhincrbyfloat\r\n$10\r\nhrandfield\r\n$4\r\nhttl\r\n\
+             $5\r\nhpttl\r\n$5\r\nhello\r\n"
+        );
+
+        let result = command_list(command("command|list", &["filterby", "aclcat", "set"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.to_string(),
+            "*17\r\n$4\r\nsadd\r\n$8\r\nsmembers\r\n$4\r\nsrem\r\n\
+             $9\r\nsismember\r\n$10\r\nsmismember\r\n$5\r\nscard\r\n$4\r\nspop\r\n\
+             $11\r\nsrandmember\r\n$5\r\nsmove\r\n$6\r\nsinter\r\n\
+             $11\r\nsinterstore\r\n$6\r\nsunion\r\n$11\r\nsunionstore\r\n\
+             $5\r\nsdiff\r\n$10\r\nsdiffstore\r\n$10\r\nsintercard\r\n\
+             $5\r\nsscan\r\n"
+        );
+
+        let result = command_list(command("command|list", &["FILTERBY", "MODULE", "json"]))
+            .await
+            .unwrap();
+        assert_eq!(result.to_string(), "*0\r\n");
+
+        assert!(
+            command_list(command("command|list", &["FILTERBY", "PATTERN"]))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_getkeys() {
+        let result = command_getkeys(command("command|getkeys", &["EXISTS", "a", "b"]))
+            .await
+            .unwrap();
+        assert_eq!(result.to_string(), "*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        // The keys follow numkeys
+        let result = command_getkeys(command(
+            "command|getkeys",
+            &["BLMPOP", "0", "2", "a", "b", "LEFT"],
+        ))
+        .await
+        .unwrap();
+        assert_eq!(result.to_string(), "*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let err = command_getkeys(command(
+            "command|getkeys",
+            &["BLMPOP", "0", "3", "a", "LEFT"],
+        ))
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "ERR The command has no key arguments");
+
+        let result = command_getkeys(command("command|getkeys", &["LMPOP", "1", "a", "LEFT"]))
+            .await
+            .unwrap();
+        assert_eq!(result.to_string(), "*1\r\n$1\r\na\r\n");
+
+        let result = command_getkeys(command(
+            "command|getkeys",
+            &["SINTERCARD", "2", "a", "b", "LIMIT", "1"],
+        ))
+        .await
+        .unwrap();
+        assert_eq!(result.to_string(), "*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let err = command_getkeys(command("command|getkeys", &["PING"]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "ERR The command has no key arguments");
+
+        let err = command_getkeys(command("command|getkeys", &["HGET", "h"]))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR Invalid number of arguments specified for command"
+        );
+
+        let err = command_getkeys(command("command|getkeys", &["NOPE", "a"]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "ERR Invalid command specified");
+    }
+}
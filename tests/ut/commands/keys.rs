@@ -1,23 +1,32 @@
 #[cfg(test)]
 mod tests {
-    use indexmap::IndexMap;
+    use crate::ut::commands::argv;
+    use bytes::Bytes;
     use std::sync::Arc;
     use tokio::sync::RwLock;
+    use zyst::client::Client;
+    use zyst::commands::build::*;
     use zyst::commands::keys::*;
+    use zyst::errors::ZystError;
+    use zyst::keyspace::Keyspace;
+    use zyst::parser::check_command;
+    use zyst::process::{execute, process_command};
+    use zyst::response::ZystResponse;
+    use zyst::state::State;
     use zyst::types::*;
 
     async fn setup_db() -> Db {
-        Arc::new(RwLock::new(IndexMap::new()))
+        Arc::new(RwLock::new(Keyspace::new()))
     }
 
     #[tokio::test]
     async fn test_set_key() {
         let db = setup_db().await;
         let command = Command {
-            command_type: CommandType::SET,
+            name: "set",
             args: CommandArgs::KeyWithValue {
-                key: "my_key".to_string(),
-                value: "value".to_string(),
+                key: Bytes::from("my_key"),
+                value: Bytes::from("value"),
             },
         };
 
@@ -25,13 +34,13 @@ mod tests {
         assert_eq!(result, "+OK\r\n");
 
         let db_read = db.read().await;
-        assert!(db_read.contains_key("my_key"));
+        assert!(db_read.contains_key("my_key".as_bytes()));
     }
 
     #[tokio::test]
     async fn test_delete_key() {
         let db = setup_db().await;
-        let key_name = "key_to_delete".to_string();
+        let key_name = Bytes::from("key_to_delete");
 
         {
             let mut db_write = db.write().await;
@@ -39,19 +48,19 @@ mod tests {
                 key_name.clone(),
                 DbValue::StringKey(Key {
                     name: key_name.clone(),
-                    data: Some("value".to_string()),
+                    data: Some(Bytes::from("value")),
                     expires_at: None,
                 }),
             );
         }
 
         let command = Command {
-            command_type: CommandType::DEL,
+            name: "del",
             args: CommandArgs::SingleKey(key_name.clone()),
         };
 
         let result = delete_key(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 1\r\n");
+        assert_eq!(result, ":1\r\n");
 
         let db_read = db.read().await;
         assert!(!db_read.contains_key(&key_name));
@@ -61,18 +70,18 @@ mod tests {
     async fn test_incr_new_key() {
         let db = setup_db().await;
         let command = Command {
-            command_type: CommandType::INCR,
-            args: CommandArgs::SingleKey("counter".to_string()),
+            name: "incr",
+            args: CommandArgs::SingleKey(Bytes::from("counter")),
         };
 
         let result = incr(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 1\r\n");
+        assert_eq!(result, ":1\r\n");
     }
 
     #[tokio::test]
     async fn test_incr_existing_key() {
         let db = setup_db().await;
-        let key_name = "counter".to_string();
+        let key_name = Bytes::from("counter");
 
         {
             let mut db_write = db.write().await;
@@ -80,37 +89,37 @@ mod tests {
                 key_name.clone(),
                 DbValue::StringKey(Key {
                     name: key_name.clone(),
-                    data: Some("5".to_string()),
+                    data: Some(Bytes::from("5")),
                     expires_at: None,
                 }),
             );
         }
 
         let command = Command {
-            command_type: CommandType::INCR,
+            name: "incr",
             args: CommandArgs::SingleKey(key_name),
         };
 
         let result = incr(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 6\r\n");
+        assert_eq!(result, ":6\r\n");
     }
 
     #[tokio::test]
     async fn test_decr_new_key() {
         let db = setup_db().await;
         let command = Command {
-            command_type: CommandType::DECR,
-            args: CommandArgs::SingleKey("counter".to_string()),
+            name: "decr",
+            args: CommandArgs::SingleKey(Bytes::from("counter")),
         };
 
         let result = decr(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) -1\r\n");
+        assert_eq!(result, ":-1\r\n");
     }
 
     #[tokio::test]
     async fn test_incrby() {
         let db = setup_db().await;
-        let key_name = "counter".to_string();
+        let key_name = Bytes::from("counter");
 
         {
             let mut db_write = db.write().await;
@@ -118,22 +127,22 @@ mod tests {
                 key_name.clone(),
                 DbValue::StringKey(Key {
                     name: key_name.clone(),
-                    data: Some("10".to_string()),
+                    data: Some(Bytes::from("10")),
                     expires_at: None,
                 }),
             );
         }
 
         let command = Command {
-            command_type: CommandType::INCRBY,
+            name: "incrby",
             args: CommandArgs::KeyWithValue {
                 key: key_name,
-                value: "5".to_string(),
+                value: Bytes::from("5"),
             },
         };
 
         let result = incrby(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 15\r\n");
+        assert_eq!(result, ":15\r\n");
     }
 
     #[tokio::test]
@@ -143,26 +152,26 @@ mod tests {
         {
             let mut db_write = db.write().await;
             db_write.insert(
-                "foo".to_string(),
+                Bytes::from("foo"),
                 DbValue::StringKey(Key {
-                    name: "foo".to_string(),
-                    data: Some("bar".to_string()),
+                    name: Bytes::from("foo"),
+                    data: Some(Bytes::from("bar")),
                     expires_at: None,
                 }),
             );
             db_write.insert(
-                "foobar".to_string(),
+                Bytes::from("foobar"),
                 DbValue::StringKey(Key {
-                    name: "foobar".to_string(),
-                    data: Some("baz".to_string()),
+                    name: Bytes::from("foobar"),
+                    data: Some(Bytes::from("baz")),
                     expires_at: None,
                 }),
             );
         }
 
         let command = Command {
-            command_type: CommandType::KEYS,
-            args: CommandArgs::SingleKey("foo*".to_string()),
+            name: "keys",
+            args: CommandArgs::SingleKey(Bytes::from("foo*")),
         };
 
         let result = get_keys(&db, command).await.unwrap().to_string();
@@ -176,21 +185,578 @@ mod tests {
         {
             let mut db_write = db.write().await;
             db_write.insert(
-                "key1".to_string(),
+                Bytes::from("key1"),
                 DbValue::StringKey(Key {
-                    name: "key1".to_string(),
-                    data: Some("val1".to_string()),
+                    name: Bytes::from("key1"),
+                    data: Some(Bytes::from("val1")),
                     expires_at: None,
                 }),
             );
         }
 
         let command = Command {
-            command_type: CommandType::EXISTS,
-            args: CommandArgs::MultipleKeys(vec!["key1".to_string(), "key2".to_string()]),
+            name: "exists",
+            args: CommandArgs::MultipleKeys(vec![Bytes::from("key1"), Bytes::from("key2")]),
         };
 
         let result = exists(&db, command).await.unwrap().to_string();
-        assert_eq!(result, "+(integer) 1\r\n");
+        assert_eq!(result, ":1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_set_get_binary_value() {
+        let db = setup_db().await;
+        let key = Bytes::from_static(b"session:\xfe\x00");
+        let value = Bytes::from_static(b"\x08\x96\x01\r\n\xff\xc3\x28");
+
+        let command = Command {
+            name: "set",
+            args: CommandArgs::KeyWithValue {
+                key: key.clone(),
+                value: value.clone(),
+            },
+        };
+        set_key(&db, command).await.unwrap();
+
+        let command = Command {
+            name: "get",
+            args: CommandArgs::SingleKey(key.clone()),
+        };
+
+        match get_key(&db, command).await.unwrap() {
+            ZystResponse::BulkString(stored) => assert_eq!(stored, value),
+            other => panic!("Expected a bulk string, got {other:?}"),
+        }
+
+        let command = Command {
+            name: "keys",
+            args: CommandArgs::SingleKey(Bytes::from_static(b"session:*")),
+        };
+
+        match get_keys(&db, command).await.unwrap() {
+            ZystResponse::List(keys) => assert_eq!(keys, vec![key]),
+            other => panic!("Expected a list, got {other:?}"),
+        }
+    }
+
+    async fn set(db: &Db, args: &[&str]) -> String {
+        let command = Command {
+            name: "set",
+            args: build_set_command(&argv(args)).unwrap(),
+        };
+        set_key(db, command).await.unwrap().to_string()
+    }
+
+    async fn ttl_of(db: &Db, key: &str) -> i64 {
+        match ttl(db, single_key("ttl", key)).await.unwrap() {
+            ZystResponse::Int(ttl) => ttl,
+            response => panic!("unexpected reply {response:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_nx_xx() {
+        let db = setup_db().await;
+
+        assert_eq!(set(&db, &["k", "v1", "XX"]).await, "$-1\r\n");
+        assert_eq!(set(&db, &["k", "v1", "NX", "EX", "60"]).await, "+OK\r\n");
+        assert_eq!(set(&db, &["k", "v2", "nx"]).await, "$-1\r\n");
+        assert_eq!(ttl_of(&db, "k").await, 60);
+
+        // Without KEEPTTL the TTL is discarded
+        assert_eq!(set(&db, &["k", "v2", "XX"]).await, "+OK\r\n");
+        assert_eq!(ttl_of(&db, "k").await, -1);
+    }
+
+    #[tokio::test]
+    async fn test_set_get_keepttl() {
+        let db = setup_db().await;
+
+        assert_eq!(
+            set(&db, &["k", "v1", "GET", "PX", "30000"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            set(&db, &["k", "v2", "KEEPTTL", "GET"]).await,
+            "$2\r\nv1\r\n"
+        );
+        assert_eq!(ttl_of(&db, "k").await, 30);
+
+        // An expired key is treated as missing
+        set(&db, &["old", "v", "PXAT", "1"]).await;
+        assert_eq!(set(&db, &["old", "v2", "XX", "GET"]).await, "$-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_set_ifeq() {
+        let db = setup_db().await;
+
+        assert_eq!(set(&db, &["k", "v1", "IFEQ", "v1"]).await, "$-1\r\n");
+        set(&db, &["k", "v1"]).await;
+        assert_eq!(set(&db, &["k", "v2", "IFEQ", "nope"]).await, "$-1\r\n");
+        assert_eq!(
+            set(&db, &["k", "v2", "IFEQ", "v1", "GET"]).await,
+            "$2\r\nv1\r\n"
+        );
+
+        {
+            let mut db_write = db.write().await;
+            db_write.insert(
+                Bytes::from("list"),
+                DbValue::ListKey(KeyList::new(Bytes::from("list"), Default::default(), None)),
+            );
+        }
+
+        let command = Command {
+            name: "set",
+            args: build_set_command(&argv(&["list", "v", "IFEQ", "v"])).unwrap(),
+        };
+        assert!(matches!(
+            set_key(&db, command).await,
+            Err(ZystError::WrongType)
+        ));
+    }
+
+    #[test]
+    fn test_set_option_errors() {
+        let cases = [
+            (vec!["k", "v", "NX", "XX"], "ERR syntax error"),
+            (vec!["k", "v", "EX", "10", "PX", "10"], "ERR syntax error"),
+            (vec!["k", "v", "KEEPTTL", "EXAT", "10"], "ERR syntax error"),
+            (vec!["k", "v", "IFEQ"], "ERR syntax error"),
+            (vec!["k", "v", "EX"], "ERR syntax error"),
+            (
+                vec!["k", "v", "EX", "0"],
+                "ERR invalid expire time in 'set' command",
+            ),
+            (
+                vec!["k", "v", "PX", "-5"],
+                "ERR invalid expire time in 'set' command",
+            ),
+            (
+                vec!["k", "v", "EX", "9223372036854775807"],
+                "ERR invalid expire time in 'set' command",
+            ),
+            (
+                vec!["k", "v", "EX", "ten"],
+                "ERR value is not an integer or out of range",
+            ),
+        ];
+
+        for (args, expected) in cases {
+            let err = build_set_command(&argv(&args)).unwrap_err();
+            assert_eq!(err.to_string(), expected, "{args:?}");
+        }
+
+        let err = build_setex_command(&argv(&["k", "0", "v"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR invalid expire time in 'setex' command"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_setnx_getset() {
+        let db = setup_db().await;
+
+        let command = Command {
+            name: "setnx",
+            args: build_setnx_command(&argv(&["k", "v1"])).unwrap(),
+        };
+        assert_eq!(
+            setnx(&db, command.clone()).await.unwrap().to_string(),
+            ":1\r\n"
+        );
+        assert_eq!(setnx(&db, command).await.unwrap().to_string(), ":0\r\n");
+
+        let command = Command {
+            name: "setex",
+            args: build_setex_command(&argv(&["k", "100", "v2"])).unwrap(),
+        };
+        assert_eq!(set_key(&db, command).await.unwrap().to_string(), "+OK\r\n");
+        assert_eq!(ttl_of(&db, "k").await, 100);
+
+        let command = Command {
+            name: "getset",
+            args: build_getset_command(&argv(&["k", "v3"])).unwrap(),
+        };
+        assert_eq!(
+            set_key(&db, command).await.unwrap().to_string(),
+            "$2\r\nv2\r\n"
+        );
+        assert_eq!(ttl_of(&db, "k").await, -1);
+    }
+
+    #[test]
+    fn test_propagate_set_uses_absolute_time() {
+        let response = ZystResponse::Ok;
+
+        let propagated = propagate_set(&argv(&["SET", "k", "EX", "NX", "EX", "10"]), &response);
+        assert_eq!(propagated.len(), 6);
+        assert_eq!(&propagated[..4], &argv(&["SET", "k", "EX", "NX"])[..]);
+        assert_eq!(propagated[4], Bytes::from("PXAT"));
+
+        let expires_at: i64 = std::str::from_utf8(&propagated[5])
+            .unwrap()
+            .parse()
+            .unwrap();
+        let remaining = expires_at - zyst::keys::current_timestamp_ms();
+        assert!(remaining > 9_000 && remaining <= 10_000);
+
+        let propagated =
+            propagate_set(&argv(&["SET", "k", "v", "IFEQ", "PX", "GET"]), &response);
+        assert_eq!(propagated, argv(&["SET", "k", "v", "IFEQ", "PX", "GET"]));
+
+        let propagated = propagate_setex(&argv(&["PSETEX", "k", "500", "v"]), &response);
+        assert_eq!(&propagated[..4], &argv(&["SET", "k", "v", "PXAT"])[..]);
+    }
+
+    #[test]
+    fn test_propagate_skips_conditions_that_failed() {
+        let set = argv(&["SET", "k", "v", "NX"]);
+        assert!(propagate_set(&set, &ZystResponse::Nil).is_empty());
+
+        // With GET, a nil reply is the previous value and says nothing of the write
+        let set = argv(&["SET", "k", "v", "NX", "GET"]);
+        assert_eq!(propagate_set(&set, &ZystResponse::Nil), set);
+
+        let setnx = argv(&["SETNX", "k", "v"]);
+        assert!(propagate_if_applied(&setnx, &ZystResponse::Int(0)).is_empty());
+        assert_eq!(propagate_if_applied(&setnx, &ZystResponse::Int(1)), setnx);
+    }
+
+    async fn expire_with(db: &Db, name: &str, args: &[&str]) -> String {
+        run(db, &[&[name], args].concat()).await
+    }
+
+    fn single_key(name: &'static str, key: &str) -> Command {
+        Command {
+            name,
+            args: CommandArgs::SingleKey(Bytes::from(key.to_string())),
+        }
+    }
+
+    async fn insert_list(db: &Db, key: &str) {
+        let mut db_write = db.write().await;
+        db_write.insert(
+            Bytes::from(key.to_string()),
+            DbValue::ListKey(KeyList::new(
+                Bytes::from(key.to_string()),
+                vec![Bytes::from("a")].into(),
+                None,
+            )),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expire_any_type() {
+        let db = setup_db().await;
+        insert_list(&db, "list").await;
+
+        assert_eq!(
+            expire_with(&db, "expire", &["missing", "10"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            expire_with(&db, "pexpire", &["list", "1500"]).await,
+            ":1\r\n"
+        );
+
+        let response = ttl(&db, single_key("ttl", "list")).await.unwrap();
+        assert_eq!(response.to_string(), ":2\r\n");
+
+        let response = pttl(&db, single_key("pttl", "list")).await.unwrap();
+        assert!(matches!(response, ZystResponse::Int(ms) if ms > 1400 && ms <= 1500));
+
+        let response = ttl(&db, single_key("ttl", "missing")).await.unwrap();
+        assert_eq!(response.to_string(), ":-2\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_expire_conditions() {
+        let db = setup_db().await;
+        set(&db, &["k", "v"]).await;
+
+        // No TTL counts as an infinite one
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "100", "XX"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "100", "GT"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "100", "LT"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "50", "NX"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "50", "GT"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "200", "gt", "xx"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(ttl_of(&db, "k").await, 200);
+
+        let response = persist(&db, single_key("persist", "k")).await.unwrap();
+        assert_eq!(response.to_string(), ":1\r\n");
+        let response = persist(&db, single_key("persist", "k")).await.unwrap();
+        assert_eq!(response.to_string(), ":0\r\n");
+
+        // XX still requires an existing TTL when combined with LT
+        assert_eq!(
+            expire_with(&db, "expire", &["k", "10", "XX", "LT"]).await,
+            ":0\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expireat_and_expiretime() {
+        let db = setup_db().await;
+        set(&db, &["k", "v"]).await;
+
+        let response = expiretime(&db, single_key("expiretime", "k"))
+            .await
+            .unwrap();
+        assert_eq!(response.to_string(), ":-1\r\n");
+
+        assert_eq!(
+            expire_with(&db, "expireat", &["k", "33177117420"]).await,
+            ":1\r\n"
+        );
+        let response = expiretime(&db, single_key("expiretime", "k"))
+            .await
+            .unwrap();
+        assert_eq!(response.to_string(), ":33177117420\r\n");
+        let response = pexpiretime(&db, single_key("pexpiretime", "k"))
+            .await
+            .unwrap();
+        assert_eq!(response.to_string(), ":33177117420000\r\n");
+
+        // A time in the past deletes the key
+        assert_eq!(
+            expire_with(&db, "pexpireat", &["k", "1000"]).await,
+            ":1\r\n"
+        );
+        assert!(db.read().await.is_empty());
+
+        set(&db, &["k", "v"]).await;
+        assert_eq!(expire_with(&db, "expire", &["k", "-1"]).await, ":1\r\n");
+        assert!(db.read().await.is_empty());
+    }
+
+    #[test]
+    fn test_expire_option_errors() {
+        let cases = [
+            (
+                vec!["k", "10", "NX", "XX"],
+                "ERR NX and XX, GT or LT options at the same time are not compatible",
+            ),
+            (
+                vec!["k", "10", "GT", "LT"],
+                "ERR GT and LT options at the same time are not compatible",
+            ),
+            (vec!["k", "10", "FOO"], "ERR Unsupported option FOO"),
+            (
+                vec!["k", "ten"],
+                "ERR value is not an integer or out of range",
+            ),
+            (
+                vec!["k", "9223372036854775807"],
+                "ERR invalid expire time in 'expire' command",
+            ),
+        ];
+
+        for (args, expected) in cases {
+            let err = build_expire_command(&argv(&args)).unwrap_err();
+            assert_eq!(err.to_string(), expected, "{args:?}");
+        }
+
+        assert!(build_expire_command(&argv(&["k", "10", "NX", "nx"])).is_ok());
+    }
+
+    // Runs a command, returns what it logs to the AOF
+    async fn propagated_by(db: &Db, args: &[&str]) -> Vec<(usize, Vec<Bytes>)> {
+        let state = State::with_databases(vec![db.clone()]);
+        let command = argv(args);
+        let spec = check_command(&command).unwrap();
+        let mut propagated = Vec::new();
+        execute(spec, command, &state, &mut Client::new(), &mut propagated)
+            .await
+            .unwrap();
+        propagated
+    }
+
+    #[tokio::test]
+    async fn test_expire_logs_the_applied_time() {
+        let db = setup_db().await;
+        set(&db, &["k", "v"]).await;
+
+        let propagated = propagated_by(&db, &["EXPIRE", "k", "10", "NX"]).await;
+        let pexpiretime = run(&db, &["PEXPIRETIME", "k"]).await;
+        let expires_at = pexpiretime.trim_start_matches(':').trim_end();
+        assert_eq!(propagated, vec![(0, argv(&["PEXPIREAT", "k", expires_at]))]);
+
+        let propagated = propagated_by(&db, &["EXPIREAT", "k", "100"]).await;
+        assert_eq!(propagated, vec![(0, argv(&["PEXPIREAT", "k", "100000"]))]);
+
+        // Expiries that weren't applied aren't logged
+        assert!(propagated_by(&db, &["EXPIRE", "k", "10"]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_renamenx_logs_only_a_rename() {
+        let db = setup_db().await;
+        set(&db, &["a", "1"]).await;
+        set(&db, &["b", "2"]).await;
+
+        assert!(propagated_by(&db, &["RENAMENX", "a", "b"]).await.is_empty());
+        assert_eq!(
+            propagated_by(&db, &["RENAMENX", "a", "c"]).await,
+            vec![(0, argv(&["RENAMENX", "a", "c"]))]
+        );
+    }
+
+    async fn run(db: &Db, args: &[&str]) -> String {
+        let state = State::with_databases(vec![db.clone()]);
+        process_command(argv(args), &state, &mut Client::new(), true)
+            .await
+            .map_or_else(|e| e.to_string(), |response| response.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_type() {
+        let db = setup_db().await;
+        run(&db, &["SET", "string", "v"]).await;
+        run(&db, &["RPUSH", "list", "a"]).await;
+        run(&db, &["SADD", "set", "a"]).await;
+        run(&db, &["HSET", "hash", "f", "v"]).await;
+
+        for (key, expected) in [
+            ("string", "+string\r\n"),
+            ("list", "+list\r\n"),
+            ("set", "+set\r\n"),
+            ("hash", "+hash\r\n"),
+            ("missing", "+none\r\n"),
+        ] {
+            assert_eq!(run(&db, &["TYPE", key]).await, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rename() {
+        let db = setup_db().await;
+        run(&db, &["RPUSH", "list", "a", "b"]).await;
+        run(&db, &["EXPIRE", "list", "100"]).await;
+        run(&db, &["SET", "target", "v"]).await;
+
+        assert_eq!(run(&db, &["RENAME", "list", "target"]).await, "+OK\r\n");
+        assert_eq!(run(&db, &["TYPE", "target"]).await, "+list\r\n");
+        assert_eq!(run(&db, &["TTL", "target"]).await, ":100\r\n");
+        assert_eq!(run(&db, &["EXISTS", "list"]).await, ":0\r\n");
+        assert!(matches!(
+            db.read().await.get(b"target".as_slice()),
+            Some(DbValue::ListKey(key)) if key.name == "target"
+        ));
+
+        assert_eq!(run(&db, &["RENAME", "list", "x"]).await, "ERR no such key");
+        assert_eq!(run(&db, &["RENAME", "target", "target"]).await, "+OK\r\n");
+
+        run(&db, &["SET", "other", "v"]).await;
+        assert_eq!(run(&db, &["RENAMENX", "target", "other"]).await, ":0\r\n");
+        assert_eq!(run(&db, &["RENAMENX", "target", "target"]).await, ":0\r\n");
+        assert_eq!(run(&db, &["RENAMENX", "target", "new"]).await, ":1\r\n");
+        assert_eq!(
+            run(&db, &["LRANGE", "new", "0", "-1"]).await,
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy() {
+        let db = setup_db().await;
+        run(&db, &["HSET", "template", "f", "v"]).await;
+        run(&db, &["EXPIRE", "template", "100"]).await;
+        run(&db, &["SET", "taken", "v"]).await;
+
+        assert_eq!(run(&db, &["COPY", "template", "copy"]).await, ":1\r\n");
+        assert_eq!(run(&db, &["HGET", "copy", "f"]).await, "$1\r\nv\r\n");
+        assert_eq!(run(&db, &["TTL", "copy"]).await, ":100\r\n");
+
+        // The copy doesn't share data with the source
+        run(&db, &["HSET", "copy", "g", "w"]).await;
+        assert_eq!(run(&db, &["HGET", "template", "g"]).await, "$-1\r\n");
+
+        assert_eq!(run(&db, &["COPY", "template", "taken"]).await, ":0\r\n");
+        assert_eq!(
+            run(&db, &["COPY", "template", "taken", "replace"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(run(&db, &["TYPE", "taken"]).await, "+hash\r\n");
+        assert_eq!(run(&db, &["COPY", "missing", "x"]).await, ":0\r\n");
+
+        assert_eq!(
+            run(&db, &["COPY", "template", "template"]).await,
+            "ERR source and destination objects are the same"
+        );
+        assert_eq!(
+            run(&db, &["COPY", "template", "x", "DB"]).await,
+            "ERR syntax error"
+        );
+        assert_eq!(
+            run(&db, &["COPY", "template", "x", "DB", "-1"]).await,
+            "ERR DB index is out of range"
+        );
+        assert_eq!(
+            run(&db, &["COPY", "template", "x", "DB", "one"]).await,
+            "ERR value is not an integer or out of range"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_randomkey_dbsize_touch() {
+        let db = setup_db().await;
+        assert_eq!(run(&db, &["RANDOMKEY"]).await, "$-1\r\n");
+        assert_eq!(run(&db, &["DBSIZE"]).await, ":0\r\n");
+
+        for key in ["a", "b", "c"] {
+            run(&db, &["SET", key, "v"]).await;
+        }
+
+        assert_eq!(run(&db, &["DBSIZE"]).await, ":3\r\n");
+        assert_eq!(run(&db, &["TOUCH", "a", "b", "missing"]).await, ":2\r\n");
+
+        let key = run(&db, &["RANDOMKEY"]).await;
+        assert!(["$1\r\na\r\n", "$1\r\nb\r\n", "$1\r\nc\r\n"].contains(&key.as_str()));
+
+        // Expired keys are never picked
+        for key in ["a", "b"] {
+            db.write().await.set_expires_at(&Bytes::from(key), Some(1));
+        }
+        for _ in 0..20 {
+            assert_eq!(run(&db, &["RANDOMKEY"]).await, "$1\r\nc\r\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unlink() {
+        let db = setup_db().await;
+        let elements: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+        let mut args = vec!["RPUSH", "big"];
+        args.extend(elements.iter().map(String::as_str));
+        run(&db, &args).await;
+        run(&db, &["SET", "small", "v"]).await;
+
+        assert_eq!(
+            run(&db, &["UNLINK", "big", "small", "missing"]).await,
+            ":2\r\n"
+        );
+        assert!(db.read().await.is_empty());
     }
 }
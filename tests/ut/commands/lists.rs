@@ -0,0 +1,694 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::{argv, run};
+    use bytes::Bytes;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::task::JoinHandle;
+    use zyst::client::Client;
+    use zyst::commands::lists::propagate_blocking_pop;
+    use zyst::response::ZystResponse;
+    use zyst::server::handle_client;
+    use zyst::state::State;
+
+    /// Runs a command from a new client in the background, returns its id
+    /// and the reply to come. Waits a bit so that the command can block
+    /// before the next one.
+    async fn run_blocked(state: &Arc<State>, args: &[&str]) -> (u64, JoinHandle<String>) {
+        let state = state.clone();
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut client = Client::new();
+        let id = client.id;
+
+        let reply = tokio::spawn(async move {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            run(&state, &mut client, &args).await
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        (id, reply)
+    }
+
+    /// Serves connections on a random port, returns its address
+    async fn serve(state: Arc<State>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let _ = handle_client(socket, state, 1024).await;
+                });
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_list_indexes() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["RPUSH", "l", "a", "b", "c"]).await;
+        assert_eq!(run(&state, &mut client, &["LLEN", "l"]).await, ":3\r\n");
+        assert_eq!(run(&state, &mut client, &["LLEN", "none"]).await, ":0\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["LINDEX", "l", "0"]).await,
+            "$1\r\na\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINDEX", "l", "-1"]).await,
+            "$1\r\nc\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINDEX", "l", "3"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINDEX", "l", "-4"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINDEX", "l", "first"]).await,
+            "ERR value is not an integer or out of range"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["LSET", "l", "-2", "B"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LSET", "l", "3", "d"]).await,
+            "ERR index out of range"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LSET", "none", "0", "d"]).await,
+            "ERR no such key"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "l", "-100", "100"]).await,
+            "*3\r\n$1\r\na\r\n$1\r\nB\r\n$1\r\nc\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "l", "2", "1"]).await,
+            "*0\r\n"
+        );
+
+        run(&state, &mut client, &["SET", "s", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["LLEN", "s"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_updates() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["LPUSHX", "l", "a"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "l"]).await, ":0\r\n");
+        run(
+            &state,
+            &mut client,
+            &["RPUSH", "l", "a", "b", "a", "c", "a"],
+        )
+        .await;
+        assert_eq!(
+            run(&state, &mut client, &["LPUSHX", "l", "x", "y"]).await,
+            ":7\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["RPUSHX", "l", "z"]).await,
+            ":8\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["LINSERT", "l", "AFTER", "c", "d"]).await,
+            ":9\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINSERT", "l", "before", "y", "w"]).await,
+            ":10\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINSERT", "l", "BEFORE", "q", "w"]).await,
+            ":-1\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LINSERT", "none", "BEFORE", "q", "w"]
+            )
+            .await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LINSERT", "l", "NEAR", "c", "d"]).await,
+            "ERR syntax error"
+        );
+
+        // The last occurrence of a goes first
+        assert_eq!(
+            run(&state, &mut client, &["LREM", "l", "-1", "a"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LREM", "l", "1", "a"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "l", "0", "-1"]).await,
+            "*8\r\n$1\r\nw\r\n$1\r\ny\r\n$1\r\nx\r\n$1\r\nb\r\n$1\r\na\r\n\
+             $1\r\nc\r\n$1\r\nd\r\n$1\r\nz\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["LTRIM", "l", "1", "-2"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "l", "0", "-1"]).await,
+            "*6\r\n$1\r\ny\r\n$1\r\nx\r\n$1\r\nb\r\n$1\r\na\r\n$1\r\nc\r\n\
+             $1\r\nd\r\n"
+        );
+
+        // Emptied lists are deleted
+        assert_eq!(
+            run(&state, &mut client, &["LTRIM", "l", "6", "10"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "l"]).await, ":0\r\n");
+        run(&state, &mut client, &["RPUSH", "l", "a", "a"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["LREM", "l", "0", "a"]).await,
+            ":2\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "l"]).await, ":0\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_lpos() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(
+            &state,
+            &mut client,
+            &["RPUSH", "l", "a", "b", "c", "1", "2", "3", "c", "c"],
+        )
+        .await;
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c"]).await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "x"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "RANK", "2"]).await,
+            ":6\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "RANK", "-1"]).await,
+            ":7\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "COUNT", "0"]).await,
+            "*3\r\n:2\r\n:6\r\n:7\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LPOS", "l", "c", "RANK", "-2", "COUNT", "2"]
+            )
+            .await,
+            "*2\r\n:6\r\n:2\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LPOS", "l", "c", "COUNT", "0", "MAXLEN", "4"]
+            )
+            .await,
+            "*1\r\n:2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "none", "c", "COUNT", "1"]).await,
+            "*0\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "RANK", "0"]).await,
+            "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second \
+             ... or use negative to start from the end of the list"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "COUNT", "-1"]).await,
+            "ERR COUNT can't be negative"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "MAXLEN", "-1"]).await,
+            "ERR MAXLEN can't be negative"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOS", "l", "c", "COUNT"]).await,
+            "ERR syntax error"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pop_count() {
+        let state = Arc::new(State::new(16));
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["RPUSH", "l", "a", "b", "c"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["LPOP", "l"]).await,
+            "$1\r\na\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LPOP", "l", "1"]).await,
+            "*1\r\n$1\r\nb\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["RPOP", "l", "0"]).await,
+            "*0\r\n"
+        );
+        for count in ["-1", "two"] {
+            assert_eq!(
+                run(&state, &mut client, &["RPOP", "l", count]).await,
+                "ERR value is out of range, must be positive"
+            );
+        }
+        assert_eq!(run(&state, &mut client, &["LLEN", "l"]).await, ":1\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["LPOP", "none", "1"]).await,
+            "*-1\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["LPOP", "none"]).await, "$-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_list_moves_and_multiple_pops() {
+        let state = Arc::new(State::new(16));
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["RPUSH", "pending", "1", "2", "3"]).await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LMOVE", "pending", "active", "LEFT", "RIGHT"]
+            )
+            .await,
+            "$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["RPOPLPUSH", "pending", "active"]).await,
+            "$1\r\n3\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "active", "0", "-1"]).await,
+            "*2\r\n$1\r\n3\r\n$1\r\n1\r\n"
+        );
+
+        // Moving to the same list rotates it
+        assert_eq!(
+            run(&state, &mut client, &["RPOPLPUSH", "active", "active"]).await,
+            "$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "active", "0", "-1"]).await,
+            "*2\r\n$1\r\n1\r\n$1\r\n3\r\n"
+        );
+
+        // The last element moved deletes the source
+        run(
+            &state,
+            &mut client,
+            &["LMOVE", "pending", "active", "RIGHT", "LEFT"],
+        )
+        .await;
+        assert_eq!(
+            run(&state, &mut client, &["EXISTS", "pending"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LMOVE", "pending", "active", "LEFT", "LEFT"]
+            )
+            .await,
+            "$-1\r\n"
+        );
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LMPOP", "2", "pending", "active", "LEFT"]
+            )
+            .await,
+            "*2\r\n$6\r\nactive\r\n*1\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["LMPOP", "1", "active", "RIGHT", "COUNT", "5"]
+            )
+            .await,
+            "*2\r\n$6\r\nactive\r\n*2\r\n$1\r\n3\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LMPOP", "1", "active", "LEFT"]).await,
+            "*-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LMPOP", "0", "active", "LEFT"]).await,
+            "ERR numkeys should be greater than 0"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LMOVE", "a", "b", "LEFT", "UP"]).await,
+            "ERR syntax error"
+        );
+
+        // A move wakes up a client blocked on the destination
+        let (_, worker) = run_blocked(&state, &["BLPOP", "active", "0"]).await;
+        run(&state, &mut client, &["RPUSH", "pending", "job"]).await;
+        run(&state, &mut client, &["RPOPLPUSH", "pending", "active"]).await;
+        assert_eq!(worker.await.unwrap(), "*2\r\n$6\r\nactive\r\n$3\r\njob\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pops_reply_right_away() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["RPUSH", "b", "1", "2", "3"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["BLPOP", "a", "b", "0"]).await,
+            "*2\r\n$1\r\nb\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["BRPOP", "a", "b", "0.5"]).await,
+            "*2\r\n$1\r\nb\r\n$1\r\n3\r\n"
+        );
+
+        run(&state, &mut client, &["RPUSH", "c", "x", "y", "z"]).await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["BLMPOP", "0", "2", "a", "c", "RIGHT", "COUNT", "2"]
+            )
+            .await,
+            "*2\r\n$1\r\nc\r\n*2\r\n$1\r\nz\r\n$1\r\ny\r\n"
+        );
+
+        // Moving to the same list rotates it
+        run(&state, &mut client, &["RPUSH", "r", "1", "2", "3"]).await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["BLMOVE", "r", "r", "LEFT", "RIGHT", "0"]
+            )
+            .await,
+            "$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "r", "0", "-1"]).await,
+            "*3\r\n$1\r\n2\r\n$1\r\n3\r\n$1\r\n1\r\n"
+        );
+
+        // Emptied lists are deleted
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["BLMOVE", "b", "d", "RIGHT", "LEFT", "0"]
+            )
+            .await,
+            "$1\r\n2\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "b"]).await, ":0\r\n");
+
+        run(&state, &mut client, &["SET", "s", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["BLPOP", "a", "s", "0"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["BLMOVE", "d", "s", "LEFT", "LEFT", "0"]
+            )
+            .await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pop_arguments() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["BLPOP", "a", "-1"]).await,
+            "ERR timeout is negative"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["BRPOP", "a", "soon"]).await,
+            "ERR timeout is not a float or out of range"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["BLPOP", "a", "inf"]).await,
+            "ERR timeout is not a float or out of range"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["BLMPOP", "0", "0", "a", "LEFT"]).await,
+            "ERR numkeys should be greater than 0"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["BLMPOP", "0", "2", "a", "LEFT"]).await,
+            "ERR syntax error"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["BLMPOP", "0", "1", "a", "LEFT", "COUNT", "0"]
+            )
+            .await,
+            "ERR count should be greater than 0"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["BLMOVE", "a", "b", "UP", "LEFT", "0"]
+            )
+            .await,
+            "ERR syntax error"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocked_clients_are_served_in_order() {
+        let state = Arc::new(State::new(16));
+        let mut client = Client::new();
+
+        let (_, first) = run_blocked(&state, &["BLPOP", "jobs", "0"]).await;
+        let (_, second) = run_blocked(&state, &["BRPOP", "other", "jobs", "0"]).await;
+        let (_, third) = run_blocked(&state, &["BLMPOP", "0", "1", "jobs", "LEFT"]).await;
+
+        assert_eq!(
+            run(&state, &mut client, &["RPUSH", "jobs", "a", "b"]).await,
+            ":2\r\n"
+        );
+        assert_eq!(first.await.unwrap(), "*2\r\n$4\r\njobs\r\n$1\r\na\r\n");
+        assert_eq!(second.await.unwrap(), "*2\r\n$4\r\njobs\r\n$1\r\nb\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["EXISTS", "jobs"]).await,
+            ":0\r\n"
+        );
+
+        // Still blocked until the next push
+        assert!(!third.is_finished());
+        run(&state, &mut client, &["LPUSH", "jobs", "c"]).await;
+        assert_eq!(
+            third.await.unwrap(),
+            "*2\r\n$4\r\njobs\r\n*1\r\n$1\r\nc\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moved_elements_wake_clients_up() {
+        let state = Arc::new(State::new(16));
+        let mut client = Client::new();
+
+        let (_, mover) = run_blocked(
+            &state,
+            &["BLMOVE", "pending", "active", "LEFT", "RIGHT", "0"],
+        )
+        .await;
+        let (_, worker) = run_blocked(&state, &["BLPOP", "active", "0"]).await;
+
+        run(&state, &mut client, &["LPUSH", "pending", "job"]).await;
+        assert_eq!(mover.await.unwrap(), "$3\r\njob\r\n");
+        assert_eq!(worker.await.unwrap(), "*2\r\n$6\r\nactive\r\n$3\r\njob\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["EXISTS", "pending", "active"]).await,
+            ":0\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pop_timeout_and_unblock() {
+        let state = Arc::new(State::new(16));
+        let mut client = Client::new();
+
+        let (_, timed_out) = run_blocked(&state, &["BLPOP", "q", "0.05"]).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(timed_out.await.unwrap(), "*-1\r\n");
+
+        let (id, unblocked) =
+            run_blocked(&state, &["BLMOVE", "q", "r", "LEFT", "LEFT", "0"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "UNBLOCK", &id.to_string()]).await,
+            ":1\r\n"
+        );
+        assert_eq!(unblocked.await.unwrap(), "$-1\r\n");
+
+        let (id, unblocked) = run_blocked(&state, &["BRPOP", "q", "0"]).await;
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["CLIENT", "UNBLOCK", &id.to_string(), "ERROR"]
+            )
+            .await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            unblocked.await.unwrap(),
+            "UNBLOCKED client unblocked via CLIENT UNBLOCK"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CLIENT", "UNBLOCK", &id.to_string()]).await,
+            ":0\r\n"
+        );
+
+        // Unblocked clients are no longer served
+        run(&state, &mut client, &["RPUSH", "q", "x"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "q", "0", "-1"]).await,
+            "*1\r\n$1\r\nx\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pops_dont_block_in_transactions() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["BLPOP", "q", "0"]).await;
+        run(&state, &mut client, &["RPUSH", "q", "x"]).await;
+        run(
+            &state,
+            &mut client,
+            &["BLMOVE", "q", "r", "LEFT", "LEFT", "0"],
+        )
+        .await;
+        run(
+            &state,
+            &mut client,
+            &["BLMOVE", "q", "r", "LEFT", "LEFT", "0"],
+        )
+        .await;
+        assert_eq!(
+            run(&state, &mut client, &["EXEC"]).await,
+            "*4\r\n*-1\r\n:1\r\n$1\r\nx\r\n$-1\r\n"
+        );
+    }
+
+    #[test]
+    fn test_propagate_blocking_pops() {
+        let reply = ZystResponse::Array(vec![
+            ZystResponse::BulkString(Bytes::from("b")),
+            ZystResponse::List(argv(&["1", "2"])),
+        ]);
+        assert_eq!(
+            propagate_blocking_pop(
+                &argv(&["BLMPOP", "1.5", "2", "a", "b", "RIGHT", "COUNT", "5"]),
+                &reply
+            ),
+            argv(&["RPOP", "b", "2"])
+        );
+        assert_eq!(
+            propagate_blocking_pop(&argv(&["BLPOP", "a", "0"]), &ZystResponse::NilArray),
+            Vec::<Bytes>::new()
+        );
+
+        // Moves are logged as LMOVE
+        let moved = ZystResponse::BulkString(Bytes::from("x"));
+        assert_eq!(
+            propagate_blocking_pop(&argv(&["BLMOVE", "a", "b", "left", "right", "0"]), &moved),
+            argv(&["LMOVE", "a", "b", "LEFT", "RIGHT"])
+        );
+        assert_eq!(
+            propagate_blocking_pop(&argv(&["RPOPLPUSH", "a", "b"]), &moved),
+            argv(&["LMOVE", "a", "b", "RIGHT", "LEFT"])
+        );
+        assert_eq!(
+            propagate_blocking_pop(
+                &argv(&["LMOVE", "a", "b", "LEFT", "LEFT"]),
+                &ZystResponse::Nil
+            ),
+            Vec::<Bytes>::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disconnected_client_stops_waiting() {
+        let state = Arc::new(State::new(16));
+        let addr = serve(state.clone()).await;
+        let mut client = Client::new();
+
+        let mut socket = TcpStream::connect(&addr).await.unwrap();
+        socket.write_all(b"BLPOP q 0\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(socket);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The element isn't handed to the closed connection
+        run(&state, &mut client, &["RPUSH", "q", "x"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["LRANGE", "q", "0", "-1"]).await,
+            "*1\r\n$1\r\nx\r\n"
+        );
+
+        let mut socket = TcpStream::connect(&addr).await.unwrap();
+        socket.write_all(b"BLPOP q 0\r\n").await.unwrap();
+        let mut reply = [0; 64];
+        let n = socket.read(&mut reply).await.unwrap();
+        assert_eq!(&reply[..n], b"*2\r\n$1\r\nq\r\n$1\r\nx\r\n");
+    }
+}
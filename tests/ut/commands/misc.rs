@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use zyst::client::Client;
+    use zyst::commands::misc::*;
+    use zyst::errors::ZystError;
+    use zyst::response::Protocol;
+    use zyst::types::*;
+
+    fn hello_command(args: &[&str]) -> Command {
+        Command {
+            name: "hello",
+            args: CommandArgs::MultipleKeys(
+                args.iter().map(|a| Bytes::from(a.to_string())).collect(),
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hello_switches_protocol() {
+        let mut client = Client::new();
+
+        let result = hello(&mut client, hello_command(&["3"])).await.unwrap();
+
+        assert_eq!(client.protocol, Protocol::Resp3);
+        assert!(result.to_string().contains("$5\r\nproto\r\n:3\r\n"));
+
+        hello(&mut client, hello_command(&["2"])).await.unwrap();
+        assert_eq!(client.protocol, Protocol::Resp2);
+    }
+
+    #[tokio::test]
+    async fn test_hello_without_version_keeps_protocol() {
+        let mut client = Client::new();
+
+        let result = hello(&mut client, hello_command(&[])).await.unwrap();
+
+        assert_eq!(client.protocol, Protocol::Resp2);
+        assert!(result.to_string().contains("$5\r\nproto\r\n:2\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_hello_auth_and_setname() {
+        let mut client = Client::new();
+
+        let command = hello_command(&["3", "AUTH", "default", "secret", "SETNAME", "worker"]);
+        hello(&mut client, command).await.unwrap();
+
+        assert_eq!(client.name.as_deref(), Some("worker"));
+
+        let command = hello_command(&["3", "AUTH", "admin", "secret"]);
+        let result = hello(&mut client, command).await;
+        assert!(matches!(result, Err(ZystError::WrongPass)));
+    }
+
+    #[tokio::test]
+    async fn test_hello_unsupported_version() {
+        let mut client = Client::new();
+
+        let result = hello(&mut client, hello_command(&["4"])).await;
+
+        assert!(matches!(result, Err(ZystError::NoProto)));
+        assert_eq!(client.protocol, Protocol::Resp2);
+    }
+}
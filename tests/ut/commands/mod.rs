@@ -1,3 +1,31 @@
+pub mod client;
 pub mod db;
 pub mod hashsets;
+pub mod introspection;
 pub mod keys;
+pub mod lists;
+pub mod misc;
+pub mod pubsub;
+pub mod registry;
+pub mod scan;
+pub mod sets;
+pub mod transactions;
+
+use bytes::Bytes;
+use zyst::client::Client;
+use zyst::process::process_command;
+use zyst::state::State;
+
+/// Arguments of a command, as the decoder hands them over
+pub fn argv(args: &[&str]) -> Vec<Bytes> {
+    args.iter()
+        .map(|arg| Bytes::from(arg.to_string()))
+        .collect()
+}
+
+/// Runs a command from a client, the reply or the error is rendered as text
+pub async fn run(state: &State, client: &mut Client, args: &[&str]) -> String {
+    process_command(argv(args), state, client, true)
+        .await
+        .map_or_else(|e| e.to_string(), |response| response.to_string())
+}
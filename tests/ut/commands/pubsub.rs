@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::run;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use zyst::client::{Client, PUBSUB_BUFFER};
+    use zyst::server::handle_client;
+    use zyst::state::State;
+
+    /// Serves connections on a random port, returns its address
+    async fn serve(state: Arc<State>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let _ = handle_client(socket, state, 1024).await;
+                });
+            }
+        });
+
+        addr
+    }
+
+    /// Sends inline commands, returns what was read until `expected` bytes
+    /// arrived or nothing more came
+    async fn exchange(socket: &mut TcpStream, commands: &str, expected: usize) -> String {
+        socket.write_all(commands.as_bytes()).await.unwrap();
+
+        let mut reply = Vec::new();
+        let mut buffer = [0; 1024];
+        while reply.len() < expected {
+            let read =
+                tokio::time::timeout(Duration::from_millis(200), socket.read(&mut buffer));
+            match read.await {
+                Ok(Ok(n)) if n > 0 => reply.extend_from_slice(&buffer[..n]),
+                _ => break,
+            }
+        }
+
+        String::from_utf8(reply).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_and_unsubscribe() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["SUBSCRIBE", "news", "sport", "news"]).await,
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n\
+             *3\r\n$9\r\nsubscribe\r\n$5\r\nsport\r\n:2\r\n\
+             *3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["PSUBSCRIBE", "n*"]).await,
+            "*3\r\n$10\r\npsubscribe\r\n$2\r\nn*\r\n:3\r\n"
+        );
+
+        // Only the pub/sub commands are allowed while subscribed
+        assert_eq!(
+            run(&state, &mut client, &["GET", "a"]).await,
+            "ERR Can't execute 'get': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING are allowed \
+             in this context"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["PING"]).await,
+            "*2\r\n$4\r\npong\r\n$0\r\n\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["UNSUBSCRIBE"]).await,
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:2\r\n\
+             *3\r\n$11\r\nunsubscribe\r\n$5\r\nsport\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["PUNSUBSCRIBE", "n*", "x*"]).await,
+            "*3\r\n$12\r\npunsubscribe\r\n$2\r\nn*\r\n:0\r\n\
+             *3\r\n$12\r\npunsubscribe\r\n$2\r\nx*\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["UNSUBSCRIBE"]).await,
+            "*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["GET", "a"]).await, "$-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_introspection() {
+        let state = State::new(16);
+        let mut first = Client::new();
+        let mut second = Client::new();
+        let mut publisher = Client::new();
+
+        run(&state, &mut first, &["SUBSCRIBE", "news.tech", "weather"]).await;
+        run(&state, &mut second, &["SUBSCRIBE", "news.tech"]).await;
+        run(&state, &mut second, &["PSUBSCRIBE", "news.*", "*"]).await;
+
+        assert_eq!(
+            run(&state, &mut publisher, &["PUBLISH", "news.tech", "hello"]).await,
+            ":4\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut publisher, &["PUBLISH", "nobody", "hello"]).await,
+            ":1\r\n"
+        );
+
+        assert_eq!(
+            first.messages.try_recv().unwrap().to_string(),
+            "*3\r\n$7\r\nmessage\r\n$9\r\nnews.tech\r\n$5\r\nhello\r\n"
+        );
+        assert!(first.messages.try_recv().is_err());
+        assert_eq!(
+            second.messages.try_recv().unwrap().to_string(),
+            "*3\r\n$7\r\nmessage\r\n$9\r\nnews.tech\r\n$5\r\nhello\r\n"
+        );
+        assert_eq!(
+            second.messages.try_recv().unwrap().to_string(),
+            "*4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$9\r\nnews.tech\r\n$5\r\nhello\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut publisher, &["PUBSUB", "CHANNELS"]).await,
+            "*2\r\n$9\r\nnews.tech\r\n$7\r\nweather\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut publisher, &["PUBSUB", "CHANNELS", "w*"]).await,
+            "*1\r\n$7\r\nweather\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut publisher,
+                &["PUBSUB", "NUMSUB", "news.tech", "x"]
+            )
+            .await,
+            "*4\r\n$9\r\nnews.tech\r\n:2\r\n$1\r\nx\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut publisher, &["PUBSUB", "NUMPAT"]).await,
+            ":2\r\n"
+        );
+
+        run(&state, &mut first, &["UNSUBSCRIBE", "weather"]).await;
+        assert_eq!(
+            run(&state, &mut publisher, &["PUBSUB", "CHANNELS", "w*"]).await,
+            "*0\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_is_disconnected() {
+        let state = State::new(16);
+        let mut subscriber = Client::new();
+        let mut publisher = Client::new();
+
+        run(&state, &mut subscriber, &["SUBSCRIBE", "jobs"]).await;
+        for _ in 0..PUBSUB_BUFFER {
+            run(&state, &mut publisher, &["PUBLISH", "jobs", "x"]).await;
+        }
+        assert!(!subscriber.handle.is_killed());
+
+        run(&state, &mut publisher, &["PUBLISH", "jobs", "x"]).await;
+        assert!(subscriber.handle.is_killed());
+    }
+
+    #[tokio::test]
+    async fn test_messages_are_pushed_to_the_connection() {
+        let state = Arc::new(State::new(16));
+        let addr = serve(state.clone()).await;
+        let mut subscriber = TcpStream::connect(&addr).await.unwrap();
+        let mut publisher = TcpStream::connect(&addr).await.unwrap();
+
+        let reply = exchange(&mut subscriber, "SUBSCRIBE events\r\n", 33).await;
+        assert_eq!(reply, "*3\r\n$9\r\nsubscribe\r\n$6\r\nevents\r\n:1\r\n");
+
+        let reply = exchange(&mut publisher, "PUBLISH events hi\r\n", 4).await;
+        assert_eq!(reply, ":1\r\n");
+        assert_eq!(
+            exchange(&mut subscriber, "", 35).await,
+            "*3\r\n$7\r\nmessage\r\n$6\r\nevents\r\n$2\r\nhi\r\n"
+        );
+
+        // RESP3 connections get push messages and can run any command
+        let mut resp3 = TcpStream::connect(&addr).await.unwrap();
+        let reply = exchange(&mut resp3, "HELLO 3\r\n", 1024).await;
+        assert!(reply.starts_with('%'));
+        exchange(&mut resp3, "SUBSCRIBE events\r\n", 33).await;
+        assert_eq!(exchange(&mut resp3, "GET k\r\n", 3).await, "_\r\n");
+
+        exchange(&mut publisher, "PUBLISH events bye\r\n", 4).await;
+        assert_eq!(
+            exchange(&mut resp3, "", 36).await,
+            ">3\r\n$7\r\nmessage\r\n$6\r\nevents\r\n$3\r\nbye\r\n"
+        );
+
+        // Subscriptions end with the connection
+        drop(subscriber);
+        drop(resp3);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            exchange(&mut publisher, "PUBSUB NUMSUB events\r\n", 22).await,
+            "*2\r\n$6\r\nevents\r\n:0\r\n"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::argv;
+    use zyst::client::Client;
+    use zyst::commands::registry::*;
+    use zyst::parser::parse_command;
+    use zyst::process::process_command;
+    use zyst::state::State;
+    use zyst::types::*;
+
+    #[test]
+    fn test_lookup_ignores_case() {
+        let spec = lookup_command(b"hGeTaLl").unwrap();
+        assert_eq!(spec.name, "hgetall");
+        assert!(lookup_command(b"nope").is_none());
+    }
+
+    #[test]
+    fn test_command_names_are_unique() {
+        let mut names = COMMANDS.iter().map(|spec| spec.name).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), COMMANDS.len());
+    }
+
+    #[test]
+    fn test_write_flags() {
+        for name in ["lpop", "rpop", "srem", "hdel", "flushdb"] {
+            assert!(
+                lookup_command(name.as_bytes()).unwrap().is_write(),
+                "{name}"
+            );
+        }
+        for name in ["get", "smembers", "ping", "hello", "client", "command"] {
+            assert!(
+                !lookup_command(name.as_bytes()).unwrap().is_write(),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_arity() {
+        let get = lookup_command(b"get").unwrap();
+        assert!(get.check_arity(2).is_ok());
+        assert!(get.check_arity(1).is_err());
+        assert!(get.check_arity(3).is_err());
+
+        let del = lookup_command(b"del").unwrap();
+        assert!(del.check_arity(1).is_err());
+        assert!(del.check_arity(2).is_ok());
+        assert!(del.check_arity(10).is_ok());
+    }
+
+    #[test]
+    fn test_keys() {
+        let del = lookup_command(b"del").unwrap();
+        let args = argv(&["DEL", "a", "b", "c"]);
+        assert_eq!(del.keys_of(&args), vec![&args[1], &args[2], &args[3]]);
+
+        let hset = lookup_command(b"hset").unwrap();
+        let args = argv(&["HSET", "h", "field", "value"]);
+        assert_eq!(hset.keys_of(&args), vec![&args[1]]);
+
+        let ping = lookup_command(b"ping").unwrap();
+        assert!(ping.keys_of(&argv(&["PING"])).is_empty());
+    }
+
+    #[test]
+    fn test_parse_command_errors() {
+        let err = parse_command(&argv(&["GET"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'get' command"
+        );
+
+        let err = parse_command(&argv(&["FOO", "bar"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown command 'FOO', with args beginning with: 'bar' "
+        );
+
+        let err = parse_command(&argv(&["HSET", "h", "f", "v", "f2"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'hset' command"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_command_dispatches() {
+        let state = State::new(1);
+        let mut client = Client::new();
+
+        let result =
+            process_command(argv(&["rpush", "l", "a", "b"]), &state, &mut client, true)
+                .await
+                .unwrap();
+        assert_eq!(result.to_string(), ":2\r\n");
+
+        let result = process_command(argv(&["LPOP", "l"]), &state, &mut client, true)
+            .await
+            .unwrap();
+        assert_eq!(result.to_string(), "$1\r\na\r\n");
+    }
+
+    #[test]
+    fn test_parse_subcommand() {
+        let (spec, command) = parse_command(&argv(&["command", "INFO", "get"])).unwrap();
+        assert_eq!(spec.name, "command|info");
+        assert!(
+            matches!(command.args, CommandArgs::MultipleKeys(ref names) if names.len() == 1)
+        );
+
+        let err = parse_command(&argv(&["COMMAND", "nope"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown subcommand 'nope'. Try COMMAND HELP."
+        );
+
+        let err = parse_command(&argv(&["COMMAND", "COUNT", "extra"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'command|count' command"
+        );
+
+        let (spec, _) = parse_command(&argv(&["COMMAND"])).unwrap();
+        assert_eq!(spec.name, "command");
+    }
+}
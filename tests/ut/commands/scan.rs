@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::argv;
+    use bytes::Bytes;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use zyst::client::Client;
+    use zyst::keyspace::Keyspace;
+    use zyst::process::process_command;
+    use zyst::response::ZystResponse;
+    use zyst::state::State;
+    use zyst::types::*;
+
+    async fn setup_db() -> Db {
+        Arc::new(RwLock::new(Keyspace::new()))
+    }
+
+    async fn run(db: &Db, args: &[&str]) -> Result<ZystResponse, String> {
+        let state = State::with_databases(vec![db.clone()]);
+        process_command(argv(args), &state, &mut Client::new(), true)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Runs one scan call, returns the next cursor and the elements
+    async fn scan_once(db: &Db, args: &[&str]) -> (String, Vec<Bytes>) {
+        match run(db, args).await {
+            Ok(ZystResponse::Array(reply)) => match &reply[..] {
+                [ZystResponse::BulkString(cursor), ZystResponse::List(elements)] => (
+                    String::from_utf8(cursor.to_vec()).unwrap(),
+                    elements.clone(),
+                ),
+                _ => panic!("unexpected scan reply {reply:?}"),
+            },
+            reply => panic!("unexpected scan reply {reply:?}"),
+        }
+    }
+
+    /// Scans until the cursor comes back to 0
+    async fn scan_all(db: &Db, command: &[&str], options: &[&str]) -> Vec<Bytes> {
+        let mut cursor = "0".to_string();
+        let mut elements = Vec::new();
+
+        loop {
+            let mut args = command.to_vec();
+            args.push(&cursor);
+            args.extend(options);
+
+            let (next, batch) = scan_once(db, &args).await;
+            elements.extend(batch);
+            cursor = next;
+
+            if cursor == "0" {
+                return elements;
+            }
+        }
+    }
+
+    fn names(elements: &[Bytes]) -> HashSet<String> {
+        elements
+            .iter()
+            .map(|element| String::from_utf8(element.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_scan_options() {
+        let db = setup_db().await;
+        for i in 0..25 {
+            run(&db, &["SET", &format!("user:{i}"), "v"]).await.unwrap();
+        }
+        run(&db, &["RPUSH", "user:list", "a"]).await.unwrap();
+        run(&db, &["SADD", "other", "a"]).await.unwrap();
+
+        assert_eq!(scan_all(&db, &["SCAN"], &[]).await.len(), 27);
+
+        let (cursor, keys) = scan_once(&db, &["SCAN", "0", "COUNT", "5"]).await;
+        assert_eq!((cursor.as_str(), keys.len()), ("22", 5));
+
+        let keys = scan_all(&db, &["SCAN"], &["MATCH", "user:1*"]).await;
+        assert_eq!(keys.len(), 11);
+
+        let keys = scan_all(&db, &["SCAN"], &["match", "user:*", "TYPE", "LIST"]).await;
+        assert_eq!(names(&keys), HashSet::from(["user:list".to_string()]));
+
+        let keys = scan_all(&db, &["SCAN"], &["TYPE", "zset", "COUNT", "100"]).await;
+        assert!(keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_errors() {
+        let db = setup_db().await;
+        run(&db, &["SET", "string", "v"]).await.unwrap();
+
+        let cases = [
+            (vec!["SCAN", "abc"], "ERR invalid cursor"),
+            (vec!["SCAN", "-1"], "ERR invalid cursor"),
+            (vec!["SCAN", "0", "COUNT", "0"], "ERR syntax error"),
+            (
+                vec!["SCAN", "0", "COUNT", "x"],
+                "ERR value is not an integer or out of range",
+            ),
+            (vec!["SCAN", "0", "MATCH"], "ERR syntax error"),
+            (
+                vec!["SCAN", "0", "TYPE", "nope"],
+                "ERR unknown type name 'nope'",
+            ),
+            (vec!["SCAN", "0", "NOVALUES"], "ERR syntax error"),
+            (vec!["SSCAN", "s", "0", "TYPE", "set"], "ERR syntax error"),
+            (
+                vec!["SSCAN", "string", "0"],
+                "WRONGTYPE Operation against a key holding the wrong kind of value",
+            ),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(run(&db, &args).await.unwrap_err(), expected, "{args:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_returns_keys_present_during_iteration() {
+        let db = setup_db().await;
+        for i in 0..100 {
+            run(&db, &["SET", &format!("key:{i}"), "v"]).await.unwrap();
+        }
+
+        let mut cursor = "0".to_string();
+        let mut seen = HashSet::new();
+        let mut round = 0;
+
+        loop {
+            let (next, keys) = scan_once(&db, &["SCAN", &cursor, "COUNT", "7"]).await;
+            seen.extend(names(&keys));
+            cursor = next;
+
+            if cursor == "0" {
+                break;
+            }
+
+            // Removals move the last keys around, additions append new ones
+            run(&db, &["DEL", &format!("key:{}", round * 3)])
+                .await
+                .unwrap();
+            run(&db, &["SET", &format!("new:{round}"), "v"])
+                .await
+                .unwrap();
+            round += 1;
+        }
+
+        for i in 0..100 {
+            let deleted = i % 3 == 0 && i / 3 < round;
+            assert!(
+                deleted || seen.contains(&format!("key:{i}")),
+                "key:{i} was missed"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sscan_and_hscan() {
+        let db = setup_db().await;
+        run(&db, &["SADD", "set", "a", "b", "c", "ab"])
+            .await
+            .unwrap();
+        run(&db, &["HSET", "hash", "f1", "v1", "f2", "v2", "g", "v3"])
+            .await
+            .unwrap();
+
+        let members = scan_all(&db, &["SSCAN", "set"], &["MATCH", "a*", "COUNT", "1"]).await;
+        assert_eq!(
+            names(&members),
+            HashSet::from(["a".to_string(), "ab".to_string()])
+        );
+
+        let (cursor, elements) = scan_once(&db, &["HSCAN", "hash", "0", "MATCH", "f*"]).await;
+        assert_eq!(cursor, "0");
+        assert_eq!(elements, vec!["f2", "v2", "f1", "v1"]);
+
+        let fields = scan_all(&db, &["HSCAN", "hash"], &["NOVALUES", "COUNT", "2"]).await;
+        assert_eq!(
+            names(&fields),
+            HashSet::from(["f1".to_string(), "f2".to_string(), "g".to_string()])
+        );
+
+        let (cursor, elements) = scan_once(&db, &["SSCAN", "missing", "0"]).await;
+        assert_eq!((cursor.as_str(), elements.len()), ("0", 0));
+    }
+}
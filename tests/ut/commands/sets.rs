@@ -0,0 +1,322 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::{argv, run};
+    use bytes::Bytes;
+    use zyst::client::Client;
+    use zyst::commands::sets::propagate_spop;
+    use zyst::response::ZystResponse;
+    use zyst::state::State;
+
+    #[tokio::test]
+    async fn test_sadd_counts_added_members() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["SADD", "s", "a", "a", "b"]).await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SADD", "s", "b", "c", "c"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SADD", "s", "a"]).await,
+            ":0\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_membership() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SADD", "s", "a", "b", "c"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SISMEMBER", "s", "b"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SISMEMBER", "s", "d"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SISMEMBER", "none", "a"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMISMEMBER", "s", "a", "d", "c"]).await,
+            "*3\r\n:1\r\n:0\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMISMEMBER", "none", "a"]).await,
+            "*1\r\n:0\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["SCARD", "s"]).await, ":3\r\n");
+        assert_eq!(run(&state, &mut client, &["SCARD", "none"]).await, ":0\r\n");
+
+        run(&state, &mut client, &["SET", "str", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SCARD", "str"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_random_members() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SADD", "s", "a", "b", "c"]).await;
+
+        let member = run(&state, &mut client, &["SRANDMEMBER", "s"]).await;
+        assert!(["$1\r\na\r\n", "$1\r\nb\r\n", "$1\r\nc\r\n"].contains(&member.as_str()));
+
+        // Distinct members with a positive count, at most the whole set
+        let members = run(&state, &mut client, &["SRANDMEMBER", "s", "10"]).await;
+        assert!(members.starts_with("*3\r\n"));
+        for member in ["a", "b", "c"] {
+            assert!(members.contains(&format!("$1\r\n{member}\r\n")));
+        }
+
+        // A negative count may repeat members
+        let members = run(&state, &mut client, &["SRANDMEMBER", "s", "-5"]).await;
+        assert!(members.starts_with("*5\r\n"));
+
+        assert_eq!(
+            run(&state, &mut client, &["SRANDMEMBER", "s", "0"]).await,
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SRANDMEMBER", "none"]).await,
+            "$-1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SRANDMEMBER", "none", "-2"]).await,
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SRANDMEMBER", "s", "x"]).await,
+            "ERR value is not an integer or out of range"
+        );
+
+        // A count larger than the set picks it whole, a negative one is bounded
+        let members = run(
+            &state,
+            &mut client,
+            &["SRANDMEMBER", "s", "9223372036854775807"],
+        )
+        .await;
+        assert!(members.starts_with("*3\r\n"));
+        for count in ["-10000000000", "-4611686018427387903"] {
+            assert_eq!(
+                run(&state, &mut client, &["SRANDMEMBER", "s", count]).await,
+                "ERR value is out of range"
+            );
+        }
+
+        let popped = run(&state, &mut client, &["SPOP", "s", "2"]).await;
+        assert!(popped.starts_with("*2\r\n"));
+        assert_eq!(run(&state, &mut client, &["SCARD", "s"]).await, ":1\r\n");
+
+        // The last member popped deletes the set
+        run(&state, &mut client, &["SPOP", "s"]).await;
+        assert_eq!(run(&state, &mut client, &["EXISTS", "s"]).await, ":0\r\n");
+        assert_eq!(run(&state, &mut client, &["SPOP", "s"]).await, "$-1\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["SPOP", "s", "3"]).await,
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SPOP", "s", "-1"]).await,
+            "ERR value is out of range, must be positive"
+        );
+    }
+
+    #[test]
+    fn test_propagate_spop() {
+        assert_eq!(
+            propagate_spop(
+                &argv(&["SPOP", "s"]),
+                &ZystResponse::BulkString(Bytes::from("a"))
+            ),
+            argv(&["SREM", "s", "a"])
+        );
+        assert_eq!(
+            propagate_spop(
+                &argv(&["SPOP", "s", "5"]),
+                &ZystResponse::Set(vec![
+                    ZystResponse::BulkString(Bytes::from("a")),
+                    ZystResponse::BulkString(Bytes::from("b")),
+                ])
+            ),
+            argv(&["SREM", "s", "a", "b"])
+        );
+        assert_eq!(
+            propagate_spop(&argv(&["SPOP", "s", "5"]), &ZystResponse::Set(Vec::new())),
+            Vec::<Bytes>::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_smove() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SADD", "src", "a", "b"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SMOVE", "src", "dst", "a"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMOVE", "src", "dst", "a"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMOVE", "src", "src", "b"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMOVE", "src", "dst", "b"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "src"]).await, ":0\r\n");
+        assert_eq!(run(&state, &mut client, &["SCARD", "dst"]).await, ":2\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["SMOVE", "src", "dst", "b"]).await,
+            ":0\r\n"
+        );
+
+        run(&state, &mut client, &["SET", "str", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SMOVE", "dst", "str", "a"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+        assert_eq!(run(&state, &mut client, &["SCARD", "dst"]).await, ":2\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_set_algebra() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SADD", "a", "1", "2", "3", "4"]).await;
+        run(&state, &mut client, &["SADD", "b", "3", "4", "5"]).await;
+        run(&state, &mut client, &["SADD", "c", "4", "6"]).await;
+
+        assert_eq!(
+            run(&state, &mut client, &["SINTER", "a", "b", "c"]).await,
+            "*1\r\n$1\r\n4\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SINTER", "a", "none"]).await,
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SUNION", "b", "c", "none"]).await,
+            "*4\r\n$1\r\n3\r\n$1\r\n4\r\n$1\r\n5\r\n$1\r\n6\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SDIFF", "a", "b", "none"]).await,
+            "*2\r\n$1\r\n1\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SDIFF", "none", "a"]).await,
+            "*0\r\n"
+        );
+
+        // The destination is overwritten whatever it holds
+        run(&state, &mut client, &["SET", "dst", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SINTERSTORE", "dst", "a", "b"]).await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMEMBERS", "dst"]).await,
+            "*2\r\n$1\r\n3\r\n$1\r\n4\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SUNIONSTORE", "dst", "dst", "c"]).await,
+            ":3\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SDIFFSTORE", "dst", "c", "a"]).await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SMEMBERS", "dst"]).await,
+            "*1\r\n$1\r\n6\r\n"
+        );
+
+        // An empty result deletes it
+        assert_eq!(
+            run(&state, &mut client, &["SINTERSTORE", "dst", "a", "none"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "dst"]).await, ":0\r\n");
+
+        run(&state, &mut client, &["SET", "str", "v"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SINTER", "none", "str"]).await,
+            "WRONGTYPE Operation against a key holding the wrong kind of value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sintercard() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["SADD", "a", "1", "2", "3", "4"]).await;
+        run(&state, &mut client, &["SADD", "b", "2", "3", "4", "5"]).await;
+
+        assert_eq!(
+            run(&state, &mut client, &["SINTERCARD", "2", "a", "b"]).await,
+            ":3\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["SINTERCARD", "2", "a", "b", "LIMIT", "2"]
+            )
+            .await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["SINTERCARD", "2", "a", "b", "LIMIT", "0"]
+            )
+            .await,
+            ":3\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SINTERCARD", "2", "a", "none"]).await,
+            ":0\r\n"
+        );
+
+        assert_eq!(
+            run(&state, &mut client, &["SINTERCARD", "0", "a"]).await,
+            "ERR numkeys should be greater than 0"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SINTERCARD", "3", "a", "b"]).await,
+            "ERR Number of keys can't be greater than number of args"
+        );
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["SINTERCARD", "1", "a", "LIMIT", "-1"]
+            )
+            .await,
+            "ERR LIMIT can't be negative"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["SINTERCARD", "1", "a", "b"]).await,
+            "ERR syntax error"
+        );
+    }
+}
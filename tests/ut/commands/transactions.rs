@@ -0,0 +1,332 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::{argv, run};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use zyst::client::Client;
+    use zyst::parser::check_command;
+    use zyst::process::execute;
+    use zyst::state::State;
+
+    #[tokio::test]
+    async fn test_exec_runs_queued_commands() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(run(&state, &mut client, &["MULTI"]).await, "+OK\r\n");
+        assert_eq!(
+            run(&state, &mut client, &["SET", "a", "1"]).await,
+            "+QUEUED\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["INCR", "a"]).await,
+            "+QUEUED\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["RPUSH", "a", "x"]).await,
+            "+QUEUED\r\n"
+        );
+        assert_eq!(run(&state, &mut client, &["GET", "a"]).await, "+QUEUED\r\n");
+
+        // Nothing ran yet
+        let mut other = Client::new();
+        assert_eq!(run(&state, &mut other, &["GET", "a"]).await, "$-1\r\n");
+
+        // Errors are replied in place, the other commands still run
+        assert_eq!(
+            run(&state, &mut client, &["EXEC"]).await,
+            "*4\r\n+OK\r\n:2\r\n-WRONGTYPE Operation against a key holding the wrong kind of \
+             value\r\n$1\r\n2\r\n"
+        );
+        assert!(client.transaction.is_none());
+        assert_eq!(run(&state, &mut client, &["MULTI"]).await, "+OK\r\n");
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*0\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_queueing_errors_abort_the_transaction() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["GET", "a", "b"]).await,
+            "ERR wrong number of arguments for 'get' command"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["EXEC"]).await,
+            "EXECABORT Transaction discarded because of previous errors."
+        );
+        assert_eq!(run(&state, &mut client, &["EXISTS", "a"]).await, ":0\r\n");
+
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["NOSUCHCOMMAND"]).await;
+        assert!(run(&state, &mut client, &["EXEC"])
+            .await
+            .starts_with("EXECABORT"));
+
+        // Syntax errors found when the command runs don't abort
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["SET", "a", "1", "NX", "XX"]).await,
+            "+QUEUED\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["EXEC"]).await,
+            "*1\r\n-ERR syntax error\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transaction_control_errors() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(&state, &mut client, &["EXEC"]).await,
+            "ERR EXEC without MULTI"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["DISCARD"]).await,
+            "ERR DISCARD without MULTI"
+        );
+
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["MULTI"]).await,
+            "ERR MULTI calls can not be nested"
+        );
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        assert_eq!(run(&state, &mut client, &["DISCARD"]).await, "+OK\r\n");
+        assert_eq!(run(&state, &mut client, &["EXISTS", "a"]).await, ":0\r\n");
+
+        // A nested MULTI doesn't abort the transaction
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*1\r\n+OK\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_exec_waits_for_commands_in_flight() {
+        let state = Arc::new(State::new(16));
+
+        let mut client = Client::new();
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+
+        let gate = state.command_gate().read().await;
+        let exec = tokio::spawn({
+            let state = state.clone();
+            async move { run(&state, &mut client, &["EXEC"]).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!exec.is_finished());
+        assert!(state.db(0).unwrap().read().await.is_empty());
+
+        drop(gate);
+        assert_eq!(exec.await.unwrap(), "*1\r\n+OK\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_exec_is_logged_as_one_unit() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        for command in [
+            &["MULTI"][..],
+            &["SET", "a", "1"],
+            &["GET", "a"],
+            &["SELECT", "1"],
+            &["EXPIRE", "a", "100", "XX"],
+            &["INCR", "b"],
+        ] {
+            run(&state, &mut client, command).await;
+        }
+
+        let exec = argv(&["EXEC"]);
+        let mut propagated = Vec::new();
+        let spec = check_command(&exec).unwrap();
+        execute(spec, exec, &state, &mut client, &mut propagated)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            propagated,
+            vec![
+                (0, argv(&["MULTI"])),
+                (0, argv(&["SET", "a", "1"])),
+                (1, argv(&["INCR", "b"])),
+                (1, argv(&["EXEC"])),
+            ]
+        );
+
+        // A single write isn't wrapped
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["INCR", "b"]).await;
+        let exec = argv(&["EXEC"]);
+        let mut propagated = Vec::new();
+        execute(spec, exec, &state, &mut client, &mut propagated)
+            .await
+            .unwrap();
+        assert_eq!(propagated, vec![(1, argv(&["INCR", "b"]))]);
+    }
+
+    #[tokio::test]
+    async fn test_watched_key_modified() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let mut other = Client::new();
+
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["WATCH", "a", "b"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(client.watched.len(), 2);
+
+        // Reads and writes to other keys don't count
+        run(&state, &mut other, &["GET", "a"]).await;
+        run(&state, &mut other, &["SET", "c", "1"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["INCR", "a"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*1\r\n:2\r\n");
+        assert!(client.watched.is_empty());
+
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        run(&state, &mut other, &["INCR", "a"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["INCR", "a"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+        assert_eq!(run(&state, &mut client, &["GET", "a"]).await, "$1\r\n3\r\n");
+
+        // Creating a watched key counts too
+        run(&state, &mut client, &["WATCH", "b"]).await;
+        run(&state, &mut other, &["RPUSH", "b", "x"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_watched_key_left_unchanged() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let mut other = Client::new();
+
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        run(&state, &mut client, &["SET", "b", "2"]).await;
+        run(&state, &mut client, &["SADD", "s", "x"]).await;
+        run(&state, &mut client, &["HSET", "h", "f", "1"]).await;
+        run(&state, &mut client, &["RPUSH", "l", "x"]).await;
+        run(&state, &mut client, &["WATCH", "a", "b", "s", "h", "l"]).await;
+
+        // Commands that fail or find nothing to change don't count
+        assert_eq!(
+            run(&state, &mut other, &["RENAMENX", "a", "b"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut other, &["RENAME", "a", "a"]).await,
+            "+OK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut other, &["EXPIRE", "a", "100", "XX"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(run(&state, &mut other, &["SADD", "s", "x"]).await, ":0\r\n");
+        assert_eq!(run(&state, &mut other, &["SREM", "s", "y"]).await, ":0\r\n");
+        assert_eq!(run(&state, &mut other, &["HDEL", "h", "g"]).await, ":0\r\n");
+        assert_eq!(
+            run(&state, &mut other, &["LREM", "l", "0", "y"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut other, &["LSET", "l", "5", "y"]).await,
+            "ERR index out of range"
+        );
+        run(&state, &mut client, &["MULTI"]).await;
+        run(&state, &mut client, &["GET", "a"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["EXEC"]).await,
+            "*1\r\n$1\r\n1\r\n"
+        );
+
+        run(&state, &mut client, &["WATCH", "s"]).await;
+        run(&state, &mut other, &["SADD", "s", "x", "y"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_watched_key_expired_or_flushed() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let mut other = Client::new();
+
+        run(&state, &mut client, &["SET", "a", "1", "PX", "20"]).await;
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+
+        // Deleted by the active expire cycle
+        run(&state, &mut client, &["SET", "a", "1", "PX", "20"]).await;
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(state.db(0).unwrap().write().await.expire_sample(20), (1, 1));
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        run(&state, &mut other, &["FLUSHALL"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+
+        // Flushing a missing key changes nothing
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        run(&state, &mut other, &["FLUSHDB"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*0\r\n");
+
+        // Watches stay with their database when it's swapped
+        run(&state, &mut other, &["SELECT", "1"]).await;
+        run(&state, &mut other, &["SET", "a", "1"]).await;
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        run(&state, &mut other, &["SWAPDB", "0", "1"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*-1\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_unwatch() {
+        let state = State::new(16);
+        let mut client = Client::new();
+        let mut other = Client::new();
+
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        assert_eq!(run(&state, &mut client, &["UNWATCH"]).await, "+OK\r\n");
+        run(&state, &mut other, &["SET", "a", "1"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*0\r\n");
+
+        run(&state, &mut client, &["WATCH", "a"]).await;
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(
+            run(&state, &mut client, &["WATCH", "b"]).await,
+            "ERR WATCH inside MULTI is not allowed"
+        );
+        assert_eq!(run(&state, &mut client, &["DISCARD"]).await, "+OK\r\n");
+        assert!(client.watched.is_empty());
+
+        // Another client watching the same key isn't affected
+        run(&state, &mut client, &["WATCH", "a", "a"]).await;
+        run(&state, &mut other, &["WATCH", "a"]).await;
+        run(&state, &mut other, &["UNWATCH"]).await;
+        assert_eq!(client.watched.len(), 1);
+        run(&state, &mut client, &["MULTI"]).await;
+        assert_eq!(run(&state, &mut client, &["EXEC"]).await, "*0\r\n");
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use zyst::glob::string_match;
+
+    fn check(cases: &[(&str, &str, bool)], nocase: bool) {
+        for (pattern, string, expected) in cases {
+            assert_eq!(
+                string_match(pattern.as_bytes(), string.as_bytes(), nocase),
+                *expected,
+                "{pattern:?} on {string:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_wildcards() {
+        check(
+            &[
+                ("*", "", true),
+                ("*", "anything", true),
+                ("", "", true),
+                ("", "a", false),
+                ("user:*", "user:1", true),
+                ("user:*", "user:", true),
+                ("user:*", "users", false),
+                ("*:1", "user:1", true),
+                ("a**b", "axxb", true),
+                ("h?llo", "hello", true),
+                ("h?llo", "hllo", false),
+                ("a*b*c", "aXbYc", true),
+                ("a*b*c", "aXbY", false),
+                ("abc*", "ab", false),
+                ("ab*", "ab", true),
+            ],
+            false,
+        );
+    }
+
+    #[test]
+    fn test_classes() {
+        check(
+            &[
+                ("h[ae]llo", "hello", true),
+                ("h[ae]llo", "hallo", true),
+                ("h[ae]llo", "hillo", false),
+                ("h[^e]llo", "hallo", true),
+                ("h[^e]llo", "hello", false),
+                ("h[!e]llo", "hallo", true),
+                ("h[!e]llo", "hello", false),
+                ("h[a-b]llo", "hbllo", true),
+                ("h[b-a]llo", "hallo", true),
+                ("h[a-b]llo", "hcllo", false),
+                ("[\\]]", "]", true),
+                ("[a\\-z]", "-", true),
+                ("[a\\-z]", "b", false),
+                ("[]", "a", false),
+                // An unterminated class ends with the pattern
+                ("[abc", "b", true),
+                ("ab[", "ab", false),
+            ],
+            false,
+        );
+    }
+
+    #[test]
+    fn test_escapes() {
+        check(
+            &[
+                ("h\\*llo", "h*llo", true),
+                ("h\\*llo", "hello", false),
+                ("\\?", "?", true),
+                ("\\?", "a", false),
+                ("a\\", "a\\", true),
+                ("\\[a]", "[a]", true),
+            ],
+            false,
+        );
+    }
+
+    #[test]
+    fn test_nocase() {
+        check(
+            &[
+                ("HELLO", "hello", true),
+                ("h[A-C]llo", "hbllo", true),
+                ("h[^E]llo", "hello", false),
+                ("*GET*", "command|getkeys", true),
+            ],
+            true,
+        );
+        check(
+            &[("HELLO", "hello", false), ("h[A-C]llo", "hbllo", false)],
+            false,
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        assert!(string_match(b"k?y", b"k\xffy", false));
+        assert!(string_match(b"*\x00*", b"a\x00b", false));
+        assert!(string_match(b"[\x80-\xff]", b"\xc3", false));
+    }
+
+    #[test]
+    fn test_pathological_patterns() {
+        let string = "a".repeat(50);
+        let pattern = format!("{}b", "a*".repeat(30));
+        assert!(!string_match(pattern.as_bytes(), string.as_bytes(), false));
+
+        let pattern = "*".repeat(5000);
+        assert!(string_match(pattern.as_bytes(), b"abc", false));
+
+        // More nested `*` than the matcher follows
+        let pattern = "a*".repeat(1100);
+        let string = "a".repeat(1100);
+        assert!(!string_match(pattern.as_bytes(), string.as_bytes(), false));
+    }
+}
@@ -0,0 +1,202 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use indexmap::IndexMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use zyst::client::Client;
+    use zyst::database::active_expire_cycle;
+    use zyst::keys::current_timestamp_ms;
+    use zyst::keyspace::Keyspace;
+    use zyst::process::process_command;
+    use zyst::state::State;
+    use zyst::types::*;
+
+    fn string_key(name: &str, expires_at: Option<i64>) -> (Bytes, DbValue) {
+        let name = Bytes::from(name.to_string());
+        let key = Key::new(name.clone(), Some(Bytes::from("value")), expires_at);
+        (name, DbValue::StringKey(key))
+    }
+
+    fn keyspace_with(expired: usize, volatile: usize, persistent: usize) -> Keyspace {
+        let mut keyspace = Keyspace::new();
+        let now = current_timestamp_ms();
+
+        for i in 0..expired {
+            let (name, value) = string_key(&format!("expired:{i}"), Some(now - 1));
+            keyspace.insert(name, value);
+        }
+        for i in 0..volatile {
+            let (name, value) = string_key(&format!("volatile:{i}"), Some(now + 60_000));
+            keyspace.insert(name, value);
+        }
+        for i in 0..persistent {
+            let (name, value) = string_key(&format!("persistent:{i}"), None);
+            keyspace.insert(name, value);
+        }
+
+        keyspace
+    }
+
+    #[test]
+    fn test_expires_index_tracks_ttls() {
+        let mut keyspace = keyspace_with(0, 2, 3);
+        assert_eq!(keyspace.len(), 5);
+        assert_eq!(keyspace.expires_len(), 2);
+
+        let key = Bytes::from("persistent:0");
+        assert!(keyspace.set_expires_at(&key, Some(current_timestamp_ms() + 1000)));
+        assert!(!keyspace.set_expires_at(&Bytes::from("missing"), Some(0)));
+        assert_eq!(keyspace.expires_len(), 3);
+
+        keyspace.swap_remove(&key);
+        assert_eq!(keyspace.expires_len(), 2);
+
+        keyspace.clear();
+        assert!(keyspace.is_empty());
+        assert_eq!(keyspace.expires_len(), 0);
+    }
+
+    #[test]
+    fn test_expire_sample() {
+        let mut keyspace = keyspace_with(30, 10, 5);
+
+        // Sampling resumes where the previous call stopped, so two calls
+        // cover every key of the index
+        let (first_sampled, first_expired) = keyspace.expire_sample(20);
+        let (sampled, expired) = keyspace.expire_sample(20);
+        assert_eq!((first_sampled + sampled, first_expired + expired), (40, 30));
+
+        assert_eq!(keyspace.len(), 15);
+        assert_eq!(keyspace.expires_len(), 10);
+        assert!(keyspace.keys().all(|key| !key.starts_with(b"expired")));
+
+        // Keys that lost their TTL are dropped from the index
+        keyspace.set_expires_at(&Bytes::from("volatile:0"), None);
+        let (sampled, expired) = keyspace.expire_sample(20);
+        assert_eq!((sampled, expired), (10, 0));
+        assert_eq!(keyspace.expires_len(), 9);
+        assert_eq!(keyspace.len(), 15);
+    }
+
+    #[test]
+    fn test_expire_sample_deletes_fields() {
+        let mut keyspace = Keyspace::new();
+        let key = Bytes::from("h");
+        let (a, b) = (Bytes::from("a"), Bytes::from("b"));
+        let fields =
+            IndexMap::from([(a.clone(), Bytes::from("1")), (b.clone(), Bytes::from("2"))]);
+        let hash = KeyHash::new(key.clone(), HashData::from(fields), None);
+        keyspace.insert(key.clone(), DbValue::HashKey(hash));
+
+        let now = current_timestamp_ms();
+        assert!(keyspace.set_field_expires_at(&key, &b, Some(now + 60_000)));
+        assert!(keyspace.set_field_expires_at(&key, &a, Some(now - 1)));
+        assert_eq!(keyspace.expires_len(), 1);
+
+        // The hash stays in the index while a field has a TTL
+        assert_eq!(keyspace.expire_sample(20), (1, 1));
+        assert_eq!(keyspace.expire_sample(20), (1, 0));
+        match keyspace.get(&key) {
+            Some(DbValue::HashKey(hash)) => {
+                assert_eq!(hash.data.keys().collect::<Vec<_>>(), vec!["b"]);
+            }
+            _ => panic!("The hash should still exist"),
+        }
+        assert_eq!(keyspace.expires_len(), 1);
+
+        // And goes with its last field
+        assert!(keyspace.set_field_expires_at(&key, &b, Some(now - 1)));
+        assert!(!keyspace.contains_key(&key));
+        assert_eq!(keyspace.expire_sample(20), (1, 1));
+        assert_eq!(keyspace.len(), 0);
+        assert_eq!(keyspace.expires_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_active_expire_cycle() {
+        let db: Db = Arc::new(RwLock::new(keyspace_with(500, 10, 10)));
+        let state = Arc::new(State::with_databases(vec![db.clone()]));
+
+        let cycle = tokio::spawn(active_expire_cycle(state, 100));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        cycle.abort();
+
+        let db_read = db.read().await;
+        assert_eq!(db_read.len(), 20);
+        assert_eq!(db_read.expires_len(), 10);
+    }
+
+    #[test]
+    fn test_lookups_hide_expired_keys() {
+        let mut keyspace = keyspace_with(1, 1, 0);
+        let expired = Bytes::from("expired:0");
+
+        assert!(keyspace.get(&expired).is_none());
+        assert!(!keyspace.contains_key(&expired));
+        assert_eq!(keyspace.keys().collect::<Vec<_>>(), vec!["volatile:0"]);
+        assert_eq!(keyspace.len(), 2);
+
+        // Writers delete the expired key
+        assert!(keyspace.get_mut(&expired).is_none());
+        assert_eq!(keyspace.len(), 1);
+        assert_eq!(keyspace.expires_len(), 1);
+
+        let (name, value) = string_key("gone", Some(current_timestamp_ms() - 1));
+        keyspace.insert(name.clone(), value);
+        assert!(keyspace.swap_remove(&name).is_none());
+        assert!(!keyspace.set_expires_at(&name, None));
+    }
+
+    #[tokio::test]
+    async fn test_commands_ignore_expired_keys() {
+        let db: Db = Arc::new(RwLock::new(Keyspace::new()));
+        let state = State::with_databases(vec![db.clone()]);
+        let mut client = Client::new();
+
+        let mut run = async |args: &[&str]| {
+            let argv = args
+                .iter()
+                .map(|arg| Bytes::from(arg.to_string()))
+                .collect();
+            process_command(argv, &state, &mut client, true)
+                .await
+                .map_or_else(|e| e.to_string(), |response| response.to_string())
+        };
+
+        for command in [
+            &["SET", "string", "1"][..],
+            &["RPUSH", "list", "a", "b"],
+            &["SADD", "set", "a"],
+            &["HSET", "hash", "f", "v"],
+        ] {
+            run(command).await;
+        }
+
+        {
+            let mut db_write = db.write().await;
+            for key in ["string", "list", "set", "hash"] {
+                db_write.set_expires_at(&Bytes::from(key), Some(current_timestamp_ms() - 1));
+            }
+        }
+
+        assert_eq!(run(&["GET", "string"]).await, "$-1\r\n");
+        assert_eq!(
+            run(&["EXISTS", "string", "list", "set", "hash"]).await,
+            ":0\r\n"
+        );
+        assert_eq!(run(&["KEYS", "*"]).await, "*0\r\n");
+        assert_eq!(run(&["LRANGE", "list", "0", "-1"]).await, "*0\r\n");
+        assert_eq!(run(&["SMEMBERS", "set"]).await, "*0\r\n");
+        assert_eq!(run(&["HGET", "hash", "f"]).await, "$-1\r\n");
+        assert_eq!(run(&["TTL", "hash"]).await, ":-2\r\n");
+
+        // Writes start from an empty key, without the old TTL
+        assert_eq!(run(&["INCR", "string"]).await, ":1\r\n");
+        assert_eq!(run(&["LPUSH", "list", "c"]).await, ":1\r\n");
+        assert_eq!(run(&["TTL", "list"]).await, ":-1\r\n");
+        assert_eq!(run(&["DEL", "set", "hash"]).await, ":0\r\n");
+        assert_eq!(db.read().await.len(), 2);
+    }
+}
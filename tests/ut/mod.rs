@@ -1 +1,6 @@
 pub mod commands;
+pub mod glob;
+pub mod keyspace;
+pub mod notify;
+pub mod resp;
+pub mod response;
@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use crate::ut::commands::run;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use zyst::client::Client;
+    use zyst::database::active_expire_cycle;
+    use zyst::notify::NotifyFlags;
+    use zyst::state::State;
+
+    // Messages received so far, as (channel, message) pairs
+    fn received(client: &mut Client) -> Vec<(String, String)> {
+        let mut messages = Vec::new();
+        while let Ok(message) = client.messages.try_recv() {
+            let text = message.to_string();
+            let parts: Vec<&str> = text.split("\r\n").collect();
+            // pmessage, pattern, channel and message
+            messages.push((parts[6].to_string(), parts[8].to_string()));
+        }
+        messages
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(channel, message)| (channel.to_string(), message.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_and_format_flags() {
+        let flags = NotifyFlags::parse(b"KEA").unwrap();
+        assert!(flags.contains(NotifyFlags::KEYSPACE | NotifyFlags::EXPIRED));
+        assert!(!flags.contains(NotifyFlags::KEY_MISS));
+        assert_eq!(flags.to_string(), "AKE");
+
+        assert_eq!(NotifyFlags::parse(b"Elg$").unwrap().to_string(), "g$lE");
+        assert_eq!(NotifyFlags::parse(b"mnKx").unwrap().to_string(), "xnKm");
+        assert_eq!(NotifyFlags::parse(b"").unwrap(), NotifyFlags::default());
+        assert_eq!(NotifyFlags::parse(b"KEq"), None);
+    }
+
+    #[tokio::test]
+    async fn test_config_get_and_set() {
+        let state = State::new(16);
+        let mut client = Client::new();
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["CONFIG", "SET", "notify-keyspace-events", "Kx"]
+            )
+            .await,
+            "+OK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CONFIG", "GET", "notify-*"]).await,
+            "*2\r\n$22\r\nnotify-keyspace-events\r\n$2\r\nxK\r\n"
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CONFIG", "GET", "databases"]).await,
+            "*2\r\n$9\r\ndatabases\r\n$2\r\n16\r\n"
+        );
+
+        assert_eq!(
+            run(
+                &state,
+                &mut client,
+                &["CONFIG", "SET", "notify-keyspace-events", "Kq"]
+            )
+            .await,
+            "ERR CONFIG SET failed (possibly related to argument 'notify-keyspace-events') - \
+             Invalid event class character. Use 'Ag$lshzxeKEtmn'."
+        );
+        assert_eq!(
+            run(&state, &mut client, &["CONFIG", "SET", "maxmemory", "1"]).await,
+            "ERR Unknown option or number of arguments for CONFIG SET - 'maxmemory'"
+        );
+        assert_eq!(state.keyspace_events().to_string(), "xK");
+    }
+
+    #[tokio::test]
+    async fn test_write_commands_publish_events() {
+        let state = State::new(16);
+        let mut listener = Client::new();
+        let mut client = Client::new();
+
+        run(&state, &mut listener, &["PSUBSCRIBE", "__key*__:*"]).await;
+
+        // Nothing is published until enabled
+        run(&state, &mut client, &["SET", "a", "1"]).await;
+        assert!(received(&mut listener).is_empty());
+
+        state.set_keyspace_events(NotifyFlags::parse(b"KEA").unwrap());
+        run(&state, &mut client, &["SET", "a", "2", "EX", "100"]).await;
+        run(&state, &mut client, &["INCR", "b"]).await;
+        assert_eq!(
+            received(&mut listener),
+            pairs(&[
+                ("__keyspace@0__:a", "set"),
+                ("__keyevent@0__:set", "a"),
+                ("__keyspace@0__:a", "expire"),
+                ("__keyevent@0__:expire", "a"),
+                ("__keyspace@0__:b", "incrby"),
+                ("__keyevent@0__:incrby", "b"),
+            ])
+        );
+
+        // Only keyevent channels, only list and generic events
+        state.set_keyspace_events(NotifyFlags::parse(b"Elgn").unwrap());
+        run(&state, &mut client, &["RPUSH", "l", "x"]).await;
+        run(&state, &mut client, &["LPOP", "l"]).await;
+        run(&state, &mut client, &["SADD", "s", "x"]).await;
+        run(&state, &mut client, &["RENAME", "a", "c"]).await;
+        run(&state, &mut client, &["DEL", "b", "c", "missing"]).await;
+        assert_eq!(
+            received(&mut listener),
+            pairs(&[
+                ("__keyevent@0__:new", "l"),
+                ("__keyevent@0__:rpush", "l"),
+                ("__keyevent@0__:lpop", "l"),
+                ("__keyevent@0__:del", "l"),
+                ("__keyevent@0__:new", "s"),
+                ("__keyevent@0__:new", "c"),
+                ("__keyevent@0__:rename_from", "a"),
+                ("__keyevent@0__:rename_to", "c"),
+                ("__keyevent@0__:del", "b"),
+                ("__keyevent@0__:del", "c"),
+            ])
+        );
+
+        // Events land in the channels of the database they happen in
+        state.set_keyspace_events(NotifyFlags::parse(b"Eghsm").unwrap());
+        run(&state, &mut client, &["MOVE", "s", "3"]).await;
+        run(&state, &mut client, &["SELECT", "3"]).await;
+        run(&state, &mut client, &["SREM", "s", "x"]).await;
+        run(&state, &mut client, &["HSET", "h", "f", "v"]).await;
+        run(&state, &mut client, &["HGET", "nothing", "f"]).await;
+        assert_eq!(
+            received(&mut listener),
+            pairs(&[
+                ("__keyevent@0__:move_from", "s"),
+                ("__keyevent@3__:move_to", "s"),
+                ("__keyevent@3__:srem", "s"),
+                ("__keyevent@3__:del", "s"),
+                ("__keyevent@3__:hset", "h"),
+                ("__keyevent@3__:keymiss", "nothing"),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_keys_publish_events() {
+        let state = Arc::new(State::new(16));
+        let mut listener = Client::new();
+        let mut client = Client::new();
+
+        state.set_keyspace_events(NotifyFlags::parse(b"Ex").unwrap());
+        run(
+            &state,
+            &mut listener,
+            &["SUBSCRIBE", "__keyevent@0__:expired"],
+        )
+        .await;
+
+        // Deleted when a command finds it expired
+        run(&state, &mut client, &["SET", "lazy", "1", "PX", "10"]).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        run(&state, &mut client, &["INCR", "lazy"]).await;
+
+        // Deleted by the active expire cycle
+        run(&state, &mut client, &["SET", "active", "1", "PX", "10"]).await;
+        let cycle = tokio::spawn(active_expire_cycle(state.clone(), 100));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cycle.abort();
+
+        let mut keys = Vec::new();
+        while let Ok(message) = listener.messages.try_recv() {
+            let text = message.to_string();
+            keys.push(text.split("\r\n").nth(6).unwrap().to_string());
+        }
+        assert_eq!(keys, vec!["lazy", "active"]);
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use zyst::errors::ZystError;
+    use zyst::resp::{encode_command, RespDecoder};
+
+    fn decode_all(decoder: &mut RespDecoder, buf: &mut BytesMut) -> Vec<Vec<String>> {
+        let mut commands = vec![];
+
+        while let Some(frame) = decoder.decode(buf).unwrap() {
+            commands.push(
+                frame
+                    .iter()
+                    .map(|arg| String::from_utf8_lossy(arg).to_string())
+                    .collect(),
+            );
+        }
+
+        commands
+    }
+
+    #[test]
+    fn test_decode_pipelined_commands() {
+        let mut decoder = RespDecoder::default();
+        let mut buf = BytesMut::from(
+            "*3\r\n$3\r\nSET\r\n$4\r\nname\r\n$5\r\nAlice\r\n*2\r\n$3\r\nGET\r\n$4\r\nname\r\n",
+        );
+
+        let commands = decode_all(&mut decoder, &mut buf);
+
+        assert_eq!(
+            commands,
+            vec![vec!["SET", "name", "Alice"], vec!["GET", "name"]]
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_partial_frame() {
+        let mut decoder = RespDecoder::default();
+        let raw = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+        let mut buf = BytesMut::new();
+
+        // Feed the frame byte by byte, it must only be returned once complete
+        for (i, byte) in raw.iter().enumerate() {
+            buf.extend_from_slice(&[*byte]);
+            let frame = decoder.decode(&mut buf).unwrap();
+
+            if i < raw.len() - 1 {
+                assert!(frame.is_none());
+            } else {
+                assert_eq!(frame.unwrap().len(), 3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_resumes_partial_frame() {
+        let mut decoder = RespDecoder::default();
+        let mut buf = BytesMut::from("*3\r\n$3\r\nSET\r\n$3\r\nke");
+
+        assert!(decoder.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"y\r\n$1\r\nv\r\n*1\r\n$4\r\nPING\r\n");
+        assert_eq!(
+            decode_all(&mut decoder, &mut buf),
+            vec![vec!["SET", "key", "v"], vec!["PING"]]
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_binary_safe_value() {
+        let mut decoder = RespDecoder::default();
+        let mut buf = BytesMut::from(&b"*2\r\n$4\r\nECHO\r\n$6\r\na\r\n\xff\x00b\r\n"[..]);
+
+        let frame = decoder.decode(&mut buf).unwrap().unwrap();
+
+        assert_eq!(&frame[1][..], b"a\r\n\xff\x00b");
+    }
+
+    #[test]
+    fn test_decode_large_value() {
+        let mut decoder = RespDecoder::default();
+        let value = "x".repeat(10_000);
+        let mut buf = BytesMut::from(
+            format!(
+                "*3\r\n$3\r\nSET\r\n$3\r\nbig\r\n${}\r\n{}\r\n",
+                value.len(),
+                value
+            )
+            .as_str(),
+        );
+
+        let frame = decoder.decode(&mut buf).unwrap().unwrap();
+
+        assert_eq!(frame[2].len(), 10_000);
+    }
+
+    #[test]
+    fn test_decode_inline_command() {
+        let mut decoder = RespDecoder::default();
+        let mut buf = BytesMut::from("SET  name Alice\r\nPING\n");
+
+        let commands = decode_all(&mut decoder, &mut buf);
+
+        assert_eq!(commands, vec![vec!["SET", "name", "Alice"], vec!["PING"]]);
+    }
+
+    #[test]
+    fn test_decode_malformed_frames() {
+        let mut decoder = RespDecoder::default();
+
+        let mut buf = BytesMut::from("*abc\r\n");
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(ZystError::InvalidArrayLength)
+        ));
+
+        let mut buf = BytesMut::from("*1\r\n+PING\r\n");
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(ZystError::InvalidBulkStringPrefix)
+        ));
+
+        let mut buf = BytesMut::from("*1\r\n$4\r\nPINGXX\r\n");
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(ZystError::InvalidBulkLength)
+        ));
+    }
+
+    #[test]
+    fn test_decode_max_bulk_len() {
+        let mut decoder = RespDecoder::new(4);
+
+        let mut buf = BytesMut::from("*1\r\n$4\r\nPING\r\n");
+        assert!(decoder.decode(&mut buf).unwrap().is_some());
+
+        let mut buf = BytesMut::from("*1\r\n$5\r\n");
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(ZystError::InvalidBulkLength)
+        ));
+    }
+
+    #[test]
+    fn test_encode_command_round_trip() {
+        let mut decoder = RespDecoder::default();
+        let args: [&[u8]; 3] = [b"SET", b"blob", b"\x00\r\n\xff"];
+        let mut buf = BytesMut::new();
+
+        encode_command(&mut buf, &args);
+        let frame = decoder.decode(&mut buf).unwrap().unwrap();
+
+        assert_eq!(frame, args.to_vec());
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use bytes::BytesMut;
+    use zyst::errors::ZystError;
+    use zyst::response::{Protocol, ZystResponse};
+
+    fn encode(response: ZystResponse) -> String {
+        encode_with(response, Protocol::Resp2)
+    }
+
+    fn encode_with(response: ZystResponse, protocol: Protocol) -> String {
+        let mut buf = BytesMut::new();
+        response.encode(&mut buf, protocol);
+        String::from_utf8_lossy(&buf).to_string()
+    }
+
+    fn bulk(value: &str) -> ZystResponse {
+        ZystResponse::BulkString(Bytes::from(value.to_string()))
+    }
+
+    #[test]
+    fn test_encode_scalars() {
+        assert_eq!(encode(ZystResponse::Ok), "+OK\r\n");
+        assert_eq!(encode(ZystResponse::Int(5)), ":5\r\n");
+        assert_eq!(encode(ZystResponse::Int(-12)), ":-12\r\n");
+        assert_eq!(
+            encode(ZystResponse::SimpleString("PONG".to_string())),
+            "+PONG\r\n"
+        );
+        assert_eq!(encode(ZystResponse::Nil), "$-1\r\n");
+        assert_eq!(encode(ZystResponse::NilArray), "*-1\r\n");
+        assert_eq!(encode(ZystResponse::EmptyArray), "*0\r\n");
+    }
+
+    #[test]
+    fn test_encode_bulk_string() {
+        assert_eq!(
+            encode(ZystResponse::BulkString(Bytes::from("a\r\nb"))),
+            "$4\r\na\r\nb\r\n"
+        );
+        assert_eq!(encode(ZystResponse::BulkString(Bytes::new())), "$0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_encode_doubles() {
+        assert_eq!(encode(ZystResponse::Double(1.5)), "$3\r\n1.5\r\n");
+        assert_eq!(encode(ZystResponse::Double(3.0)), "$1\r\n3\r\n");
+        assert_eq!(encode(ZystResponse::Double(f64::INFINITY)), "$3\r\ninf\r\n");
+        assert_eq!(
+            encode(ZystResponse::Double(f64::NEG_INFINITY)),
+            "$4\r\n-inf\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_nested_arrays() {
+        let response = ZystResponse::Array(vec![
+            ZystResponse::Int(1),
+            ZystResponse::Nil,
+            ZystResponse::List(vec![Bytes::from("foo"), Bytes::from("bar")]),
+        ]);
+
+        assert_eq!(
+            encode(response),
+            "*3\r\n:1\r\n$-1\r\n*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        assert_eq!(
+            encode(ZystResponse::Error(ZystError::WrongType)),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+        assert_eq!(
+            encode(ZystResponse::Error(ZystError::Custom(
+                "no code\r\nhere".to_string()
+            ))),
+            "-ERR no code  here\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_resp3_types() {
+        let resp3 = Protocol::Resp3;
+
+        assert_eq!(encode_with(ZystResponse::Nil, resp3), "_\r\n");
+        assert_eq!(encode_with(ZystResponse::NilArray, resp3), "_\r\n");
+        assert_eq!(encode_with(ZystResponse::Double(1.5), resp3), ",1.5\r\n");
+        assert_eq!(encode_with(ZystResponse::Boolean(true), resp3), "#t\r\n");
+        assert_eq!(encode_with(ZystResponse::Boolean(false), resp3), "#f\r\n");
+        assert_eq!(
+            encode_with(
+                ZystResponse::BigNumber("1234567890123456789012".to_string()),
+                resp3
+            ),
+            "(1234567890123456789012\r\n"
+        );
+        assert_eq!(
+            encode_with(
+                ZystResponse::Map(vec![(bulk("name"), bulk("Alice"))]),
+                resp3
+            ),
+            "%1\r\n$4\r\nname\r\n$5\r\nAlice\r\n"
+        );
+        assert_eq!(
+            encode_with(ZystResponse::Set(vec![bulk("a")]), resp3),
+            "~1\r\n$1\r\na\r\n"
+        );
+        assert_eq!(
+            encode_with(ZystResponse::Push(vec![bulk("message")]), resp3),
+            ">1\r\n$7\r\nmessage\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_resp3_types_downgraded() {
+        assert_eq!(
+            encode(ZystResponse::Map(vec![(bulk("name"), bulk("Alice"))])),
+            "*2\r\n$4\r\nname\r\n$5\r\nAlice\r\n"
+        );
+        assert_eq!(
+            encode(ZystResponse::Set(vec![bulk("a")])),
+            "*1\r\n$1\r\na\r\n"
+        );
+        assert_eq!(
+            encode(ZystResponse::Push(vec![bulk("message")])),
+            "*1\r\n$7\r\nmessage\r\n"
+        );
+        assert_eq!(encode(ZystResponse::Boolean(true)), ":1\r\n");
+        assert_eq!(
+            encode(ZystResponse::BigNumber("12".to_string())),
+            "$2\r\n12\r\n"
+        );
+    }
+}